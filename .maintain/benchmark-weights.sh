@@ -0,0 +1,40 @@
+#!/bin/sh
+#
+# Regenerate the weights of the bridge pallets, on the reference hardware.
+#
+# cargo build --release --features runtime-benchmarks
+# .maintain/benchmark-weights.sh
+#
+
+set -xe
+
+BIN_PATH=$(dirname $(readlink -f $0))
+WORK_PATH=${BIN_PATH}/..
+DARWINIA=${WORK_PATH}/target/release/darwinia
+
+benchmark() {
+	${DARWINIA} benchmark \
+		--chain $1-dev \
+		--execution wasm \
+		--wasm-execution compiled \
+		--pallet $2 \
+		--extrinsic '*' \
+		--steps 50 \
+		--repeat 20 \
+		--raw \
+		--template ${BIN_PATH}/frame-weight-template.hbs \
+		--output ${WORK_PATH}/runtime/$1/src/weights/$2.rs
+}
+
+benchmark crab darwinia_crab_issuing
+
+for PALLET in \
+	darwinia_crab_backing \
+	darwinia_ethereum_backing \
+	darwinia_ethereum_relay \
+	darwinia_relay_authorities \
+	darwinia_relayer_game \
+	darwinia_tron_backing
+do
+	benchmark darwinia ${PALLET}
+done
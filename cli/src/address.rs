@@ -0,0 +1,198 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! Conversion between the SS58, hex and DVM H160 addresses of an account.
+
+// --- crates ---
+use serde_json::{json, Value};
+use structopt::StructOpt;
+// --- substrate ---
+use frame_support::traits::Get;
+use sp_core::{
+	bytes,
+	crypto::{Ss58AddressFormat, Ss58Codec},
+	H160,
+};
+use sp_runtime::traits::AccountIdConversion;
+// --- darwinia ---
+use crate::cli::parse_runtime_variant;
+use darwinia_primitives::{
+	address::{self, EthereumAddress, TronAddress},
+	AccountId,
+};
+use darwinia_service::service::{crab::crab_runtime, darwinia::darwinia_runtime, RuntimeVariant};
+
+/// The `address` command used to convert addresses and list the module accounts.
+#[derive(Debug, StructOpt)]
+pub struct AddressCmd {
+	#[allow(missing_docs)]
+	#[structopt(subcommand)]
+	pub command: AddressSubcommand,
+}
+
+/// The address operations.
+#[derive(Debug, StructOpt)]
+pub enum AddressSubcommand {
+	/// Print every form of an address.
+	///
+	/// Accepts an SS58 address of any format, a 32 bytes hex account, a 20 bytes hex Ethereum
+	/// address or a Tron address.
+	Convert {
+		/// The address to convert.
+		address: String,
+	},
+	/// List the module accounts of a chain.
+	ModuleAccounts {
+		/// The chain of the module accounts, `crab` or `darwinia`.
+		#[structopt(long, value_name = "CHAIN", parse(try_from_str = parse_runtime_variant))]
+		chain: RuntimeVariant,
+	},
+}
+
+impl AddressCmd {
+	/// Run the address command.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let output = match &self.command {
+			AddressSubcommand::Convert { address } => convert(address)?,
+			AddressSubcommand::ModuleAccounts { chain } => module_accounts(*chain),
+		};
+
+		println!(
+			"{}",
+			serde_json::to_string_pretty(&output).map_err(|e| format!("{}", e))?
+		);
+
+		Ok(())
+	}
+}
+
+fn convert(input: &str) -> Result<Value, String> {
+	let input = input.trim();
+
+	if let Ok((account, format)) = AccountId::from_ss58check_with_version(input) {
+		return Ok(json!({
+			"input": format!("SS58 ({})", u8::from(format)),
+			"account": account_json(&account),
+		}));
+	}
+	if input.starts_with("0x") && input.len() == 66 {
+		let raw = bytes::from_hex(input).map_err(|e| format!("Invalid hex account: {}", e))?;
+		let mut account = [0; 32];
+
+		account.copy_from_slice(&raw);
+
+		return Ok(json!({
+			"input": "hex account",
+			"account": account_json(&account.into()),
+		}));
+	}
+	if let Ok(ethereum) = input.parse::<EthereumAddress>() {
+		return Ok(json!({
+			"input": "Ethereum address",
+			"address": h160_json(ethereum.0),
+		}));
+	}
+	if let Ok(tron) = input.parse::<TronAddress>() {
+		return Ok(json!({
+			"input": "Tron address",
+			"address": h160_json(tron.0),
+		}));
+	}
+
+	Err(format!("Unrecognized address `{}`", input))
+}
+
+fn account_json(account: &AccountId) -> Value {
+	json!({
+		"hex": bytes::to_hex(account.as_ref(), false),
+		"ss58": ss58_json(account),
+		// Set if the account is the DVM mapping of an H160, by `ConcatAddressMapping`
+		"dvm_address": address::dvm_address_of(account)
+			.map(|address| EthereumAddress(address).to_string()),
+		// The address the account acts as in the DVM, by `EnsureAddressTruncated`
+		"truncated_address": EthereumAddress(address::truncated_address_of(account)).to_string(),
+	})
+}
+
+fn h160_json(address: H160) -> Value {
+	let dvm_account = address::dvm_account_id(address);
+
+	json!({
+		"ethereum": EthereumAddress(address).to_string(),
+		"tron": TronAddress(address).to_string(),
+		// The account of the address in the DVM, by `ConcatAddressMapping`
+		"dvm_account": {
+			"hex": bytes::to_hex(dvm_account.as_ref(), false),
+			"ss58": ss58_json(&dvm_account),
+		},
+	})
+}
+
+fn ss58_json(account: &AccountId) -> Value {
+	json!({
+		"darwinia": account.to_ss58check_with_version(Ss58AddressFormat::DarwiniaAccount),
+		"substrate": account.to_ss58check_with_version(Ss58AddressFormat::SubstrateAccount),
+	})
+}
+
+fn module_accounts(chain: RuntimeVariant) -> Value {
+	let module_ids = match chain {
+		RuntimeVariant::Crab => vec![
+			("Treasury", crab_runtime::TreasuryModuleId::get()),
+			("Society", crab_runtime::SocietyModuleId::get()),
+			("Staking", crab_runtime::StakingModuleId::get()),
+			("Claims", crab_runtime::ClaimsModuleId::get()),
+			("CrabIssuing", crab_runtime::CrabIssuingModuleId::get()),
+		],
+		RuntimeVariant::Darwinia => vec![
+			("Treasury", darwinia_runtime::TreasuryModuleId::get()),
+			("Society", darwinia_runtime::SocietyModuleId::get()),
+			("Staking", darwinia_runtime::StakingModuleId::get()),
+			(
+				"EthereumRelay",
+				darwinia_runtime::EthereumRelayModuleId::get(),
+			),
+			(
+				"EthereumBacking",
+				darwinia_runtime::EthereumBackingModuleId::get(),
+			),
+			(
+				"EthereumBackingFee",
+				darwinia_runtime::EthereumBackingFeeModuleId::get(),
+			),
+			("CrabBacking", darwinia_runtime::CrabBackingModuleId::get()),
+			("TronBacking", darwinia_runtime::TronBackingModuleId::get()),
+		],
+	};
+
+	Value::Array(
+		module_ids
+			.into_iter()
+			.map(|(pallet, module_id)| {
+				let account: AccountId = module_id.into_account();
+
+				json!({
+					"pallet": pallet,
+					"id": String::from_utf8_lossy(&module_id.0),
+					"hex": bytes::to_hex(account.as_ref(), false),
+					"ss58": account.to_ss58check_with_version(chain.ss58_format()),
+				})
+			})
+			.collect(),
+	)
+}
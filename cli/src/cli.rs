@@ -18,10 +18,26 @@
 
 //! Darwinia CLI library.
 
+// --- std ---
+use std::{net::SocketAddr, time::Duration};
 // --- crates ---
 use structopt::StructOpt;
 // --- substrate ---
-use sc_cli::{KeySubcommand, SignCmd, VanityCmd, VerifyCmd};
+use sc_cli::{
+	CliConfiguration, KeySubcommand, PruningParams, SharedParams, SignCmd, VanityCmd, VerifyCmd,
+};
+// --- darwinia ---
+use crate::{
+	address::AddressCmd, decode::DecodeCmd, dvm::DvmCmd, export_checkpoint::ExportCheckpointCmd,
+	fork_spec::ForkSpecCmd, inspect::InspectCmd, session_keys::SessionKeysCmd,
+	testnet_spec::TestnetSpecCmd, upgrade_dry_run::UpgradeDryRunCmd,
+	verify_genesis::VerifyGenesisCmd,
+};
+use darwinia_primitives::BlockNumber;
+use darwinia_service::{
+	chain_spec::GrandpaVotingRule,
+	service::{dvm::DvmConfig, health::HealthConfig, sealing::Sealing, RuntimeVariant},
+};
 
 #[allow(missing_docs)]
 #[derive(Debug, StructOpt)]
@@ -46,8 +62,8 @@ pub struct RunCmd {
 	#[structopt(flatten)]
 	pub base: sc_cli::RunCmd,
 
-	/// Force using Crab native runtime.
-	#[structopt(long = "force-crab")]
+	/// Deprecated, the native runtime is now detected from the genesis of the chain spec.
+	#[structopt(long = "force-crab", hidden = true)]
 	pub force_crab: bool,
 
 	/// Disable the authority discovery module on validator or sentry nodes.
@@ -66,6 +82,146 @@ pub struct RunCmd {
 	///     to a subset.
 	#[structopt(long = "disable-authority-discovery")]
 	pub authority_discovery_disabled: bool,
+
+	/// Restrict the GRANDPA votes further than the defaults, replacing the voting rules of the
+	/// chain spec. Can be given several times.
+	///
+	/// `behind-best-by=<N>`: vote on blocks at least N blocks behind the best block.
+	///
+	/// `ethereum-relay-confirmed`: never vote past the last block which confirmed an Ethereum
+	/// relay header parcel, Darwinia only.
+	#[structopt(long = "grandpa-voting-rule", value_name = "RULE")]
+	pub grandpa_voting_rules: Vec<GrandpaVotingRule>,
+
+	/// Seal the blocks of a Crab development chain without BABE and GRANDPA: `instant` on each
+	/// new transaction, `manual` through the `engine_createBlock` and `engine_finalizeBlock`
+	/// RPCs, or every `<ms>` milliseconds with `interval:<ms>`.
+	#[structopt(long = "sealing", value_name = "SEALING")]
+	pub sealing: Option<Sealing>,
+
+	/// Index the transfers, staking rewards and slashes, treasury spends, bridge locks and redeems
+	/// and DVM transactions of the finalized blocks into an embedded SQLite database, served by
+	/// the `indexer_*` RPCs. Only the blocks whose state is kept are indexed, see `--pruning`.
+	#[structopt(long = "indexer")]
+	pub indexer: bool,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub dvm: DvmParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub health: HealthParams,
+}
+
+/// The tuning of the DVM mapping database and of its maintenance tasks.
+#[derive(Debug, StructOpt)]
+pub struct DvmParams {
+	/// The cache size of the DVM mapping database, in MiB. Defaults to 0.
+	///
+	/// The mapping database is always on RocksDB, whatever `--database`, and keeps the mappings
+	/// of every block.
+	#[structopt(long = "dvm-db-cache", value_name = "MiB")]
+	pub db_cache_size: Option<usize>,
+
+	/// The number of blocks an imported Ethereum transaction stays in the pending transactions.
+	/// Defaults to 5.
+	#[structopt(long = "dvm-transaction-retain", value_name = "BLOCKS")]
+	pub transaction_retain_threshold: Option<u64>,
+
+	/// The number of blocks an eth filter stays in the filter pool. Defaults to 100.
+	#[structopt(long = "dvm-filter-retain", value_name = "BLOCKS")]
+	pub filter_retain_threshold: Option<u64>,
+
+	/// The maximum number of eth filters in the filter pool. Defaults to 500.
+	#[structopt(long = "dvm-max-stored-filters", value_name = "COUNT")]
+	pub max_stored_filters: Option<usize>,
+
+	/// The longest time between two runs of the DVM mapping sync, without any imported block.
+	/// Defaults to 6.
+	#[structopt(long = "dvm-sync-interval", value_name = "SECONDS")]
+	pub sync_interval: Option<u64>,
+}
+impl DvmParams {
+	/// The DVM config, the defaults completed with the given parameters.
+	pub fn dvm_config(&self) -> DvmConfig {
+		let default = DvmConfig::default();
+
+		DvmConfig {
+			db_cache_size: self.db_cache_size.unwrap_or(default.db_cache_size),
+			transaction_retain_threshold: self
+				.transaction_retain_threshold
+				.unwrap_or(default.transaction_retain_threshold),
+			filter_retain_threshold: self
+				.filter_retain_threshold
+				.unwrap_or(default.filter_retain_threshold),
+			max_stored_filters: self
+				.max_stored_filters
+				.unwrap_or(default.max_stored_filters),
+			sync_interval: self
+				.sync_interval
+				.map(Duration::from_secs)
+				.unwrap_or(default.sync_interval),
+		}
+	}
+}
+
+/// The `revert` command, which rebuilds the DVM mapping database of the reverted chain too.
+#[derive(Debug, StructOpt)]
+pub struct RevertCmd {
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub base: sc_cli::RevertCmd,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub dvm: DvmParams,
+}
+impl CliConfiguration for RevertCmd {
+	fn shared_params(&self) -> &SharedParams {
+		self.base.shared_params()
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		self.base.pruning_params()
+	}
+}
+
+/// The HTTP health endpoints, `/health`, `/ready` and `/validator`.
+#[derive(Debug, StructOpt)]
+pub struct HealthParams {
+	/// Serve the health endpoints on this address, they are disabled by default.
+	#[structopt(long = "health-addr", value_name = "IP:PORT")]
+	pub listen_addr: Option<SocketAddr>,
+
+	/// `/ready` fails if the best block is more than this number of blocks behind the best block
+	/// seen from the peers. Defaults to 10.
+	#[structopt(long = "health-max-sync-lag", value_name = "BLOCKS")]
+	pub max_sync_lag: Option<BlockNumber>,
+
+	/// `/ready` fails with less connected peers. Defaults to 1.
+	#[structopt(long = "health-min-peers", value_name = "COUNT")]
+	pub min_peers: Option<usize>,
+
+	/// `/ready` fails if the finalized block is this number of blocks behind the best block or
+	/// more. Defaults to 50.
+	#[structopt(long = "health-max-finality-lag", value_name = "BLOCKS")]
+	pub max_finality_lag: Option<BlockNumber>,
+}
+impl HealthParams {
+	/// The health config, the defaults completed with the given parameters, if enabled.
+	pub fn health_config(&self) -> Option<HealthConfig> {
+		self.listen_addr.map(|listen_addr| {
+			let default = HealthConfig::new(listen_addr);
+
+			HealthConfig {
+				listen_addr,
+				max_sync_lag: self.max_sync_lag.unwrap_or(default.max_sync_lag),
+				min_peers: self.min_peers.unwrap_or(default.min_peers),
+				max_finality_lag: self.max_finality_lag.unwrap_or(default.max_finality_lag),
+			}
+		})
+	}
 }
 
 #[allow(missing_docs)]
@@ -90,7 +246,7 @@ pub enum Subcommand {
 	PurgeChain(sc_cli::PurgeChainCmd),
 
 	/// Revert the chain to a previous state.
-	Revert(sc_cli::RevertCmd),
+	Revert(RevertCmd),
 
 	/// Key management cli utilities
 	Key(KeySubcommand),
@@ -104,8 +260,55 @@ pub enum Subcommand {
 	/// Sign a message, with a given (secret) key.
 	Sign(SignCmd),
 
+	/// Convert between the SS58, hex and DVM H160 addresses, or list the module accounts.
+	Address(AddressCmd),
+
+	/// Decode SCALE encoded extrinsics, calls, events or storage entries of a chain.
+	Decode(DecodeCmd),
+
+	/// Print a block of the local database, with its extrinsics, events and DVM transactions.
+	Inspect(InspectCmd),
+
+	/// Generate the stash, controller and session keys of a genesis authority.
+	SessionKeys(SessionKeysCmd),
+
+	/// Build a raw chain spec of a local testnet from a TOML or JSON description.
+	TestnetSpec(TestnetSpecCmd),
+
+	/// Rewrite the state of a live chain into a raw chain spec of a local dev network.
+	ForkSpec(ForkSpecCmd),
+
+	/// Write the checkpoint of a finalized block of the local database, for the chain spec.
+	ExportCheckpoint(ExportCheckpointCmd),
+
+	/// Run the upgrade to a wasm runtime on the state of the local database, without writing to it.
+	UpgradeDryRun(UpgradeDryRunCmd),
+
+	/// Rebuild the genesis from its sources and compare it with the shipped chain spec.
+	VerifyGenesis(VerifyGenesisCmd),
+
+	/// Purge, rebuild or verify the DVM mapping database.
+	Dvm(DvmCmd),
+
+	/// The custom benchmark subcommand benchmarking runtime pallets.
+	#[cfg(feature = "runtime-benchmarks")]
+	#[structopt(name = "benchmark", about = "Benchmark runtime pallets.")]
+	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
+
 	/// Try some experimental command on the runtime. This includes migration and runtime-upgrade
 	/// testing.
 	#[cfg(feature = "try-runtime")]
 	TryRuntime(try_runtime_cli::TryRuntimeCmd),
 }
+
+/// Parse a `--chain` argument of the offline subcommands into the runtime it refers to.
+pub fn parse_runtime_variant(chain: &str) -> Result<RuntimeVariant, String> {
+	match chain.to_lowercase().as_ref() {
+		"crab" => Ok(RuntimeVariant::Crab),
+		"darwinia" => Ok(RuntimeVariant::Darwinia),
+		chain => Err(format!(
+			"Unknown chain `{}`, expected `crab` or `darwinia`",
+			chain
+		)),
+	}
+}
@@ -19,22 +19,27 @@
 // --- std ---
 use std::path::PathBuf;
 // --- crates ---
-use log::info;
+use log::{info, warn};
 // --- substrate ---
 use sc_cli::{Role, RunCmd, RuntimeVersion, SubstrateCli};
-use sp_core::crypto::Ss58AddressFormat;
+use sc_service::ChainType;
+use sp_api::ProvideRuntimeApi;
 // --- darwinia ---
-use crate::cli::{Cli, Subcommand};
+use crate::{
+	cli::{Cli, Subcommand},
+	dvm::DvmCmd,
+};
 use darwinia_cli::{Configuration, DarwiniaCli};
 use darwinia_service::{
 	chain_spec,
 	service::{
+		checkpoint,
 		crab::{self, crab_runtime, CrabExecutor},
 		darwinia::{self, darwinia_runtime, DarwiniaExecutor},
-		IdentifyVariant,
+		dvm, IdentifyVariant, RuntimeVariant,
 	},
-	CrabChainSpec, DarwiniaChainSpec,
 };
+use dvm_rpc_runtime_api::EthereumRuntimeRPCApi;
 
 impl SubstrateCli for Cli {
 	fn impl_name() -> String {
@@ -66,12 +71,9 @@ impl SubstrateCli for Cli {
 	}
 
 	fn native_runtime_version(spec: &Box<dyn sc_service::ChainSpec>) -> &'static RuntimeVersion {
-		if spec.is_crab() {
-			&crab_runtime::VERSION
-		} else if spec.is_darwinia() {
-			&darwinia_runtime::VERSION
-		} else {
-			&darwinia_runtime::VERSION
+		match spec.variant() {
+			Ok(RuntimeVariant::Crab) => &crab_runtime::VERSION,
+			_ => &darwinia_runtime::VERSION,
 		}
 	}
 
@@ -94,12 +96,7 @@ impl SubstrateCli for Cli {
 			"darwinia" => Box::new(chain_spec::darwinia_config()?),
 			"darwinia-dev" | "dev" => Box::new(chain_spec::darwinia_development_config()),
 			"darwinia-genesis" => Box::new(chain_spec::darwinia_build_spec_config()),
-			path if self.run.force_crab => Box::new(CrabChainSpec::from_json_file(
-				std::path::PathBuf::from(path),
-			)?),
-			path => Box::new(DarwiniaChainSpec::from_json_file(
-				std::path::PathBuf::from(path),
-			)?),
+			path => chain_spec::load_spec_from_json_file(PathBuf::from(path))?,
 		})
 	}
 }
@@ -125,16 +122,8 @@ fn get_exec_name() -> Option<String> {
 		.and_then(|s| s.into_string().ok())
 }
 
-fn set_default_ss58_version(spec: &Box<dyn sc_service::ChainSpec>) {
-	let ss58_version = if spec.is_crab() {
-		Ss58AddressFormat::SubstrateAccount
-	} else if spec.is_darwinia() {
-		Ss58AddressFormat::DarwiniaAccount
-	} else {
-		Ss58AddressFormat::DarwiniaAccount
-	};
-
-	sp_core::crypto::set_default_ss58_version(ss58_version);
+fn set_default_ss58_version(variant: RuntimeVariant) {
+	sp_core::crypto::set_default_ss58_version(variant.ss58_format());
 }
 
 /// Parses Darwinia specific CLI arguments and run the service.
@@ -144,10 +133,41 @@ pub fn run() -> sc_cli::Result<()> {
 	match &cli.subcommand {
 		None => {
 			let authority_discovery_disabled = cli.run.authority_discovery_disabled;
+			let grandpa_voting_rules = cli.run.grandpa_voting_rules.clone();
+			let dvm_config = cli.run.dvm.dvm_config();
+			let health_config = cli.run.health.health_config();
+			let force_crab = cli.run.force_crab;
+			let sealing = cli.run.sealing;
+			let indexer_enabled = cli.run.indexer;
 			let runner = Configuration::create_runner(cli)?;
-			let chain_spec = &runner.config().chain_spec;
+			let variant = runner.config().chain_spec.variant()?;
+
+			if sealing.is_some() {
+				if variant != RuntimeVariant::Crab {
+					return Err("`--sealing` is only supported by the Crab runtime".into());
+				}
+				if runner.config().chain_spec.chain_type() != ChainType::Development {
+					return Err("`--sealing` is only supported by development chains".into());
+				}
+				if matches!(runner.config().role, Role::Light) {
+					return Err("`--sealing` is not supported by light clients".into());
+				}
+				if indexer_enabled {
+					return Err("`--indexer` is not supported with `--sealing`".into());
+				}
+			}
+			if indexer_enabled && matches!(runner.config().role, Role::Light) {
+				return Err("`--indexer` is not supported by light clients".into());
+			}
 
-			set_default_ss58_version(chain_spec);
+			if force_crab {
+				warn!(
+					"`--force-crab` is deprecated and has no effect, \
+					the runtime is detected from the chain spec"
+				);
+			}
+
+			set_default_ss58_version(variant);
 
 			info!("  _____                      _       _       ");
 			info!(" |  __ \\                    (_)     (_)      ");
@@ -156,29 +176,42 @@ pub fn run() -> sc_cli::Result<()> {
 			info!(" | |__| | (_| | |   \\ V  V /| | | | | | (_| |");
 			info!(" |_____/ \\__,_|_|    \\_/\\_/ |_|_| |_|_|\\__,_|");
 
-			if chain_spec.is_crab() {
-				runner.run_node_until_exit(|config| async move {
-					match config.role {
-						Role::Light => {
+			match variant {
+				RuntimeVariant::Crab => runner.run_node_until_exit(|config| async move {
+					match (config.role.clone(), sealing) {
+						(Role::Light, _) => {
 							crab::crab_new_light(config).map(|(task_manager, _)| task_manager)
 						}
-						_ => crab::crab_new_full(config, authority_discovery_disabled)
+						(_, Some(sealing)) => crab::crab_new_dev(config, sealing, dvm_config)
 							.map(|(task_manager, _, _)| task_manager),
+						_ => crab::crab_new_full(
+							config,
+							authority_discovery_disabled,
+							grandpa_voting_rules,
+							dvm_config,
+							health_config,
+							indexer_enabled,
+						)
+						.map(|(task_manager, _, _)| task_manager),
 					}
 					.map_err(sc_cli::Error::Service)
-				})
-			} else if chain_spec.is_darwinia() {
-				runner.run_node_until_exit(|config| async move {
+				}),
+				RuntimeVariant::Darwinia => runner.run_node_until_exit(|config| async move {
 					match config.role {
 						Role::Light => darwinia::darwinia_new_light(config)
 							.map(|(task_manager, _)| task_manager),
-						_ => darwinia::darwinia_new_full(config, authority_discovery_disabled)
-							.map(|(task_manager, _, _)| task_manager),
+						_ => darwinia::darwinia_new_full(
+							config,
+							authority_discovery_disabled,
+							grandpa_voting_rules,
+							dvm_config,
+							health_config,
+							indexer_enabled,
+						)
+						.map(|(task_manager, _, _)| task_manager),
 					}
 					.map_err(sc_cli::Error::Service)
-				})
-			} else {
-				unreachable!()
+				}),
 			}
 		}
 		Some(Subcommand::BuildSpec(cmd)) => {
@@ -187,149 +220,424 @@ pub fn run() -> sc_cli::Result<()> {
 		}
 		Some(Subcommand::CheckBlock(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
-			let chain_spec = &runner.config().chain_spec;
+			let variant = runner.config().chain_spec.variant()?;
 
-			set_default_ss58_version(chain_spec);
+			set_default_ss58_version(variant);
 
-			if chain_spec.is_crab() {
-				runner.async_run(|mut config| {
+			match variant {
+				RuntimeVariant::Crab => runner.async_run(|mut config| {
 					let (client, _, import_queue, task_manager) =
 						crab::new_chain_ops::<crab_runtime::RuntimeApi, CrabExecutor>(&mut config)?;
 
 					Ok((cmd.run(client, import_queue), task_manager))
-				})
-			} else if chain_spec.is_darwinia() {
-				runner.async_run(|mut config| {
+				}),
+				RuntimeVariant::Darwinia => runner.async_run(|mut config| {
 					let (client, _, import_queue, task_manager) = darwinia::new_chain_ops::<
 						darwinia_runtime::RuntimeApi,
 						DarwiniaExecutor,
 					>(&mut config)?;
 
 					Ok((cmd.run(client, import_queue), task_manager))
-				})
-			} else {
-				unreachable!()
+				}),
 			}
 		}
 		Some(Subcommand::ExportBlocks(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
-			let chain_spec = &runner.config().chain_spec;
+			let variant = runner.config().chain_spec.variant()?;
 
-			set_default_ss58_version(chain_spec);
+			set_default_ss58_version(variant);
 
-			if chain_spec.is_crab() {
-				runner.async_run(|mut config| {
+			match variant {
+				RuntimeVariant::Crab => runner.async_run(|mut config| {
 					let (client, _, _, task_manager) =
 						crab::new_chain_ops::<crab_runtime::RuntimeApi, CrabExecutor>(&mut config)?;
 
 					Ok((cmd.run(client, config.database), task_manager))
-				})
-			} else if chain_spec.is_darwinia() {
-				runner.async_run(|mut config| {
+				}),
+				RuntimeVariant::Darwinia => runner.async_run(|mut config| {
 					let (client, _, _, task_manager) = darwinia::new_chain_ops::<
 						darwinia_runtime::RuntimeApi,
 						DarwiniaExecutor,
 					>(&mut config)?;
 
 					Ok((cmd.run(client, config.database), task_manager))
-				})
-			} else {
-				unreachable!()
+				}),
 			}
 		}
 		Some(Subcommand::ExportState(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
-			let chain_spec = &runner.config().chain_spec;
+			let variant = runner.config().chain_spec.variant()?;
 
-			set_default_ss58_version(chain_spec);
+			set_default_ss58_version(variant);
 
-			if chain_spec.is_crab() {
-				runner.async_run(|mut config| {
+			match variant {
+				RuntimeVariant::Crab => runner.async_run(|mut config| {
 					let (client, _, _, task_manager) =
 						crab::new_chain_ops::<crab_runtime::RuntimeApi, CrabExecutor>(&mut config)?;
 
 					Ok((cmd.run(client, config.chain_spec), task_manager))
-				})
-			} else if chain_spec.is_darwinia() {
-				runner.async_run(|mut config| {
+				}),
+				RuntimeVariant::Darwinia => runner.async_run(|mut config| {
 					let (client, _, _, task_manager) = darwinia::new_chain_ops::<
 						darwinia_runtime::RuntimeApi,
 						DarwiniaExecutor,
 					>(&mut config)?;
 
 					Ok((cmd.run(client, config.chain_spec), task_manager))
-				})
-			} else {
-				unreachable!()
+				}),
 			}
 		}
 		Some(Subcommand::ImportBlocks(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
-			let chain_spec = &runner.config().chain_spec;
+			let variant = runner.config().chain_spec.variant()?;
 
-			set_default_ss58_version(chain_spec);
+			set_default_ss58_version(variant);
 
-			if chain_spec.is_crab() {
-				runner.async_run(|mut config| {
+			match variant {
+				RuntimeVariant::Crab => runner.async_run(|mut config| {
 					let (client, _, import_queue, task_manager) =
 						crab::new_chain_ops::<crab_runtime::RuntimeApi, CrabExecutor>(&mut config)?;
 
 					Ok((cmd.run(client, import_queue), task_manager))
-				})
-			} else if chain_spec.is_darwinia() {
-				runner.async_run(|mut config| {
+				}),
+				RuntimeVariant::Darwinia => runner.async_run(|mut config| {
 					let (client, _, import_queue, task_manager) = darwinia::new_chain_ops::<
 						darwinia_runtime::RuntimeApi,
 						DarwiniaExecutor,
 					>(&mut config)?;
 
 					Ok((cmd.run(client, import_queue), task_manager))
-				})
-			} else {
-				unreachable!()
+				}),
 			}
 		}
 		Some(Subcommand::PurgeChain(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
-			runner.sync_run(|config| cmd.run(config.database))
+			runner.sync_run(|config| {
+				let dvm_database_dir = dvm::database_dir(&config);
+				let database_path = config
+					.database
+					.path()
+					.filter(|path| path.exists())
+					.map(ToOwned::to_owned);
+
+				cmd.run(config.database)?;
+
+				// Only follow the chain database if it existed and its purge was confirmed
+				if database_path.map_or(false, |path| !path.exists())
+					&& dvm::purge(&dvm_database_dir)?
+				{
+					println!("{:?} removed.", dvm_database_dir);
+				}
+
+				Ok(())
+			})
 		}
 		Some(Subcommand::Revert(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
-			let chain_spec = &runner.config().chain_spec;
+			let variant = runner.config().chain_spec.variant()?;
 
-			set_default_ss58_version(chain_spec);
+			set_default_ss58_version(variant);
 
-			if chain_spec.is_crab() {
-				runner.async_run(|mut config| {
+			let dvm_config = cmd.dvm.dvm_config();
+
+			match variant {
+				RuntimeVariant::Crab => runner.async_run(|mut config| {
 					let (client, backend, _, task_manager) =
 						crab::new_chain_ops::<crab_runtime::RuntimeApi, CrabExecutor>(&mut config)?;
 
-					Ok((cmd.run(client, backend), task_manager))
-				})
-			} else if chain_spec.is_darwinia() {
-				runner.async_run(|mut config| {
+					Ok((
+						async move {
+							cmd.base.run(client.clone(), backend.clone()).await?;
+
+							if let Some(synced) = dvm::rebuild_if_exists(
+								&*client,
+								&*backend,
+								&config,
+								&dvm_config,
+								|synced| info!("Synced {} blocks", synced),
+							)? {
+								info!("Rebuilt the DVM mapping of {} blocks", synced);
+							}
+
+							Ok(())
+						},
+						task_manager,
+					))
+				}),
+				RuntimeVariant::Darwinia => runner.async_run(|mut config| {
 					let (client, backend, _, task_manager) = darwinia::new_chain_ops::<
 						darwinia_runtime::RuntimeApi,
 						DarwiniaExecutor,
 					>(&mut config)?;
 
-					Ok((cmd.run(client, backend), task_manager))
-				})
-			} else {
-				unreachable!()
+					Ok((
+						async move {
+							cmd.base.run(client.clone(), backend.clone()).await?;
+
+							if let Some(synced) = dvm::rebuild_if_exists(
+								&*client,
+								&*backend,
+								&config,
+								&dvm_config,
+								|synced| info!("Synced {} blocks", synced),
+							)? {
+								info!("Rebuilt the DVM mapping of {} blocks", synced);
+							}
+
+							Ok(())
+						},
+						task_manager,
+					))
+				}),
 			}
 		}
 		Some(Subcommand::Key(cmd)) => cmd.run(&cli),
 		Some(Subcommand::Sign(cmd)) => cmd.run(),
 		Some(Subcommand::Verify(cmd)) => cmd.run(),
 		Some(Subcommand::Vanity(cmd)) => cmd.run(),
+		Some(Subcommand::Address(cmd)) => cmd.run(),
+		Some(Subcommand::Decode(cmd)) => cmd.run(),
+		Some(Subcommand::SessionKeys(cmd)) => cmd.run(),
+		Some(Subcommand::TestnetSpec(cmd)) => cmd.run(),
+		Some(Subcommand::VerifyGenesis(cmd)) => cmd.run(),
+		Some(Subcommand::Inspect(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			let variant = runner.config().chain_spec.variant()?;
+
+			set_default_ss58_version(variant);
+
+			match variant {
+				RuntimeVariant::Crab => runner.async_run(|mut config| {
+					let (client, _, _, task_manager) =
+						crab::new_chain_ops::<crab_runtime::RuntimeApi, CrabExecutor>(&mut config)?;
+					let dvm_transactions = {
+						let client = client.clone();
+
+						move |at: &_| {
+							client
+								.runtime_api()
+								.current_transaction_statuses(at)
+								.ok()
+								.flatten()
+						}
+					};
+
+					Ok((
+						async move {
+							cmd.run::<
+								crab_runtime::Runtime,
+								crab_runtime::UncheckedExtrinsic,
+								_,
+								_,
+							>(client, variant, dvm_transactions)
+						},
+						task_manager,
+					))
+				}),
+				RuntimeVariant::Darwinia => runner.async_run(|mut config| {
+					let (client, _, _, task_manager) = darwinia::new_chain_ops::<
+						darwinia_runtime::RuntimeApi,
+						DarwiniaExecutor,
+					>(&mut config)?;
+					let dvm_transactions = {
+						let client = client.clone();
+
+						move |at: &_| {
+							client
+								.runtime_api()
+								.current_transaction_statuses(at)
+								.ok()
+								.flatten()
+						}
+					};
+
+					Ok((
+						async move {
+							cmd.run::<
+								darwinia_runtime::Runtime,
+								darwinia_runtime::UncheckedExtrinsic,
+								_,
+								_,
+							>(client, variant, dvm_transactions)
+						},
+						task_manager,
+					))
+				}),
+			}
+		}
+		Some(Subcommand::UpgradeDryRun(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			let variant = runner.config().chain_spec.variant()?;
+
+			set_default_ss58_version(variant);
+
+			match variant {
+				RuntimeVariant::Crab => runner.async_run(|mut config| {
+					let (client, _, _, task_manager) =
+						crab::new_chain_ops::<crab_runtime::RuntimeApi, CrabExecutor>(&mut config)?;
+					let (wasm_method, heap_pages) = (config.wasm_method, config.default_heap_pages);
+
+					Ok((
+						async move { cmd.run(client, wasm_method, heap_pages) },
+						task_manager,
+					))
+				}),
+				RuntimeVariant::Darwinia => runner.async_run(|mut config| {
+					let (client, _, _, task_manager) = darwinia::new_chain_ops::<
+						darwinia_runtime::RuntimeApi,
+						DarwiniaExecutor,
+					>(&mut config)?;
+					let (wasm_method, heap_pages) = (config.wasm_method, config.default_heap_pages);
+
+					Ok((
+						async move { cmd.run(client, wasm_method, heap_pages) },
+						task_manager,
+					))
+				}),
+			}
+		}
+		Some(Subcommand::ExportCheckpoint(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			let variant = runner.config().chain_spec.variant()?;
+
+			set_default_ss58_version(variant);
+
+			match variant {
+				RuntimeVariant::Crab => runner.async_run(|mut config| {
+					let (client, _, _, task_manager) =
+						crab::new_chain_ops::<crab_runtime::RuntimeApi, CrabExecutor>(&mut config)?;
+
+					Ok((
+						async move { cmd.run(|id| checkpoint::export(&*client, id)) },
+						task_manager,
+					))
+				}),
+				RuntimeVariant::Darwinia => runner.async_run(|mut config| {
+					let (client, _, _, task_manager) = darwinia::new_chain_ops::<
+						darwinia_runtime::RuntimeApi,
+						DarwiniaExecutor,
+					>(&mut config)?;
+
+					Ok((
+						async move { cmd.run(|id| checkpoint::export(&*client, id)) },
+						task_manager,
+					))
+				}),
+			}
+		}
+		Some(Subcommand::ForkSpec(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+
+			if let Some(state) = &cmd.state {
+				return runner.sync_run(|_| cmd.run_with_state(state));
+			}
+
+			let variant = runner.config().chain_spec.variant()?;
+
+			set_default_ss58_version(variant);
+
+			match variant {
+				RuntimeVariant::Crab => runner.async_run(|mut config| {
+					let (client, _, _, task_manager) =
+						crab::new_chain_ops::<crab_runtime::RuntimeApi, CrabExecutor>(&mut config)?;
+
+					Ok((
+						async move { cmd.run_with_client(client, config.chain_spec, variant) },
+						task_manager,
+					))
+				}),
+				RuntimeVariant::Darwinia => runner.async_run(|mut config| {
+					let (client, _, _, task_manager) = darwinia::new_chain_ops::<
+						darwinia_runtime::RuntimeApi,
+						DarwiniaExecutor,
+					>(&mut config)?;
+
+					Ok((
+						async move { cmd.run_with_client(client, config.chain_spec, variant) },
+						task_manager,
+					))
+				}),
+			}
+		}
+		Some(Subcommand::Dvm(DvmCmd::Purge(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run(&config))
+		}
+		Some(Subcommand::Dvm(DvmCmd::Rebuild(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			let variant = runner.config().chain_spec.variant()?;
+
+			set_default_ss58_version(variant);
+
+			match variant {
+				RuntimeVariant::Crab => runner.async_run(|mut config| {
+					let (client, backend, _, task_manager) =
+						crab::new_chain_ops::<crab_runtime::RuntimeApi, CrabExecutor>(&mut config)?;
+
+					Ok((
+						async move { cmd.run(client, backend, &config) },
+						task_manager,
+					))
+				}),
+				RuntimeVariant::Darwinia => runner.async_run(|mut config| {
+					let (client, backend, _, task_manager) = darwinia::new_chain_ops::<
+						darwinia_runtime::RuntimeApi,
+						DarwiniaExecutor,
+					>(&mut config)?;
+
+					Ok((
+						async move { cmd.run(client, backend, &config) },
+						task_manager,
+					))
+				}),
+			}
+		}
+		Some(Subcommand::Dvm(DvmCmd::Verify(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			let variant = runner.config().chain_spec.variant()?;
+
+			set_default_ss58_version(variant);
+
+			match variant {
+				RuntimeVariant::Crab => runner.async_run(|mut config| {
+					let (client, _, _, task_manager) =
+						crab::new_chain_ops::<crab_runtime::RuntimeApi, CrabExecutor>(&mut config)?;
+
+					Ok((async move { cmd.run(client, &config) }, task_manager))
+				}),
+				RuntimeVariant::Darwinia => runner.async_run(|mut config| {
+					let (client, _, _, task_manager) = darwinia::new_chain_ops::<
+						darwinia_runtime::RuntimeApi,
+						DarwiniaExecutor,
+					>(&mut config)?;
+
+					Ok((async move { cmd.run(client, &config) }, task_manager))
+				}),
+			}
+		}
+		#[cfg(feature = "runtime-benchmarks")]
+		Some(Subcommand::Benchmark(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			let variant = runner.config().chain_spec.variant()?;
+
+			set_default_ss58_version(variant);
+
+			match variant {
+				RuntimeVariant::Crab => {
+					runner.sync_run(|config| cmd.run::<crab_runtime::Block, CrabExecutor>(config))
+				}
+				RuntimeVariant::Darwinia => runner.sync_run(|config| {
+					cmd.run::<darwinia_runtime::Block, DarwiniaExecutor>(config)
+				}),
+			}
+		}
 		#[cfg(feature = "try-runtime")]
 		Some(Subcommand::TryRuntime(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
-			let chain_spec = &runner.config().chain_spec;
+			let variant = runner.config().chain_spec.variant()?;
 
-			if chain_spec.is_crab() {
-				runner.async_run(|config| {
+			match variant {
+				RuntimeVariant::Crab => runner.async_run(|config| {
 					let registry = config.prometheus_config.as_ref().map(|cfg| &cfg.registry);
 					// we don't need any of the components of new_partial, just a runtime, or a task
 					// manager to do `async_run`.
@@ -343,9 +651,8 @@ pub fn run() -> sc_cli::Result<()> {
 						cmd.run::<crab_runtime::Block, CrabExecutor>(config),
 						task_manager,
 					))
-				})
-			} else if chain_spec.is_darwinia() {
-				runner.async_run(|config| {
+				}),
+				RuntimeVariant::Darwinia => runner.async_run(|config| {
 					let registry = config.prometheus_config.as_ref().map(|cfg| &cfg.registry);
 					// we don't need any of the components of new_partial, just a runtime, or a task
 					// manager to do `async_run`.
@@ -359,9 +666,7 @@ pub fn run() -> sc_cli::Result<()> {
 						cmd.run::<darwinia_runtime::Block, DarwiniaExecutor>(config),
 						task_manager,
 					))
-				})
-			} else {
-				unreachable!()
+				}),
 			}
 		}
 	}
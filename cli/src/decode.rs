@@ -0,0 +1,431 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! Offline decoding of SCALE encoded extrinsics, calls, events and storage entries.
+
+// --- std ---
+use std::fmt::Debug;
+// --- crates ---
+use codec::{Decode, DecodeAll, Encode};
+use serde_json::{json, Value};
+use structopt::StructOpt;
+// --- substrate ---
+use frame_support::dispatch::GetCallMetadata;
+use frame_system::{AccountInfo, EventRecord};
+use sp_core::{
+	bytes,
+	crypto::{AccountId32, Ss58AddressFormat, Ss58Codec},
+};
+use sp_runtime::{generic::UncheckedExtrinsic, traits::SignedExtension, MultiAddress};
+// --- darwinia ---
+use crate::cli::parse_runtime_variant;
+use darwinia_primitives::{Balance, Moment};
+use darwinia_service::{
+	chain_spec::metadata::{StorageEntries, StorageHasher},
+	service::{crab::crab_runtime, darwinia::darwinia_runtime, RuntimeVariant},
+};
+
+/// The parts of a runtime `UncheckedExtrinsic` the decoder prints.
+pub trait RuntimeExtrinsic: Decode {
+	/// The outer call of the runtime.
+	type Call;
+	/// The signed extensions of the runtime.
+	type SignedExtra: Debug;
+
+	/// The signer, the encoded signature and the signed extensions of a signed extrinsic.
+	fn signature(&self) -> Option<(&MultiAddress<AccountId32, ()>, Vec<u8>, &Self::SignedExtra)>;
+
+	/// The call of the extrinsic.
+	fn call(&self) -> &Self::Call;
+}
+impl<Call, Signature, Extra> RuntimeExtrinsic
+	for UncheckedExtrinsic<MultiAddress<AccountId32, ()>, Call, Signature, Extra>
+where
+	Call: Decode,
+	Signature: Decode + Encode,
+	Extra: SignedExtension,
+{
+	type Call = Call;
+	type SignedExtra = Extra;
+
+	fn signature(&self) -> Option<(&MultiAddress<AccountId32, ()>, Vec<u8>, &Self::SignedExtra)> {
+		self.signature
+			.as_ref()
+			.map(|(address, signature, extra)| (address, signature.encode(), extra))
+	}
+
+	fn call(&self) -> &Self::Call {
+		&self.function
+	}
+}
+
+/// The `decode` command used to decode SCALE encoded data with the native runtime types.
+#[derive(Debug, StructOpt)]
+pub struct DecodeCmd {
+	/// The chain the data comes from, `crab` or `darwinia`.
+	#[structopt(long, value_name = "CHAIN", parse(try_from_str = parse_runtime_variant))]
+	pub chain: RuntimeVariant,
+
+	#[allow(missing_docs)]
+	#[structopt(subcommand)]
+	pub input: DecodeInput,
+}
+
+/// The kind of the data to decode.
+#[derive(Debug, StructOpt)]
+pub enum DecodeInput {
+	/// Decode an extrinsic, signed or unsigned.
+	Extrinsic {
+		/// Hex encoded extrinsic, `0x` prefix is optional.
+		hex: String,
+	},
+	/// Decode a call.
+	Call {
+		/// Hex encoded call, `0x` prefix is optional.
+		hex: String,
+	},
+	/// Decode an event.
+	Event {
+		/// Hex encoded event, `0x` prefix is optional.
+		hex: String,
+	},
+	/// Decode a storage key and optionally its value.
+	Storage {
+		/// Hex encoded storage key, `0x` prefix is optional.
+		key: String,
+		/// Hex encoded storage value, `0x` prefix is optional.
+		value: Option<String>,
+	},
+}
+
+impl DecodeCmd {
+	/// Run the decode command.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let decoded = match self.chain {
+			RuntimeVariant::Crab => self
+				.decode::<crab_runtime::Runtime, crab_runtime::UncheckedExtrinsic>(
+					StorageEntries::new(crab_runtime::Runtime::metadata())?,
+				),
+			RuntimeVariant::Darwinia => self
+				.decode::<darwinia_runtime::Runtime, darwinia_runtime::UncheckedExtrinsic>(
+					StorageEntries::new(darwinia_runtime::Runtime::metadata())?,
+				),
+		}?;
+
+		println!(
+			"{}",
+			serde_json::to_string_pretty(&decoded).map_err(|e| format!("{}", e))?
+		);
+
+		Ok(())
+	}
+
+	fn decode<R, Xt>(&self, entries: StorageEntries) -> Result<Value, String>
+	where
+		R: frame_system::Config<AccountId = AccountId32>,
+		R::Call: GetCallMetadata,
+		Xt: RuntimeExtrinsic<Call = R::Call>,
+	{
+		let decoder = Decoder::new(self.chain.ss58_format());
+
+		match &self.input {
+			DecodeInput::Extrinsic { hex } => decode_all::<Xt>(&from_hex(hex)?)
+				.map(|extrinsic| decoder.extrinsic::<R, Xt>(&extrinsic)),
+			DecodeInput::Call { hex } => {
+				decode_all::<R::Call>(&from_hex(hex)?).map(|call| decoder.call::<R>(&call))
+			}
+			DecodeInput::Event { hex } => {
+				decode_all::<R::Event>(&from_hex(hex)?).map(|event| decoder.event::<R>(&event))
+			}
+			DecodeInput::Storage { key, value } => decoder.storage::<R>(
+				&entries,
+				&from_hex(key)?,
+				value.as_ref().map(|v| from_hex(v)).transpose()?,
+			),
+		}
+	}
+}
+
+/// Turns runtime types into JSON, printing the accounts in the SS58 format of the chain.
+pub(crate) struct Decoder {
+	ss58_format: Ss58AddressFormat,
+}
+impl Decoder {
+	/// Create a decoder for a chain.
+	///
+	/// This also makes the format the default one, which the runtime types print their accounts
+	/// with.
+	pub(crate) fn new(ss58_format: Ss58AddressFormat) -> Self {
+		sp_core::crypto::set_default_ss58_version(ss58_format);
+
+		Self { ss58_format }
+	}
+
+	fn extrinsic<R, Xt>(&self, extrinsic: &Xt) -> Value
+	where
+		R: frame_system::Config<AccountId = AccountId32>,
+		R::Call: GetCallMetadata,
+		Xt: RuntimeExtrinsic<Call = R::Call>,
+	{
+		let signature = extrinsic.signature().map(|(address, signature, extra)| {
+			json!({
+				"address": self.multi_address(address),
+				"signature": bytes::to_hex(&signature, false),
+				"extra": self.debug(extra),
+			})
+		});
+
+		json!({
+			"signed": signature.is_some(),
+			"signature": signature,
+			"call": self.call::<R>(extrinsic.call()),
+		})
+	}
+
+	pub(crate) fn call<R>(&self, call: &R::Call) -> Value
+	where
+		R: frame_system::Config,
+		R::Call: GetCallMetadata,
+	{
+		let metadata = call.get_call_metadata();
+		let encoded = call.encode();
+
+		json!({
+			"pallet": metadata.pallet_name,
+			"call": metadata.function_name,
+			"index": [encoded[0], encoded[1]],
+			"value": self.debug(call),
+		})
+	}
+
+	pub(crate) fn event<R>(&self, event: &R::Event) -> Value
+	where
+		R: frame_system::Config,
+	{
+		let encoded = event.encode();
+
+		json!({
+			"index": [encoded[0], encoded[1]],
+			"value": self.debug(event),
+		})
+	}
+
+	/// Decode a storage key, and its value if any, with the types named in the runtime metadata.
+	///
+	/// The keys behind a non-concat hasher can't be recovered and are printed as hashes, so is
+	/// anything of a type unknown to `Decoder::typed`.
+	fn storage<R>(
+		&self,
+		entries: &StorageEntries,
+		key: &[u8],
+		value: Option<Vec<u8>>,
+	) -> Result<Value, String>
+	where
+		R: frame_system::Config<AccountId = AccountId32>,
+	{
+		if key.len() < 32 {
+			return Err("Storage key is shorter than a pallet and item prefix".into());
+		}
+
+		let entry = match entries.entry_of(key) {
+			Some(entry) => entry,
+			None => {
+				return Ok(json!({
+					"pallet": bytes::to_hex(&key[..16], false),
+					"item": bytes::to_hex(&key[16..32], false),
+					"key": bytes::to_hex(&key[32..], false),
+					"value": value.map(|v| bytes::to_hex(&v, false)),
+				}))
+			}
+		};
+		let mut suffix = &key[32..];
+		let mut keys = vec![];
+
+		for (hasher, ty) in &entry.keys {
+			// A prefix of the item, or of its first key
+			if suffix.is_empty() {
+				break;
+			}
+
+			let (hash_len, concat) = match hasher {
+				StorageHasher::Blake2_128 | StorageHasher::Twox128 => (16, false),
+				StorageHasher::Blake2_256 | StorageHasher::Twox256 => (32, false),
+				StorageHasher::Blake2_128Concat => (16, true),
+				StorageHasher::Twox64Concat => (8, true),
+				StorageHasher::Identity => (0, true),
+			};
+
+			if suffix.len() < hash_len {
+				return Err(format!(
+					"Storage key is too short for the hashed keys of `{}::{}`",
+					entry.pallet, entry.item
+				));
+			}
+
+			let (hash, rest) = suffix.split_at(hash_len);
+
+			suffix = rest;
+
+			if !concat {
+				keys.push(json!({ "hash": bytes::to_hex(hash, false) }));
+
+				continue;
+			}
+
+			match self.typed::<R>(ty, &mut suffix) {
+				Some(key) => keys.push(key?),
+				None => {
+					keys.push(Value::String(bytes::to_hex(suffix, false)));
+					suffix = &[];
+				}
+			}
+		}
+
+		if !suffix.is_empty() {
+			return Err(format!(
+				"Storage key has {} unexpected bytes for `{}::{}`",
+				suffix.len(),
+				entry.pallet,
+				entry.item
+			));
+		}
+
+		let value = value
+			.map(|value| {
+				let mut input = &value[..];
+
+				match self.typed::<R>(&entry.value, &mut input) {
+					Some(decoded) if input.is_empty() => decoded,
+					Some(decoded) => decoded.and_then(|_| {
+						Err(format!(
+							"Storage value has {} unexpected bytes for `{}`",
+							input.len(),
+							entry.value
+						))
+					}),
+					None => Ok(Value::String(bytes::to_hex(&value, false))),
+				}
+			})
+			.transpose()?;
+
+		Ok(json!({
+			"pallet": entry.pallet,
+			"item": entry.item,
+			"keys": keys,
+			"value": value,
+		}))
+	}
+
+	/// Decode a value of a type named in the runtime metadata, `None` if the type isn't known here.
+	fn typed<R>(&self, ty: &str, input: &mut &[u8]) -> Option<Result<Value, String>>
+	where
+		R: frame_system::Config<AccountId = AccountId32>,
+	{
+		macro_rules! decode {
+			($ty:ty, $to_json:expr) => {
+				Some(
+					<$ty>::decode(input)
+						.map(|decoded| $to_json(&decoded))
+						.map_err(|e| format!("Failed to decode `{}`: {}", ty, e)),
+				)
+			};
+		}
+
+		// The type names are written as in the runtime, without whitespaces
+		match ty.replace(char::is_whitespace, "").as_str() {
+			"T::AccountId" => decode!(AccountId32, |account| Value::String(self.address(account))),
+			"Vec<T::AccountId>" => decode!(Vec<AccountId32>, |accounts: &Vec<_>| {
+				Value::Array(
+					accounts
+						.iter()
+						.map(|account| Value::String(self.address(account)))
+						.collect(),
+				)
+			}),
+			"T::BlockNumber" => decode!(R::BlockNumber, |number| Value::String(self.debug(number))),
+			"T::Hash" => decode!(R::Hash, |hash| Value::String(self.debug(hash))),
+			"T::Index" => decode!(R::Index, |index| Value::String(self.debug(index))),
+			"AccountInfo<T::Index,T::AccountData>" => decode!(
+				AccountInfo<R::Index, R::AccountData>,
+				|info| Value::String(self.debug(info))
+			),
+			"Vec<EventRecord<T::Event,T::Hash>>" => {
+				decode!(Vec<EventRecord<R::Event, R::Hash>>, |records: &Vec<_>| self
+					.event_records::<R>(records))
+			}
+			"T::Moment" | "u64" => decode!(Moment, |moment: &u64| Value::from(*moment)),
+			// `u128` doesn't fit into a JSON number
+			"T::Balance" | "BalanceOf<T>" | "RingBalance<T>" | "KtonBalance<T>" | "Balance"
+			| "u128" => decode!(Balance, |balance: &u128| Value::String(balance.to_string())),
+			"EraIndex" | "SessionIndex" | "u32" => decode!(u32, |n: &u32| Value::from(*n)),
+			"u16" => decode!(u16, |n: &u16| Value::from(*n)),
+			"u8" => decode!(u8, |n: &u8| Value::from(*n)),
+			"bool" => decode!(bool, |b: &bool| Value::from(*b)),
+			_ => None,
+		}
+	}
+
+	fn event_records<R>(&self, records: &[EventRecord<R::Event, R::Hash>]) -> Value
+	where
+		R: frame_system::Config,
+	{
+		Value::Array(
+			records
+				.iter()
+				.map(|record| {
+					json!({
+						"phase": self.debug(&record.phase),
+						"event": self.event::<R>(&record.event),
+						"topics": record
+							.topics
+							.iter()
+							.map(|topic| self.debug(topic))
+							.collect::<Vec<_>>(),
+					})
+				})
+				.collect(),
+		)
+	}
+
+	pub(crate) fn address(&self, account: &AccountId32) -> String {
+		account.to_ss58check_with_version(self.ss58_format)
+	}
+
+	pub(crate) fn multi_address(&self, address: &MultiAddress<AccountId32, ()>) -> String {
+		match address {
+			MultiAddress::Id(account) => self.address(account),
+			address => self.debug(address),
+		}
+	}
+
+	/// The `Debug` output of a runtime type.
+	///
+	/// The accounts in it are abbreviated, in the default SS58 format set by `Decoder::new`.
+	pub(crate) fn debug<T: Debug>(&self, t: &T) -> String {
+		format!("{:?}", t)
+	}
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>, String> {
+	bytes::from_hex(hex.trim()).map_err(|e| format!("Invalid hex input `{}`: {}", hex, e))
+}
+
+pub(crate) fn decode_all<T: Decode>(encoded: &[u8]) -> Result<T, String> {
+	T::decode_all(encoded)
+		.map_err(|e| format!("Failed to decode `{}`: {}", std::any::type_name::<T>(), e))
+}
@@ -0,0 +1,232 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! Maintenance of the DVM mapping database.
+
+// --- std ---
+use std::{
+	io::{self, Write},
+	sync::Arc,
+};
+// --- crates ---
+use log::info;
+use serde_json::json;
+use structopt::StructOpt;
+// --- substrate ---
+use sc_cli::{CliConfiguration, ImportParams, SharedParams};
+use sc_client_api::blockchain::HeaderBackend;
+use sc_service::Configuration;
+use sp_api::ProvideRuntimeApi;
+// --- darwinia ---
+use crate::cli::DvmParams;
+use darwinia_primitives::OpaqueBlock as Block;
+use darwinia_service::service::dvm::{self, MappingCheck};
+use dvm_rpc_runtime_api::EthereumRuntimeRPCApi;
+
+/// The `dvm` command used to maintain the DVM mapping database.
+#[derive(Debug, StructOpt)]
+pub enum DvmCmd {
+	/// Remove the DVM mapping database, the node rebuilds it while syncing.
+	Purge(DvmPurgeCmd),
+	/// Remove the DVM mapping database and rebuild it from the local chain.
+	Rebuild(DvmRebuildCmd),
+	/// Spot check the DVM mapping database against the local chain.
+	Verify(DvmVerifyCmd),
+}
+
+/// The `dvm purge` command.
+#[derive(Debug, StructOpt)]
+pub struct DvmPurgeCmd {
+	/// Skip interactive prompt by answering yes automatically.
+	#[structopt(short = "y")]
+	pub yes: bool,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl DvmPurgeCmd {
+	/// Run the dvm purge command.
+	pub fn run(&self, config: &Configuration) -> sc_cli::Result<()> {
+		let database_dir = dvm::database_dir(config);
+
+		if !self.yes {
+			print!("Are you sure to remove {:?}? [y/N]: ", database_dir);
+			io::stdout().flush()?;
+
+			let mut input = String::new();
+
+			io::stdin().read_line(&mut input)?;
+
+			match input.trim() {
+				"y" | "Y" => (),
+				_ => {
+					println!("Aborted");
+
+					return Ok(());
+				}
+			}
+		}
+
+		if dvm::purge(&database_dir)? {
+			println!("{:?} removed.", database_dir);
+		} else {
+			eprintln!("{:?} did not exist.", database_dir);
+		}
+
+		Ok(())
+	}
+}
+
+impl CliConfiguration for DvmPurgeCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
+
+/// The `dvm rebuild` command.
+#[derive(Debug, StructOpt)]
+pub struct DvmRebuildCmd {
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub dvm: DvmParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub import_params: ImportParams,
+}
+
+impl DvmRebuildCmd {
+	/// Run the dvm rebuild command.
+	pub fn run<C, BE>(
+		&self,
+		client: Arc<C>,
+		backend: Arc<BE>,
+		config: &Configuration,
+	) -> sc_cli::Result<()>
+	where
+		C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync,
+		C::Api: EthereumRuntimeRPCApi<Block>,
+		BE: sc_client_api::Backend<Block>,
+	{
+		dvm::purge(&dvm::database_dir(config))?;
+
+		let frontier_backend = dvm::open_backend(config, &self.dvm.dvm_config())?;
+
+		info!(
+			"Rebuilding the DVM mapping up to #{}",
+			client.info().best_number
+		);
+
+		let synced = dvm::rebuild(&*client, &*backend, &frontier_backend, |synced| {
+			info!("Synced {} blocks", synced)
+		})?;
+
+		info!("Rebuilt the DVM mapping of {} blocks", synced);
+
+		Ok(())
+	}
+}
+
+impl CliConfiguration for DvmRebuildCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
+
+/// The `dvm verify` command.
+#[derive(Debug, StructOpt)]
+pub struct DvmVerifyCmd {
+	/// The number of blocks to check, evenly spread over the chain.
+	#[structopt(long, value_name = "COUNT", default_value = "1000")]
+	pub samples: u32,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub dvm: DvmParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub import_params: ImportParams,
+}
+
+impl DvmVerifyCmd {
+	/// Run the dvm verify command.
+	pub fn run<C>(&self, client: Arc<C>, config: &Configuration) -> sc_cli::Result<()>
+	where
+		C: HeaderBackend<Block>,
+	{
+		let frontier_backend = dvm::open_backend(config, &self.dvm.dvm_config())?;
+		let checks = dvm::verify(&*client, &frontier_backend, self.samples)?;
+		let unsynced = checks
+			.iter()
+			.filter(|(_, _, check)| *check == MappingCheck::Unsynced)
+			.count();
+		let invalid = checks
+			.iter()
+			.filter_map(|(number, hash, check)| match check {
+				MappingCheck::Invalid(reason) => Some(json!({
+					"number": number,
+					"hash": format!("{:?}", hash),
+					"reason": reason,
+				})),
+				_ => None,
+			})
+			.collect::<Vec<_>>();
+		let valid = invalid.is_empty();
+
+		println!(
+			"{}",
+			serde_json::to_string_pretty(&json!({
+				"checked": checks.len(),
+				"unsynced": unsynced,
+				"invalid": invalid,
+			}))
+			.map_err(|e| format!("{}", e))?
+		);
+
+		if valid {
+			Ok(())
+		} else {
+			Err("The DVM mapping database does not match the chain, see `dvm rebuild`".into())
+		}
+	}
+}
+
+impl CliConfiguration for DvmVerifyCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
@@ -0,0 +1,87 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! Export a checkpoint of the local chain, to put in the `checkpoints` of a chain spec.
+
+// --- std ---
+use std::{fs, path::PathBuf};
+// --- crates ---
+use log::info;
+use structopt::StructOpt;
+// --- substrate ---
+use sc_cli::{BlockNumberOrHash, CliConfiguration, ImportParams, SharedParams};
+use sp_runtime::generic::BlockId;
+// --- darwinia ---
+use darwinia_primitives::OpaqueBlock as Block;
+use darwinia_service::chain_spec::Checkpoint;
+
+/// The `export-checkpoint` command used to write a checkpoint from the local database.
+#[derive(Debug, StructOpt)]
+pub struct ExportCheckpointCmd {
+	/// Export the checkpoint of this finalized block, the last finalized block by default.
+	#[structopt(long, value_name = "HASH or NUMBER")]
+	pub at: Option<BlockNumberOrHash>,
+
+	/// Write the checkpoint to this file instead of stdout.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub import_params: ImportParams,
+}
+
+impl ExportCheckpointCmd {
+	/// Write the checkpoint read by `export`, see `darwinia_service::service::checkpoint::export`.
+	pub fn run(
+		&self,
+		export: impl FnOnce(Option<BlockId<Block>>) -> Result<Checkpoint, String>,
+	) -> sc_cli::Result<()> {
+		let id = self.at.as_ref().map(|at| at.parse::<Block>()).transpose()?;
+		let checkpoint = export(id)?;
+
+		info!(
+			"Exporting the checkpoint of block #{} ({})",
+			checkpoint.block_number, checkpoint.block_hash
+		);
+
+		let json = serde_json::to_string_pretty(&checkpoint).map_err(|e| format!("{}", e))?;
+
+		if let Some(output) = &self.output {
+			fs::write(output, json)?;
+		} else {
+			println!("{}", json);
+		}
+
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ExportCheckpointCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
@@ -0,0 +1,162 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! Fork the state of a live chain into a local dev network.
+
+// --- std ---
+use std::{fs, path::PathBuf, sync::Arc};
+// --- crates ---
+use log::info;
+use serde_json::Value;
+use structopt::StructOpt;
+// --- substrate ---
+use sc_cli::{BlockNumberOrHash, CliConfiguration, ImportParams, SharedParams};
+use sc_client_api::{StorageProvider, UsageProvider};
+use sc_service::ChainSpec;
+use sp_core::storage::Storage;
+use sp_runtime::generic::BlockId;
+// --- darwinia ---
+use darwinia_primitives::OpaqueBlock as Block;
+use darwinia_service::{
+	chain_spec::{self, crab::crab_fork_storage, darwinia::darwinia_fork_storage},
+	service::{IdentifyVariant, RuntimeVariant},
+};
+
+/// The `fork-spec` command used to turn the state of a live chain into a local dev network.
+#[derive(Debug, StructOpt)]
+pub struct ForkSpecCmd {
+	/// Fork the state of this block of the local database, the best block by default.
+	#[structopt(long, value_name = "HASH or NUMBER", conflicts_with = "state")]
+	pub at: Option<BlockNumberOrHash>,
+
+	/// Fork the state of a raw chain spec, such as the output of `export-state`, instead of the
+	/// local database.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	pub state: Option<PathBuf>,
+
+	/// The number of dev validators of the fork, `Alice`, `Bob`, ...
+	#[structopt(long, value_name = "COUNT", default_value = "1")]
+	pub validators: u32,
+
+	/// The sudo key of the fork, an SS58 address, a hex public key or a development seed.
+	#[structopt(long, value_name = "ACCOUNT", default_value = "//Alice")]
+	pub sudo: String,
+
+	/// Write the chain spec to this file instead of stdout.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub import_params: ImportParams,
+}
+
+impl ForkSpecCmd {
+	/// Fork the state of the raw chain spec given by `--state`.
+	pub fn run_with_state(&self, state: &PathBuf) -> sc_cli::Result<()> {
+		let chain_spec = chain_spec::load_spec_from_json_file(state.to_owned())?;
+		let storage = chain_spec.as_storage_builder().build_storage()?;
+		let variant = chain_spec.variant()?;
+
+		self.fork(chain_spec, storage, variant)
+	}
+
+	/// Fork the state of a block of the local database.
+	pub fn run_with_client<C, BE>(
+		&self,
+		client: Arc<C>,
+		chain_spec: Box<dyn ChainSpec>,
+		variant: RuntimeVariant,
+	) -> sc_cli::Result<()>
+	where
+		C: StorageProvider<Block, BE> + UsageProvider<Block>,
+		BE: sc_client_api::Backend<Block>,
+	{
+		let id = match &self.at {
+			Some(at) => at.parse::<Block>()?,
+			None => BlockId::Hash(client.usage_info().chain.best_hash),
+		};
+
+		info!("Exporting the state at {}", id);
+
+		let storage = sc_service::chain_ops::export_raw_state(client, &id)?;
+
+		self.fork(chain_spec, storage, variant)
+	}
+
+	fn fork(
+		&self,
+		mut chain_spec: Box<dyn ChainSpec>,
+		mut storage: Storage,
+		variant: RuntimeVariant,
+	) -> sc_cli::Result<()> {
+		let authorities = chain_spec::get_dev_authority_keys(self.validators);
+		let sudo = chain_spec::parse_account_id(&self.sudo)?;
+
+		match variant {
+			RuntimeVariant::Crab => crab_fork_storage(&mut storage, &authorities, &sudo)?,
+			RuntimeVariant::Darwinia => darwinia_fork_storage(&mut storage, &authorities, &sudo)?,
+		}
+
+		let id = format!("{}_fork", chain_spec.id());
+		let name = format!("{} Fork", chain_spec.name());
+
+		chain_spec.set_storage(storage);
+
+		let mut json = serde_json::from_str::<Value>(&chain_spec.as_json(true)?)
+			.map_err(|e| format!("Failed to parse the forked chain spec: {}", e))?;
+
+		// A fork must never connect to the live network
+		json["id"] = id.into();
+		json["name"] = name.into();
+		json["chainType"] = "Local".into();
+		json["bootNodes"] = Value::Array(vec![]);
+		json["telemetryEndpoints"] = Value::Null;
+		// Nor follow its forks and checkpoints, the fork has a genesis of its own
+		json["forkBlocks"] = Value::Null;
+		json["badBlocks"] = Value::Null;
+		if let Some(json) = json.as_object_mut() {
+			json.remove("grandpaHardForks");
+			json.remove("checkpoints");
+		}
+
+		let json = serde_json::to_string_pretty(&json).map_err(|e| format!("{}", e))?;
+
+		if let Some(output) = &self.output {
+			fs::write(output, json)?;
+		} else {
+			println!("{}", json);
+		}
+
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ForkSpecCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
@@ -0,0 +1,304 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! Offline inspection of the blocks in the local database.
+
+// --- std ---
+use std::sync::Arc;
+// --- crates ---
+use codec::{Compact, Decode, Encode};
+use log::warn;
+use serde_json::{json, Value};
+use structopt::StructOpt;
+// --- substrate ---
+use frame_support::dispatch::GetCallMetadata;
+use frame_system::{EventRecord, Phase};
+use sc_cli::{BlockNumberOrHash, CliConfiguration, ImportParams, SharedParams};
+use sc_client_api::{Backend, BlockBackend, StorageProvider};
+use sp_core::{
+	bytes,
+	crypto::AccountId32,
+	hashing::{blake2_256, twox_128},
+	storage::StorageKey,
+};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Header as HeaderT, SignedExtension},
+};
+// --- darwinia ---
+use crate::decode::{self, Decoder, RuntimeExtrinsic};
+use darwinia_primitives::{Balance, Nonce, OpaqueBlock as Block};
+use darwinia_service::service::{crab::crab_runtime, darwinia::darwinia_runtime, RuntimeVariant};
+use dvm_rpc_runtime_api::TransactionStatus;
+
+/// The `inspect` command used to print a block of the local database.
+#[derive(Debug, StructOpt)]
+pub struct InspectCmd {
+	/// Block hash or number.
+	#[structopt(value_name = "HASH or NUMBER")]
+	pub input: BlockNumberOrHash,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub import_params: ImportParams,
+}
+
+impl InspectCmd {
+	/// Print the header, the extrinsics with their events and the DVM transactions of the block.
+	///
+	/// Events and DVM transactions are read from the state of the block, they are skipped if the
+	/// state has been pruned.
+	pub fn run<R, Xt, BE, C>(
+		&self,
+		client: Arc<C>,
+		variant: RuntimeVariant,
+		dvm_transactions: impl Fn(&BlockId<Block>) -> Option<Vec<TransactionStatus>>,
+	) -> sc_cli::Result<()>
+	where
+		R: frame_system::Config<AccountId = AccountId32>,
+		R::Call: GetCallMetadata,
+		Xt: RuntimeExtrinsic<Call = R::Call>,
+		Xt::SignedExtra: SignedExtraInfo,
+		BE: Backend<Block>,
+		C: BlockBackend<Block> + StorageProvider<Block, BE>,
+	{
+		let id = self.input.parse::<Block>()?;
+		let block = client
+			.block(&id)?
+			.ok_or_else(|| format!("Block {} not found in the database", id))?
+			.block;
+		let hash = block.header.hash();
+		let at = BlockId::Hash(hash);
+		let decoder = Decoder::new(variant.ss58_format());
+		let (records, undecodable) = match client.storage(&at, &storage_key("System", "Events")) {
+			Ok(Some(events)) => {
+				let (records, undecodable) = decode_event_records::<R>(&events.0)?;
+
+				(Some(records), undecodable)
+			}
+			Ok(None) => (Some(vec![]), None),
+			Err(e) => {
+				warn!(
+					"State of block {} is unavailable, skip its events: {}",
+					hash, e
+				);
+
+				(None, None)
+			}
+		};
+		let events_of = |phase: Phase| {
+			records.as_ref().map(|records| {
+				let mut events = records
+					.iter()
+					.filter(|record| record.phase == phase)
+					.map(|record| decoder.event::<R>(&record.event))
+					.collect::<Vec<_>>();
+
+				if let Some(undecodable) = &undecodable {
+					if undecodable.phase == Some(phase) {
+						events.push(undecodable.to_json());
+					}
+				}
+
+				events
+			})
+		};
+		let mut extrinsics = vec![];
+
+		for (i, opaque) in block.extrinsics.iter().enumerate() {
+			let encoded = opaque.encode();
+			let extrinsic = match decode::decode_all::<Xt>(&encoded) {
+				Ok(extrinsic) => extrinsic,
+				Err(e) => {
+					extrinsics.push(json!({
+						"index": i,
+						"hash": bytes::to_hex(&blake2_256(&encoded), false),
+						"raw": bytes::to_hex(&encoded, false),
+						"note": format!("cannot decode with current runtime: {}", e),
+						"events": events_of(Phase::ApplyExtrinsic(i as _)),
+					}));
+
+					continue;
+				}
+			};
+			let (signer, nonce, tip) = match extrinsic.signature() {
+				Some((address, _, extra)) => (
+					Some(decoder.multi_address(address)),
+					Some(extra.nonce()),
+					// `u128` doesn't fit into a JSON number
+					Some(extra.tip().to_string()),
+				),
+				None => (None, None, None),
+			};
+
+			extrinsics.push(json!({
+				"index": i,
+				"hash": bytes::to_hex(&blake2_256(&encoded), false),
+				"signer": signer,
+				"nonce": nonce,
+				"tip": tip,
+				"call": decoder.call::<R>(extrinsic.call()),
+				"events": events_of(Phase::ApplyExtrinsic(i as _)),
+			}));
+		}
+
+		let dvm_transactions = dvm_transactions(&at).map(|statuses| {
+			statuses
+				.into_iter()
+				.map(|status| {
+					json!({
+						"hash": status.transaction_hash,
+						"index": status.transaction_index,
+						"from": status.from,
+						"to": status.to,
+						"contract_address": status.contract_address,
+						"logs": status.logs.len(),
+					})
+				})
+				.collect::<Vec<_>>()
+		});
+		let inspected: Value = json!({
+			"hash": hash,
+			"header": block.header,
+			"initialization": events_of(Phase::Initialization),
+			"extrinsics": extrinsics,
+			"finalization": events_of(Phase::Finalization),
+			"dvm_transactions": dvm_transactions,
+			// Undecodable from their phase on, so they can't be placed
+			"undecodable_events": undecodable
+				.as_ref()
+				.filter(|undecodable| undecodable.phase.is_none())
+				.map(|undecodable| undecodable.to_json()),
+		});
+
+		println!(
+			"{}",
+			serde_json::to_string_pretty(&inspected).map_err(|e| format!("{}", e))?
+		);
+
+		Ok(())
+	}
+}
+
+impl CliConfiguration for InspectCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
+
+/// Read the nonce and the tip out of the signed extensions of a runtime.
+pub trait SignedExtraInfo: SignedExtension {
+	/// The nonce of the signer.
+	fn nonce(&self) -> Nonce;
+
+	/// The tip paid to the block author.
+	fn tip(&self) -> Balance;
+}
+macro_rules! impl_signed_extra_info {
+	($($runtime:ident),+) => {
+		$(
+			// `CheckNonce` and `ChargeTransactionPayment` don't expose their inner values, but they
+			// are encoded as compact new types.
+			impl SignedExtraInfo for $runtime::SignedExtra {
+				fn nonce(&self) -> Nonce {
+					Compact::<Nonce>::decode(&mut &self.4.encode()[..])
+						.map(|nonce| nonce.0)
+						.unwrap_or_default()
+				}
+
+				fn tip(&self) -> Balance {
+					Compact::<Balance>::decode(&mut &self.6.encode()[..])
+						.map(|tip| tip.0)
+						.unwrap_or_default()
+				}
+			}
+		)+
+	};
+}
+impl_signed_extra_info!(crab_runtime, darwinia_runtime);
+
+/// The events of a block, from the first one the current runtime can't decode.
+struct UndecodableEvents {
+	/// The phase of the first undecodable event, `None` if even the phase can't be decoded.
+	phase: Option<Phase>,
+	raw: Vec<u8>,
+	error: String,
+}
+impl UndecodableEvents {
+	fn to_json(&self) -> Value {
+		json!({
+			"raw": bytes::to_hex(&self.raw, false),
+			"note": format!("cannot decode with current runtime: {}", self.error),
+		})
+	}
+}
+
+/// Decode the event records of a block one by one.
+///
+/// The records can't be split without being decoded, so everything from the first undecodable one
+/// on is kept raw.
+fn decode_event_records<R>(
+	encoded: &[u8],
+) -> Result<
+	(
+		Vec<EventRecord<R::Event, R::Hash>>,
+		Option<UndecodableEvents>,
+	),
+	String,
+>
+where
+	R: frame_system::Config,
+{
+	let mut input = encoded;
+	let len = Compact::<u32>::decode(&mut input)
+		.map_err(|e| format!("Failed to decode the number of events: {}", e))?
+		.0;
+	let mut records = vec![];
+
+	for _ in 0..len {
+		let rest = input;
+
+		match EventRecord::decode(&mut input) {
+			Ok(record) => records.push(record),
+			Err(e) => {
+				return Ok((
+					records,
+					Some(UndecodableEvents {
+						phase: Phase::decode(&mut &rest[..]).ok(),
+						raw: rest.to_vec(),
+						error: e.to_string(),
+					}),
+				))
+			}
+		}
+	}
+
+	Ok((records, None))
+}
+
+fn storage_key(pallet: &str, item: &str) -> StorageKey {
+	StorageKey([twox_128(pallet.as_bytes()), twox_128(item.as_bytes())].concat())
+}
@@ -21,10 +21,30 @@
 #![warn(missing_docs)]
 #![warn(unused_extern_crates)]
 
+#[cfg(feature = "cli")]
+mod address;
 #[cfg(feature = "cli")]
 mod cli;
 #[cfg(feature = "cli")]
 mod command;
+#[cfg(feature = "cli")]
+mod decode;
+#[cfg(feature = "cli")]
+mod dvm;
+#[cfg(feature = "cli")]
+mod export_checkpoint;
+#[cfg(feature = "cli")]
+mod fork_spec;
+#[cfg(feature = "cli")]
+mod inspect;
+#[cfg(feature = "cli")]
+mod session_keys;
+#[cfg(feature = "cli")]
+mod testnet_spec;
+#[cfg(feature = "cli")]
+mod upgrade_dry_run;
+#[cfg(feature = "cli")]
+mod verify_genesis;
 
 #[cfg(feature = "cli")]
 pub use command::run;
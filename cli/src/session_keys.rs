@@ -0,0 +1,138 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! Generation of the stash, controller and session keys of a genesis authority.
+
+// --- crates ---
+use codec::Encode;
+use serde::Serialize;
+use serde_json::{json, Value};
+use structopt::StructOpt;
+// --- substrate ---
+use sp_core::{
+	bytes,
+	crypto::{Pair, Ss58Codec},
+	sr25519,
+};
+// --- darwinia ---
+use crate::cli::parse_runtime_variant;
+use darwinia_primitives::AccountId;
+use darwinia_service::{
+	chain_spec::{self, crab::crab_session_keys, darwinia::darwinia_session_keys, AuthorityKeys},
+	service::RuntimeVariant,
+};
+
+/// The `session-keys` command used to generate the keys of a genesis authority.
+#[derive(Debug, StructOpt)]
+pub struct SessionKeysCmd {
+	/// The chain the keys are generated for, `crab` or `darwinia`.
+	#[structopt(long, value_name = "CHAIN", parse(try_from_str = parse_runtime_variant))]
+	pub chain: RuntimeVariant,
+
+	/// Derive the keys from a development seed, e.g. `Alice`, the same way the dev chain specs do.
+	#[structopt(long, value_name = "NAME", conflicts_with = "suri")]
+	pub seed: Option<String>,
+
+	/// Derive the keys from a secret URI.
+	///
+	/// The stash is derived at `<SURI>//stash`, the controller at `<SURI>//controller` and the
+	/// session keys at `<SURI>`.
+	///
+	/// A new mnemonic phrase is generated if neither this nor `--seed` is given.
+	#[structopt(long, value_name = "SURI")]
+	pub suri: Option<String>,
+
+	/// The password of the secret URI.
+	#[structopt(long, value_name = "PASSWORD", requires = "suri")]
+	pub password: Option<String>,
+}
+
+impl SessionKeysCmd {
+	/// Run the session-keys command.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		// The accounts of the chain spec fragment are serialized in the SS58 format of the chain
+		sp_core::crypto::set_default_ss58_version(self.chain.ss58_format());
+
+		let (phrase, keys) = if let Some(seed) = &self.seed {
+			(None, chain_spec::try_get_authority_keys_from_seed(seed)?)
+		} else if let Some(suri) = &self.suri {
+			(
+				None,
+				chain_spec::get_authority_keys_from_suri(suri, self.password.as_deref())?,
+			)
+		} else {
+			let (_, phrase, _) = sr25519::Pair::generate_with_phrase(None);
+			let keys = chain_spec::get_authority_keys_from_suri(&phrase, None)?;
+
+			(Some(phrase), keys)
+		};
+		let (_, _, babe, grandpa, im_online, authority_discovery) = keys.clone();
+		let output = match self.chain {
+			RuntimeVariant::Crab => to_json(
+				phrase,
+				keys,
+				crab_session_keys(babe, grandpa, im_online, authority_discovery),
+			),
+			RuntimeVariant::Darwinia => to_json(
+				phrase,
+				keys,
+				darwinia_session_keys(babe, grandpa, im_online, authority_discovery),
+			),
+		};
+
+		println!(
+			"{}",
+			serde_json::to_string_pretty(&output).map_err(|e| format!("{}", e))?
+		);
+
+		Ok(())
+	}
+}
+
+fn to_json<SessionKeys>(
+	phrase: Option<String>,
+	keys: AuthorityKeys,
+	session_keys: SessionKeys,
+) -> Value
+where
+	SessionKeys: Encode + Serialize,
+{
+	let account = |account: &AccountId| {
+		json!({
+			"ss58": account.to_ss58check(),
+			"hex": bytes::to_hex(account.as_ref(), false),
+		})
+	};
+	let (stash, controller, babe, grandpa, im_online, authority_discovery) = keys;
+
+	json!({
+		"secret_phrase": phrase,
+		"stash": account(&stash),
+		"controller": account(&controller),
+		"session_keys": {
+			"babe": bytes::to_hex(babe.as_ref(), false),
+			"grandpa": bytes::to_hex(grandpa.as_ref(), false),
+			"im_online": bytes::to_hex(im_online.as_ref(), false),
+			"authority_discovery": bytes::to_hex(authority_discovery.as_ref(), false),
+		},
+		// The `keys` argument of `session.setKeys`
+		"set_keys": bytes::to_hex(&session_keys.encode(), false),
+		// An entry of `palletSession.keys` in a chain spec
+		"chain_spec": [stash, stash, session_keys],
+	})
+}
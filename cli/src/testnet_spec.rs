@@ -0,0 +1,73 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! Generation of local testnet chain specs from a declarative description.
+
+// --- std ---
+use std::{fs, path::PathBuf};
+// --- crates ---
+use structopt::StructOpt;
+// --- substrate ---
+use sc_service::ChainSpec;
+// --- darwinia ---
+use crate::cli::parse_runtime_variant;
+use darwinia_service::{
+	chain_spec::{self, TestnetDescription},
+	service::RuntimeVariant,
+};
+
+/// The `testnet-spec` command used to build a raw chain spec from a testnet description.
+#[derive(Debug, StructOpt)]
+pub struct TestnetSpecCmd {
+	/// The runtime of the testnet, `crab` or `darwinia`.
+	#[structopt(long, value_name = "CHAIN", parse(try_from_str = parse_runtime_variant))]
+	pub chain: RuntimeVariant,
+
+	/// The `.toml` or `.json` description of the testnet.
+	#[structopt(value_name = "DESCRIPTION", parse(from_os_str))]
+	pub description: PathBuf,
+
+	/// Write the chain spec to this file instead of stdout.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	pub output: Option<PathBuf>,
+}
+
+impl TestnetSpecCmd {
+	/// Run the testnet-spec command.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		// Accept the SS58 addresses of the chain in the description
+		sp_core::crypto::set_default_ss58_version(self.chain.ss58_format());
+
+		let description = TestnetDescription::from_file(&self.description)?;
+		let chain_spec: Box<dyn ChainSpec> = match self.chain {
+			RuntimeVariant::Crab => Box::new(chain_spec::crab_testnet_config(&description)?),
+			RuntimeVariant::Darwinia => {
+				Box::new(chain_spec::darwinia_testnet_config(&description)?)
+			}
+		};
+		let json = chain_spec.as_json(true)?;
+
+		if let Some(output) = &self.output {
+			fs::write(output, json)?;
+		} else {
+			println!("{}", json);
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,195 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! Offline runtime upgrade rehearsal against the state of the local database.
+
+// --- std ---
+use std::{collections::BTreeMap, fs, path::PathBuf, sync::Arc};
+// --- crates ---
+use log::info;
+use serde_json::json;
+use structopt::StructOpt;
+// --- substrate ---
+use sc_cli::{BlockNumberOrHash, CliConfiguration, ImportParams, SharedParams};
+use sc_client_api::{StorageProvider, UsageProvider};
+use sc_executor::WasmExecutionMethod;
+use sp_core::{bytes, hashing::blake2_256, storage::well_known_keys};
+use sp_runtime::generic::BlockId;
+use sp_version::RuntimeVersion;
+// --- darwinia ---
+use darwinia_primitives::OpaqueBlock as Block;
+use darwinia_service::{
+	chain_spec::metadata::StoragePrefixes,
+	service::{self, dry_run},
+};
+
+/// The `upgrade-dry-run` command used to rehearse a runtime upgrade without a live node.
+#[derive(Debug, StructOpt)]
+pub struct UpgradeDryRunCmd {
+	/// The wasm runtime to upgrade to, built with the `try-runtime` feature.
+	#[structopt(value_name = "WASM", parse(from_os_str))]
+	pub wasm: PathBuf,
+
+	/// Upgrade the state of this block of the local database, the best block by default.
+	#[structopt(long, value_name = "HASH or NUMBER")]
+	pub at: Option<BlockNumberOrHash>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub import_params: ImportParams,
+}
+
+impl UpgradeDryRunCmd {
+	/// Run the upgrade-dry-run command.
+	///
+	/// The state is copied out of the database, which is never written to.
+	pub fn run<C, BE>(
+		&self,
+		client: Arc<C>,
+		wasm_method: WasmExecutionMethod,
+		heap_pages: Option<u64>,
+	) -> sc_cli::Result<()>
+	where
+		C: StorageProvider<Block, BE> + UsageProvider<Block>,
+		BE: sc_client_api::Backend<Block>,
+	{
+		let code = fs::read(&self.wasm)?;
+		let id = match &self.at {
+			Some(at) => at.parse::<Block>()?,
+			None => BlockId::Hash(client.usage_info().chain.best_hash),
+		};
+
+		info!("Exporting the state at {}", id);
+
+		let storage = sc_service::chain_ops::export_raw_state(client, &id)?;
+		let from = storage
+			.top
+			.get(well_known_keys::CODE)
+			.map(|code| service::runtime_version_of(code))
+			.transpose()?;
+		let to = service::runtime_version_of(&code)?;
+		let code_hash = bytes::to_hex(&blake2_256(&code), false);
+
+		info!("Running the upgrade to {}", version_string(&to));
+
+		let dry_run = dry_run::dry_run_runtime_upgrade(storage, code, wasm_method, heap_pages)?;
+		let prefixes = dry_run.metadata.map(StoragePrefixes::new).transpose()?;
+		let mut pallets = BTreeMap::<String, [u32; 3]>::new();
+
+		for (key, change) in &dry_run.changes {
+			let pallet = if key.starts_with(b":") {
+				"well_known".into()
+			} else {
+				prefixes
+					.as_ref()
+					.and_then(|prefixes| prefixes.pallet_of(key))
+					.map(ToString::to_string)
+					.unwrap_or_else(|| bytes::to_hex(&key[..key.len().min(16)], false))
+			};
+
+			count(pallets.entry(pallet).or_default(), change);
+		}
+
+		let child_tries = dry_run
+			.child_changes
+			.iter()
+			.map(|(storage_key, changes)| {
+				let mut counts = [0; 3];
+
+				changes
+					.values()
+					.for_each(|change| count(&mut counts, change));
+
+				let [added, removed, changed] = counts;
+
+				json!({
+					"storage_key": bytes::to_hex(storage_key, false),
+					"added": added,
+					"removed": removed,
+					"changed": changed,
+				})
+			})
+			.collect::<Vec<_>>();
+		let (weight, error) = match &dry_run.result {
+			Ok((weight, max_weight)) => {
+				(Some(json!({ "consumed": weight, "max": max_weight })), None)
+			}
+			Err(e) => (None, Some(e)),
+		};
+		let report = json!({
+			"at": format!("{}", id),
+			"from": from.as_ref().map(version_string),
+			"to": version_string(&to),
+			"code_hash": code_hash,
+			"succeeded": error.is_none(),
+			// The panic message or the `pre_upgrade`/`post_upgrade` error
+			"error": error,
+			"weight": weight,
+			"heap_pages": dry_run.heap_pages,
+			"pallets": pallets
+				.into_iter()
+				.map(|(pallet, [added, removed, changed])| {
+					json!({
+						"pallet": pallet,
+						"added": added,
+						"removed": removed,
+						"changed": changed,
+					})
+				})
+				.collect::<Vec<_>>(),
+			"child_tries": child_tries,
+		});
+
+		println!(
+			"{}",
+			serde_json::to_string_pretty(&report).map_err(|e| format!("{}", e))?
+		);
+
+		dry_run
+			.result
+			.map(|_| ())
+			.map_err(|e| format!("The runtime upgrade failed: {}", e).into())
+	}
+}
+
+impl CliConfiguration for UpgradeDryRunCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
+
+/// Count a storage change as added, removed or changed.
+fn count(counts: &mut [u32; 3], (before, after): &(Option<Vec<u8>>, Option<Vec<u8>>)) {
+	match (before, after) {
+		(None, Some(_)) => counts[0] += 1,
+		(Some(_), None) => counts[1] += 1,
+		_ => counts[2] += 1,
+	}
+}
+
+fn version_string(version: &RuntimeVersion) -> String {
+	format!("{} v{}", version.spec_name, version.spec_version)
+}
@@ -0,0 +1,312 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! Verification of the shipped genesis against the one rebuilt from the genesis resources.
+
+// --- std ---
+use std::collections::{BTreeMap, BTreeSet};
+// --- crates ---
+use serde_json::{json, Value};
+use structopt::StructOpt;
+// --- substrate ---
+use frame_system::AccountInfo;
+use sp_core::{
+	bytes,
+	crypto::AccountId32,
+	hashing::blake2_256,
+	storage::{well_known_keys, Storage},
+};
+use sp_runtime::BuildStorage;
+// --- darwinia ---
+use crate::{
+	cli::parse_runtime_variant,
+	decode::{decode_all, Decoder},
+};
+use darwinia_service::{
+	chain_spec::{self, metadata::StoragePrefixes},
+	service::{crab::crab_runtime, darwinia::darwinia_runtime, RuntimeVariant},
+};
+
+/// The pallets whose genesis is rebuilt from the genesis resources, next to `System::Account`.
+///
+/// The storage of every other pallet, such as the genesis authorities or the `System` block
+/// items, differs between the launch and the current runtime, it is reported but not verified.
+const VERIFIED_PALLETS: &[&str] = &[
+	"Balances",
+	"Kton",
+	"Vesting",
+	"CrabBacking",
+	"EthereumBacking",
+	"TronBacking",
+];
+
+/// The `verify-genesis` command used to check the shipped chain spec against its sources.
+///
+/// The genesis matches if the accounts, balances, vesting and backing storage, see
+/// `VERIFIED_PALLETS`, are the same, and the `:code` too with `--compare-code`. The state roots
+/// and genesis hashes of the whole storage are reported along.
+///
+/// The genesis resources are read relatively to the working directory, so this must run from the
+/// root of the repository.
+#[derive(Debug, StructOpt)]
+pub struct VerifyGenesisCmd {
+	/// The chain to verify, `crab` or `darwinia`.
+	#[structopt(long, value_name = "CHAIN", parse(try_from_str = parse_runtime_variant))]
+	pub chain: RuntimeVariant,
+
+	/// Compare the genesis `:code` too.
+	///
+	/// By default the `:code` is left out, since the native runtime has been upgraded since the
+	/// launch: the shipped genesis is compared to the one rebuilt with the shipped runtime, which
+	/// is reported apart from the genuinely rebuilt one.
+	#[structopt(long)]
+	pub compare_code: bool,
+
+	/// List at most this many differing accounts.
+	#[structopt(long, value_name = "COUNT", default_value = "100")]
+	pub max_accounts: usize,
+}
+
+impl VerifyGenesisCmd {
+	/// Run the verify-genesis command.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		sp_core::crypto::set_default_ss58_version(self.chain.ss58_format());
+
+		let report = match self.chain {
+			RuntimeVariant::Crab => {
+				let shipped = chain_spec::crab_config()?.build_storage()?;
+				let rebuilt = chain_spec::crab::crab_build_spec_genesis().build_storage()?;
+
+				self.verify::<crab_runtime::Runtime>(
+					shipped,
+					rebuilt,
+					StoragePrefixes::new(crab_runtime::Runtime::metadata())?,
+					json!({}),
+				)?
+			}
+			RuntimeVariant::Darwinia => {
+				let shipped = chain_spec::darwinia_config()?.build_storage()?;
+				let (genesis, swapped_ring_for_crab) =
+					chain_spec::darwinia::darwinia_build_spec_genesis_with_swap();
+				let rebuilt = genesis.build_storage()?;
+				let ethereum_backing = &genesis.darwinia_ethereum_backing;
+				let tron_backing = &genesis.darwinia_tron_backing;
+
+				self.verify::<darwinia_runtime::Runtime>(
+					shipped,
+					rebuilt,
+					StoragePrefixes::new(darwinia_runtime::Runtime::metadata())?,
+					json!({
+						"swapped_ring_for_crab": swapped_ring_for_crab.to_string(),
+						"ethereum_backing": {
+							"ring_locked": ethereum_backing.ring_locked.to_string(),
+							"kton_locked": ethereum_backing.kton_locked.to_string(),
+						},
+						"tron_backing": {
+							"backed_ring": tron_backing.backed_ring.to_string(),
+							"backed_kton": tron_backing.backed_kton.to_string(),
+						},
+					}),
+				)?
+			}
+		};
+		let matches = report["matches"] == Value::Bool(true);
+
+		println!(
+			"{}",
+			serde_json::to_string_pretty(&report).map_err(|e| format!("{}", e))?
+		);
+
+		if matches {
+			Ok(())
+		} else {
+			Err("The rebuilt genesis does not match the shipped chain spec".into())
+		}
+	}
+
+	fn verify<R>(
+		&self,
+		shipped: Storage,
+		rebuilt: Storage,
+		prefixes: StoragePrefixes,
+		mut totals: Value,
+	) -> Result<Value, String>
+	where
+		R: frame_system::Config<AccountId = AccountId32>,
+	{
+		let decoder = Decoder::new(self.chain.ss58_format());
+		let code_hash = |storage: &Storage| {
+			storage
+				.top
+				.get(well_known_keys::CODE)
+				.map(|code| bytes::to_hex(&blake2_256(code), false))
+		};
+		let code = json!({
+			"shipped_hash": code_hash(&shipped),
+			"rebuilt_hash": code_hash(&rebuilt),
+			"compared": self.compare_code,
+		});
+
+		let (shipped_root, shipped_hash) = chain_spec::genesis_state_root_and_hash(&shipped);
+		let (rebuilt_root, rebuilt_hash) = chain_spec::genesis_state_root_and_hash(&rebuilt);
+		// The genesis rebuilt with the shipped runtime, to compare everything but the `:code`
+		let code_substituted = if self.compare_code {
+			None
+		} else {
+			let mut rebuilt = rebuilt.clone();
+
+			if let Some(code) = shipped.top.get(well_known_keys::CODE) {
+				rebuilt
+					.top
+					.insert(well_known_keys::CODE.to_vec(), code.to_owned());
+			}
+
+			Some(chain_spec::genesis_state_root_and_hash(&rebuilt))
+		};
+		let genesis_hash_matches = match code_substituted {
+			Some((_, hash)) => shipped_hash == hash,
+			None => shipped_hash == rebuilt_hash,
+		};
+		let keys = shipped
+			.top
+			.keys()
+			.chain(rebuilt.top.keys())
+			.collect::<BTreeSet<_>>();
+		let account_prefix = prefixes.item_key("System", "Account")?;
+		let mut pallets = BTreeMap::<String, [u32; 3]>::new();
+		let mut accounts = vec![];
+		let mut differing_accounts = 0;
+		let mut differing_verified = 0;
+
+		for key in keys {
+			let (shipped_value, rebuilt_value) = (shipped.top.get(key), rebuilt.top.get(key));
+
+			if shipped_value == rebuilt_value
+				|| (!self.compare_code && key.as_slice() == well_known_keys::CODE)
+			{
+				continue;
+			}
+
+			let pallet = if key.starts_with(b":") {
+				"well_known".into()
+			} else {
+				prefixes
+					.pallet_of(key)
+					.map(ToString::to_string)
+					.unwrap_or_else(|| bytes::to_hex(&key[..key.len().min(16)], false))
+			};
+
+			if key.starts_with(&account_prefix)
+				|| key.as_slice() == well_known_keys::CODE
+				|| VERIFIED_PALLETS.contains(&pallet.as_str())
+			{
+				differing_verified += 1;
+			}
+
+			let counts = pallets.entry(pallet).or_default();
+
+			match (shipped_value, rebuilt_value) {
+				(Some(_), None) => counts[0] += 1,
+				(None, Some(_)) => counts[1] += 1,
+				_ => counts[2] += 1,
+			}
+
+			// `Blake2_128Concat` hashed `AccountId`
+			if key.starts_with(&account_prefix) && key.len() == account_prefix.len() + 16 + 32 {
+				differing_accounts += 1;
+
+				if accounts.len() < self.max_accounts {
+					let account = decode_all::<AccountId32>(&key[account_prefix.len() + 16..])?;
+					let info = |value: Option<&Vec<u8>>| {
+						value
+							.map(|v| decode_all::<AccountInfo<R::Index, R::AccountData>>(v))
+							.transpose()
+							.map(|info| info.map(|info| decoder.debug(&info)))
+					};
+
+					accounts.push(json!({
+						"account": decoder.address(&account),
+						"shipped": info(shipped_value)?,
+						"rebuilt": info(rebuilt_value)?,
+					}));
+				}
+			}
+		}
+
+		let total_issuance = |storage: &Storage, pallet: &str| {
+			prefixes
+				.item_key(pallet, "TotalIssuance")
+				.ok()
+				.and_then(|key| storage.top.get(&key))
+				.map(|v| decode_all::<u128>(v))
+				.transpose()
+				.map(|issuance| issuance.map(|issuance| issuance.to_string()))
+		};
+
+		totals["ring_issuance"] = json!({
+			"shipped": total_issuance(&shipped, "Balances")?,
+			"rebuilt": total_issuance(&rebuilt, "Balances")?,
+		});
+		totals["kton_issuance"] = json!({
+			"shipped": total_issuance(&shipped, "Kton")?,
+			"rebuilt": total_issuance(&rebuilt, "Kton")?,
+		});
+
+		Ok(json!({
+			// Of the accounts, balances, vesting and backing storage only
+			"matches": differing_verified == 0,
+			"verified_pallets": VERIFIED_PALLETS
+				.iter()
+				.filter(|pallet| prefixes.contains(pallet))
+				.collect::<Vec<_>>(),
+			"genesis_hash_matches": genesis_hash_matches,
+			"shipped": {
+				"state_root": format!("{:?}", shipped_root),
+				"genesis_hash": format!("{:?}", shipped_hash),
+			},
+			"rebuilt": {
+				"state_root": format!("{:?}", rebuilt_root),
+				"genesis_hash": format!("{:?}", rebuilt_hash),
+			},
+			// Compared to the shipped genesis unless `--compare-code`
+			"rebuilt_with_shipped_code": code_substituted.map(|(root, hash)| {
+				json!({
+					"state_root": format!("{:?}", root),
+					"genesis_hash": format!("{:?}", hash),
+				})
+			}),
+			"code": code,
+			"pallets": pallets
+				.into_iter()
+				.map(|(pallet, [missing, unexpected, changed])| {
+					json!({
+						"pallet": pallet,
+						// In the shipped spec only
+						"missing": missing,
+						// In the rebuilt genesis only
+						"unexpected": unexpected,
+						"changed": changed,
+					})
+				})
+				.collect::<Vec<_>>(),
+			"accounts": accounts,
+			"differing_accounts": differing_accounts,
+			"totals": totals,
+		}))
+	}
+}
@@ -0,0 +1,632 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! An embedded SQLite index of the events explorers look for, see `--indexer`.
+//!
+//! The node writes the events of each finalized block in a single transaction, the `indexer_*`
+//! RPCs read them. Accounts are SS58 strings, DVM addresses and hashes `0x` prefixed hex, and
+//! amounts decimal strings, as the balances do not fit in the SQLite integers.
+
+// --- std ---
+use std::path::Path;
+// --- crates ---
+use parking_lot::Mutex;
+use rusqlite::{params, types::ToSql, Connection, Row, Transaction};
+use serde::{Deserialize, Serialize};
+
+pub use rusqlite::Error;
+
+/// The result of the indexer operations.
+pub type Result<T> = rusqlite::Result<T>;
+
+/// The rows a query returns when it gives no limit.
+pub const DEFAULT_LIMIT: u32 = 100;
+/// The most rows a query returns.
+pub const MAX_LIMIT: u32 = 1000;
+
+const SCHEMA: &str = "
+	CREATE TABLE IF NOT EXISTS blocks (
+		number INTEGER PRIMARY KEY,
+		hash TEXT NOT NULL
+	);
+	CREATE TABLE IF NOT EXISTS transfers (
+		block_number INTEGER NOT NULL,
+		block_hash TEXT NOT NULL,
+		extrinsic_index INTEGER,
+		event_index INTEGER NOT NULL,
+		token TEXT NOT NULL,
+		from_account TEXT NOT NULL,
+		to_account TEXT NOT NULL,
+		amount TEXT NOT NULL
+	);
+	CREATE INDEX IF NOT EXISTS transfers_from ON transfers (from_account, block_number);
+	CREATE INDEX IF NOT EXISTS transfers_to ON transfers (to_account, block_number);
+	CREATE TABLE IF NOT EXISTS staking_rewards (
+		block_number INTEGER NOT NULL,
+		block_hash TEXT NOT NULL,
+		extrinsic_index INTEGER,
+		event_index INTEGER NOT NULL,
+		stash TEXT NOT NULL,
+		amount TEXT NOT NULL
+	);
+	CREATE INDEX IF NOT EXISTS staking_rewards_stash ON staking_rewards (stash, block_number);
+	CREATE TABLE IF NOT EXISTS staking_slashes (
+		block_number INTEGER NOT NULL,
+		block_hash TEXT NOT NULL,
+		extrinsic_index INTEGER,
+		event_index INTEGER NOT NULL,
+		account TEXT NOT NULL,
+		ring TEXT NOT NULL,
+		kton TEXT NOT NULL
+	);
+	CREATE INDEX IF NOT EXISTS staking_slashes_account ON staking_slashes (account, block_number);
+	CREATE TABLE IF NOT EXISTS treasury_spends (
+		block_number INTEGER NOT NULL,
+		block_hash TEXT NOT NULL,
+		extrinsic_index INTEGER,
+		event_index INTEGER NOT NULL,
+		proposal_index INTEGER NOT NULL,
+		beneficiary TEXT NOT NULL,
+		ring TEXT NOT NULL,
+		kton TEXT NOT NULL
+	);
+	CREATE INDEX IF NOT EXISTS treasury_spends_beneficiary
+		ON treasury_spends (beneficiary, block_number);
+	CREATE TABLE IF NOT EXISTS bridge_events (
+		block_number INTEGER NOT NULL,
+		block_hash TEXT NOT NULL,
+		extrinsic_index INTEGER,
+		event_index INTEGER NOT NULL,
+		action TEXT NOT NULL,
+		token TEXT NOT NULL,
+		account TEXT NOT NULL,
+		amount TEXT NOT NULL
+	);
+	CREATE INDEX IF NOT EXISTS bridge_events_account ON bridge_events (account, block_number);
+	CREATE TABLE IF NOT EXISTS dvm_transactions (
+		block_number INTEGER NOT NULL,
+		block_hash TEXT NOT NULL,
+		transaction_index INTEGER NOT NULL,
+		transaction_hash TEXT NOT NULL,
+		from_address TEXT NOT NULL,
+		to_address TEXT,
+		contract_address TEXT
+	);
+	CREATE INDEX IF NOT EXISTS dvm_transactions_from
+		ON dvm_transactions (from_address, block_number);
+	CREATE INDEX IF NOT EXISTS dvm_transactions_to ON dvm_transactions (to_address, block_number);
+	CREATE INDEX IF NOT EXISTS dvm_transactions_contract
+		ON dvm_transactions (contract_address, block_number);
+";
+
+/// The two tokens of the chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Token {
+	/// *RING*, the `Balances` instance.
+	Ring,
+	/// *KTON*, the `Kton` instance.
+	Kton,
+}
+impl Token {
+	fn as_str(&self) -> &'static str {
+		match self {
+			Token::Ring => "ring",
+			Token::Kton => "kton",
+		}
+	}
+
+	fn from_row(row: &Row, index: usize) -> Result<Self> {
+		match row.get::<_, String>(index)?.as_str() {
+			"kton" => Ok(Token::Kton),
+			_ => Ok(Token::Ring),
+		}
+	}
+}
+
+/// The way tokens crossed a bridge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BridgeAction {
+	/// Locked on this chain, to be issued on the other one.
+	Lock,
+	/// Redeemed on this chain, after being locked on the other one.
+	Redeem,
+}
+impl BridgeAction {
+	fn as_str(&self) -> &'static str {
+		match self {
+			BridgeAction::Lock => "lock",
+			BridgeAction::Redeem => "redeem",
+		}
+	}
+
+	fn from_row(row: &Row, index: usize) -> Result<Self> {
+		match row.get::<_, String>(index)?.as_str() {
+			"redeem" => Ok(BridgeAction::Redeem),
+			_ => Ok(BridgeAction::Lock),
+		}
+	}
+}
+
+/// An indexed runtime event, with the data explorers need.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum Event {
+	/// A transfer of *RING* or *KTON*.
+	#[serde(rename_all = "camelCase")]
+	Transfer {
+		token: Token,
+		from: String,
+		to: String,
+		amount: String,
+	},
+	/// The staking reward of a stash.
+	#[serde(rename_all = "camelCase")]
+	StakingReward { stash: String, amount: String },
+	/// The staking slash of a validator or of a nominator.
+	#[serde(rename_all = "camelCase")]
+	StakingSlash {
+		account: String,
+		ring: String,
+		kton: String,
+	},
+	/// An awarded treasury proposal.
+	#[serde(rename_all = "camelCase")]
+	TreasurySpend {
+		proposal_index: u32,
+		beneficiary: String,
+		ring: String,
+		kton: String,
+	},
+	/// Tokens locked or redeemed through a bridge.
+	#[serde(rename_all = "camelCase")]
+	Bridge {
+		action: BridgeAction,
+		token: Token,
+		account: String,
+		amount: String,
+	},
+}
+
+/// Where an event was emitted.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventLocation {
+	/// The number of the block.
+	pub block_number: u32,
+	/// The hash of the block.
+	pub block_hash: String,
+	/// The extrinsic which emitted the event, `None` for the block initialization and
+	/// finalization.
+	pub extrinsic_index: Option<u32>,
+	/// The index of the event in the block.
+	pub event_index: u32,
+}
+impl EventLocation {
+	fn from_row(row: &Row) -> Result<Self> {
+		Ok(Self {
+			block_number: row.get(0)?,
+			block_hash: row.get(1)?,
+			extrinsic_index: row.get(2)?,
+			event_index: row.get(3)?,
+		})
+	}
+}
+
+/// An indexed event with its location.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct IndexedEvent {
+	/// Where the event was emitted.
+	#[serde(flatten)]
+	pub location: EventLocation,
+	/// The event.
+	#[serde(flatten)]
+	pub event: Event,
+}
+
+/// An indexed DVM transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DvmTransaction {
+	/// The number of the block.
+	pub block_number: u32,
+	/// The hash of the block.
+	pub block_hash: String,
+	/// The index of the transaction in the Ethereum block.
+	pub transaction_index: u32,
+	/// The hash of the transaction.
+	pub transaction_hash: String,
+	/// The sender.
+	pub from: String,
+	/// The receiver, `None` for a contract creation.
+	pub to: Option<String>,
+	/// The created contract, if any.
+	pub contract_address: Option<String>,
+}
+
+/// The page of a query, the newest rows first.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Query {
+	/// Only the rows of the blocks before this one.
+	pub before_block: Option<u32>,
+	/// The number of rows, `DEFAULT_LIMIT` by default and `MAX_LIMIT` at most.
+	pub limit: Option<u32>,
+}
+impl Query {
+	fn before_block(&self) -> u32 {
+		self.before_block.unwrap_or(u32::MAX)
+	}
+
+	fn limit(&self) -> u32 {
+		self.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT)
+	}
+}
+
+/// The indexer database.
+pub struct Indexer {
+	connection: Mutex<Connection>,
+}
+impl Indexer {
+	/// Open, or create, the database at `path`.
+	pub fn open(path: &Path) -> Result<Self> {
+		Self::init(Connection::open(path)?)
+	}
+
+	/// Open a database in memory, gone once dropped.
+	pub fn open_in_memory() -> Result<Self> {
+		Self::init(Connection::open_in_memory()?)
+	}
+
+	fn init(connection: Connection) -> Result<Self> {
+		// The RPCs read while the node writes
+		connection.pragma_update(None, "journal_mode", &"WAL")?;
+		connection.execute_batch(SCHEMA)?;
+
+		Ok(Self {
+			connection: Mutex::new(connection),
+		})
+	}
+
+	/// The number of the last indexed block, `None` if none.
+	pub fn last_indexed_block(&self) -> Result<Option<u32>> {
+		self.connection
+			.lock()
+			.query_row("SELECT MAX(number) FROM blocks", params![], |row| {
+				row.get(0)
+			})
+	}
+
+	/// Write the events and the DVM transactions of a block, all or nothing.
+	pub fn insert_block(
+		&self,
+		number: u32,
+		hash: &str,
+		events: &[IndexedEvent],
+		dvm_transactions: &[DvmTransaction],
+	) -> Result<()> {
+		let mut connection = self.connection.lock();
+		let transaction = connection.transaction()?;
+
+		transaction.execute(
+			"INSERT OR REPLACE INTO blocks (number, hash) VALUES (?1, ?2)",
+			params![number, hash],
+		)?;
+
+		for event in events {
+			insert_event(&transaction, event)?;
+		}
+		for dvm_transaction in dvm_transactions {
+			transaction.execute(
+				"INSERT INTO dvm_transactions VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+				params![
+					dvm_transaction.block_number,
+					dvm_transaction.block_hash,
+					dvm_transaction.transaction_index,
+					dvm_transaction.transaction_hash,
+					dvm_transaction.from,
+					dvm_transaction.to,
+					dvm_transaction.contract_address,
+				],
+			)?;
+		}
+
+		transaction.commit()
+	}
+
+	/// The transfers from or to `account`.
+	pub fn transfers(&self, account: &str, query: Query) -> Result<Vec<IndexedEvent>> {
+		self.query(
+			"SELECT * FROM transfers
+			WHERE (from_account = ?1 OR to_account = ?1) AND block_number < ?2
+			ORDER BY block_number DESC, event_index DESC LIMIT ?3",
+			params![account, query.before_block(), query.limit()],
+			|row| {
+				Ok(Event::Transfer {
+					token: Token::from_row(row, 4)?,
+					from: row.get(5)?,
+					to: row.get(6)?,
+					amount: row.get(7)?,
+				})
+			},
+		)
+	}
+
+	/// The staking rewards of `stash`.
+	pub fn staking_rewards(&self, stash: &str, query: Query) -> Result<Vec<IndexedEvent>> {
+		self.query(
+			"SELECT * FROM staking_rewards
+			WHERE stash = ?1 AND block_number < ?2
+			ORDER BY block_number DESC, event_index DESC LIMIT ?3",
+			params![stash, query.before_block(), query.limit()],
+			|row| {
+				Ok(Event::StakingReward {
+					stash: row.get(4)?,
+					amount: row.get(5)?,
+				})
+			},
+		)
+	}
+
+	/// The staking slashes of `account`, or of every account.
+	pub fn staking_slashes(
+		&self,
+		account: Option<&str>,
+		query: Query,
+	) -> Result<Vec<IndexedEvent>> {
+		self.query(
+			"SELECT * FROM staking_slashes
+			WHERE (?1 IS NULL OR account = ?1) AND block_number < ?2
+			ORDER BY block_number DESC, event_index DESC LIMIT ?3",
+			params![account, query.before_block(), query.limit()],
+			|row| {
+				Ok(Event::StakingSlash {
+					account: row.get(4)?,
+					ring: row.get(5)?,
+					kton: row.get(6)?,
+				})
+			},
+		)
+	}
+
+	/// The treasury spends to `beneficiary`, or to every beneficiary.
+	pub fn treasury_spends(
+		&self,
+		beneficiary: Option<&str>,
+		query: Query,
+	) -> Result<Vec<IndexedEvent>> {
+		self.query(
+			"SELECT * FROM treasury_spends
+			WHERE (?1 IS NULL OR beneficiary = ?1) AND block_number < ?2
+			ORDER BY block_number DESC, event_index DESC LIMIT ?3",
+			params![beneficiary, query.before_block(), query.limit()],
+			|row| {
+				Ok(Event::TreasurySpend {
+					proposal_index: row.get(4)?,
+					beneficiary: row.get(5)?,
+					ring: row.get(6)?,
+					kton: row.get(7)?,
+				})
+			},
+		)
+	}
+
+	/// The bridge locks and redeems of `account`, or of every account.
+	pub fn bridge_events(&self, account: Option<&str>, query: Query) -> Result<Vec<IndexedEvent>> {
+		self.query(
+			"SELECT * FROM bridge_events
+			WHERE (?1 IS NULL OR account = ?1) AND block_number < ?2
+			ORDER BY block_number DESC, event_index DESC LIMIT ?3",
+			params![account, query.before_block(), query.limit()],
+			|row| {
+				Ok(Event::Bridge {
+					action: BridgeAction::from_row(row, 4)?,
+					token: Token::from_row(row, 5)?,
+					account: row.get(6)?,
+					amount: row.get(7)?,
+				})
+			},
+		)
+	}
+
+	/// The DVM transactions sent by, sent to or creating `address`.
+	pub fn dvm_transactions(&self, address: &str, query: Query) -> Result<Vec<DvmTransaction>> {
+		let connection = self.connection.lock();
+		let mut statement = connection.prepare_cached(
+			"SELECT * FROM dvm_transactions
+			WHERE (from_address = ?1 OR to_address = ?1 OR contract_address = ?1)
+				AND block_number < ?2
+			ORDER BY block_number DESC, transaction_index DESC LIMIT ?3",
+		)?;
+		let rows = statement.query_map(
+			params![address, query.before_block(), query.limit()],
+			|row| {
+				Ok(DvmTransaction {
+					block_number: row.get(0)?,
+					block_hash: row.get(1)?,
+					transaction_index: row.get(2)?,
+					transaction_hash: row.get(3)?,
+					from: row.get(4)?,
+					to: row.get(5)?,
+					contract_address: row.get(6)?,
+				})
+			},
+		)?;
+
+		rows.collect()
+	}
+
+	// Run a query of an event table, its first columns being the location of the event.
+	fn query(
+		&self,
+		sql: &str,
+		params: &[&dyn ToSql],
+		event: impl Fn(&Row) -> Result<Event>,
+	) -> Result<Vec<IndexedEvent>> {
+		let connection = self.connection.lock();
+		let mut statement = connection.prepare_cached(sql)?;
+		let rows = statement.query_map(params, |row| {
+			Ok(IndexedEvent {
+				location: EventLocation::from_row(row)?,
+				event: event(row)?,
+			})
+		})?;
+
+		rows.collect()
+	}
+}
+
+fn insert_event(transaction: &Transaction, indexed_event: &IndexedEvent) -> Result<()> {
+	let IndexedEvent { location, event } = indexed_event;
+	let location = params![
+		location.block_number,
+		location.block_hash,
+		location.extrinsic_index,
+		location.event_index,
+	];
+	let insert = |sql, fields: &[&dyn ToSql]| -> Result<()> {
+		transaction.execute(sql, location.iter().chain(fields))?;
+
+		Ok(())
+	};
+
+	match event {
+		Event::Transfer {
+			token,
+			from,
+			to,
+			amount,
+		} => insert(
+			"INSERT INTO transfers VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+			&[&token.as_str(), from, to, amount],
+		),
+		Event::StakingReward { stash, amount } => insert(
+			"INSERT INTO staking_rewards VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+			&[stash, amount],
+		),
+		Event::StakingSlash {
+			account,
+			ring,
+			kton,
+		} => insert(
+			"INSERT INTO staking_slashes VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+			&[account, ring, kton],
+		),
+		Event::TreasurySpend {
+			proposal_index,
+			beneficiary,
+			ring,
+			kton,
+		} => insert(
+			"INSERT INTO treasury_spends VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+			&[proposal_index, beneficiary, ring, kton],
+		),
+		Event::Bridge {
+			action,
+			token,
+			account,
+			amount,
+		} => insert(
+			"INSERT INTO bridge_events VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+			&[&action.as_str(), &token.as_str(), account, amount],
+		),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	// --- darwinia ---
+	use super::*;
+
+	fn location(block_number: u32, event_index: u32) -> EventLocation {
+		EventLocation {
+			block_number,
+			block_hash: format!("0x{:064x}", block_number),
+			extrinsic_index: Some(1),
+			event_index,
+		}
+	}
+
+	#[test]
+	fn index_and_query_should_work() {
+		let indexer = Indexer::open_in_memory().unwrap();
+		let transfer = |block_number, event_index, from: &str, to: &str| IndexedEvent {
+			location: location(block_number, event_index),
+			event: Event::Transfer {
+				token: Token::Kton,
+				from: from.into(),
+				to: to.into(),
+				// More than `i64::MAX`
+				amount: u128::MAX.to_string(),
+			},
+		};
+		let redeem = IndexedEvent {
+			location: location(2, 1),
+			event: Event::Bridge {
+				action: BridgeAction::Redeem,
+				token: Token::Ring,
+				account: "bob".into(),
+				amount: "1".into(),
+			},
+		};
+
+		assert_eq!(indexer.last_indexed_block().unwrap(), None);
+
+		indexer
+			.insert_block(1, "0x01", &[transfer(1, 0, "alice", "bob")], &[])
+			.unwrap();
+		indexer
+			.insert_block(
+				2,
+				"0x02",
+				&[transfer(2, 0, "bob", "charlie"), redeem.clone()],
+				&[],
+			)
+			.unwrap();
+
+		assert_eq!(indexer.last_indexed_block().unwrap(), Some(2));
+		assert_eq!(
+			indexer.transfers("bob", Query::default()).unwrap(),
+			vec![
+				transfer(2, 0, "bob", "charlie"),
+				transfer(1, 0, "alice", "bob")
+			]
+		);
+		assert_eq!(
+			indexer
+				.transfers(
+					"bob",
+					Query {
+						before_block: Some(2),
+						limit: None,
+					}
+				)
+				.unwrap(),
+			vec![transfer(1, 0, "alice", "bob")]
+		);
+		assert_eq!(
+			indexer.bridge_events(None, Query::default()).unwrap(),
+			vec![redeem]
+		);
+		assert!(indexer
+			.staking_rewards("alice", Query::default())
+			.unwrap()
+			.is_empty());
+	}
+}
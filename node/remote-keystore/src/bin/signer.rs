@@ -0,0 +1,82 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! The reference signer, serving the keys of a local keystore to the nodes started with
+//! `--keystore-uri`.
+
+// --- std ---
+use std::{fs, path::PathBuf, process, sync::Arc};
+// --- crates ---
+use log::{error, info};
+use structopt::StructOpt;
+// --- substrate ---
+use sc_keystore::LocalKeystore;
+use sp_core::crypto::SecretString;
+// --- darwinia ---
+use darwinia_remote_keystore::{Endpoint, Server};
+
+/// Serve the keys of a keystore to the nodes started with `--keystore-uri`.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "darwinia-signer")]
+struct Opt {
+	/// The keystore directory, laid out as the `--keystore-path` of a node.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	keystore_path: PathBuf,
+
+	/// Listen on `unix:<path>` or `http://<host>:<port>` of the loopback, given to the node as
+	/// `--keystore-uri`.
+	#[structopt(long, value_name = "URI", default_value = "unix:darwinia-signer.sock")]
+	listen: Endpoint,
+
+	/// File that contains the password of the keystore.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	password_filename: Option<PathBuf>,
+}
+
+fn main() {
+	env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+	if let Err(e) = run(Opt::from_args()) {
+		error!("{}", e);
+
+		process::exit(1);
+	}
+}
+
+fn run(opt: Opt) -> Result<(), String> {
+	// Read the same way as the `--password-filename` of the node
+	let password = opt
+		.password_filename
+		.as_ref()
+		.map(|path| fs::read_to_string(path).map(SecretString::new))
+		.transpose()
+		.map_err(|e| format!("Failed to read the password file: {}", e))?;
+	let keystore = LocalKeystore::open(&opt.keystore_path, password)
+		.map_err(|e| format!("Failed to open the keystore: {}", e))?;
+	let server = Server::bind(&opt.listen, Arc::new(keystore))
+		.map_err(|e| format!("Failed to listen on {:?}: {}", opt.listen, e))?;
+
+	info!(
+		"Serving the keys of {:?} on {:?}",
+		opt.keystore_path, opt.listen
+	);
+
+	server
+		.run()
+		.map_err(|e| format!("Failed to accept a connection: {}", e))
+}
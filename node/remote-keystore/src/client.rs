@@ -0,0 +1,492 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! The keystore of the node, forwarding every call to the signer.
+
+// --- std ---
+use std::{
+	io,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc,
+	},
+};
+// --- crates ---
+use async_std::task;
+use async_trait::async_trait;
+use log::{debug, error};
+use parking_lot::Mutex;
+use schnorrkel::vrf::{VRFOutput, VRFProof};
+use serde::de::DeserializeOwned;
+// --- substrate ---
+use sp_core::{
+	crypto::{CryptoTypeId, CryptoTypePublicPair, KeyTypeId},
+	ecdsa, ed25519, sr25519,
+};
+use sp_keystore::{
+	vrf::{VRFSignature, VRFTranscriptData, VRFTranscriptValue},
+	CryptoStore, Error, SyncCryptoStore,
+};
+// --- darwinia ---
+use crate::{
+	http::{self, Connection},
+	protocol::{
+		Call, PublicKey, Request, Response, Transcript, TranscriptValue, VrfSignature,
+		JSONRPC_VERSION,
+	},
+	Endpoint, LOG_TARGET,
+};
+
+/// A `CryptoStore` and `SyncCryptoStore` whose keys are held by the signer at an `Endpoint`.
+///
+/// The connection is opened again on the next call if the signer went away, the calls fail with
+/// `Error::Unavailable` in the meantime.
+#[derive(Clone)]
+pub struct RemoteKeystore(Arc<Signer>);
+impl RemoteKeystore {
+	/// Connect to the signer at `uri`, `unix:<path>` or `http://<host>:<port>`.
+	pub fn open(uri: &str) -> Result<Self, String> {
+		Self::connect(uri.parse()?)
+			.map_err(|e| format!("Failed to connect to the signer at `{}`: {}", uri, e))
+	}
+
+	/// Connect to the signer at `endpoint`.
+	pub fn connect(endpoint: Endpoint) -> io::Result<Self> {
+		let connection = endpoint.connect()?;
+
+		Ok(Self(Arc::new(Signer {
+			endpoint,
+			connection: Mutex::new(Some(connection)),
+			next_id: AtomicU64::new(0),
+		})))
+	}
+
+	fn call<T>(&self, call: Call) -> Result<T, Error>
+	where
+		T: DeserializeOwned,
+	{
+		let method = call.method();
+		let request = Request {
+			jsonrpc: JSONRPC_VERSION.into(),
+			id: self.0.next_id.fetch_add(1, Ordering::Relaxed),
+			call,
+		};
+		let response = self.0.exchange(&request).map_err(|e| {
+			error!(target: LOG_TARGET, "Failed to call `{}` on the signer: {}", method, e);
+
+			Error::Unavailable
+		})?;
+
+		if let Some(error) = response.error {
+			return Err(error.into());
+		}
+
+		serde_json::from_value(response.result.unwrap_or_default()).map_err(|e| {
+			Error::Other(format!(
+				"Invalid result of `{}` from the signer: {}",
+				method, e
+			))
+		})
+	}
+
+	/// Run a call on the blocking threads, the executor of the caller must not wait on the signer.
+	async fn blocking<F, R>(&self, f: F) -> R
+	where
+		F: 'static + Send + FnOnce(&Self) -> R,
+		R: 'static + Send,
+	{
+		let keystore = self.clone();
+
+		task::spawn_blocking(move || f(&keystore)).await
+	}
+
+	fn public_keys<P>(&self, key_type: KeyTypeId, crypto: CryptoTypeId) -> Vec<P>
+	where
+		P: Default + AsMut<[u8]>,
+	{
+		self.call::<Vec<sp_core::Bytes>>(Call::PublicKeys {
+			key_type: key_type.into(),
+			crypto: crypto.into(),
+		})
+		.and_then(|keys| keys.iter().map(|key| public_from_bytes(key)).collect())
+		.unwrap_or_else(|e| {
+			error!(
+				target: LOG_TARGET,
+				"Failed to list the keys of {:?}: {}", key_type, e
+			);
+
+			vec![]
+		})
+	}
+
+	fn generate_new<P>(
+		&self,
+		key_type: KeyTypeId,
+		crypto: CryptoTypeId,
+		seed: Option<&str>,
+	) -> Result<P, Error>
+	where
+		P: Default + AsMut<[u8]>,
+	{
+		let public = self.call::<sp_core::Bytes>(Call::GenerateNew {
+			key_type: key_type.into(),
+			crypto: crypto.into(),
+			seed: seed.map(Into::into),
+		})?;
+
+		public_from_bytes(&public)
+	}
+}
+impl SyncCryptoStore for RemoteKeystore {
+	fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+		self.public_keys(id, sr25519::CRYPTO_ID)
+	}
+
+	fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<sr25519::Public, Error> {
+		self.generate_new(id, sr25519::CRYPTO_ID, seed)
+	}
+
+	fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+		self.public_keys(id, ed25519::CRYPTO_ID)
+	}
+
+	fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ed25519::Public, Error> {
+		self.generate_new(id, ed25519::CRYPTO_ID, seed)
+	}
+
+	fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+		self.public_keys(id, ecdsa::CRYPTO_ID)
+	}
+
+	fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ecdsa::Public, Error> {
+		self.generate_new(id, ecdsa::CRYPTO_ID, seed)
+	}
+
+	fn insert_unknown(&self, key_type: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+		self.call(Call::InsertUnknown {
+			key_type: key_type.into(),
+			suri: suri.into(),
+			public: public.to_vec().into(),
+		})
+		.map_err(|e| {
+			error!(
+				target: LOG_TARGET,
+				"Failed to insert a key of {:?}: {}", key_type, e
+			)
+		})
+	}
+
+	fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+	) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		let known_keys = SyncCryptoStore::keys(self, id)?;
+
+		Ok(keys
+			.into_iter()
+			.filter(|key| known_keys.contains(key))
+			.collect())
+	}
+
+	fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		let keys = self.call::<Vec<PublicKey>>(Call::Keys {
+			key_type: id.into(),
+		})?;
+
+		Ok(keys.into_iter().map(Into::into).collect())
+	}
+
+	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		self.call(Call::HasKeys {
+			keys: public_keys
+				.iter()
+				.map(|(public, key_type)| (public.clone().into(), (*key_type).into()))
+				.collect(),
+		})
+		.unwrap_or_else(|e| {
+			error!(target: LOG_TARGET, "Failed to look up keys: {}", e);
+
+			false
+		})
+	}
+
+	fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> Result<Vec<u8>, Error> {
+		let signature = self.call::<sp_core::Bytes>(Call::Sign {
+			key_type: id.into(),
+			key: key.clone().into(),
+			message: msg.to_vec().into(),
+		})?;
+
+		Ok(signature.0)
+	}
+
+	fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		transcript_data: VRFTranscriptData,
+	) -> Result<VRFSignature, Error> {
+		let signature = self.call::<VrfSignature>(Call::Sr25519VrfSign {
+			key_type: key_type.into(),
+			public: public.0.to_vec().into(),
+			transcript: Transcript {
+				label: transcript_data.label.to_vec().into(),
+				items: transcript_data
+					.items
+					.into_iter()
+					.map(|(name, value)| {
+						let value = match value {
+							VRFTranscriptValue::Bytes(bytes) => {
+								TranscriptValue::Bytes(bytes.into())
+							}
+							VRFTranscriptValue::U64(number) => TranscriptValue::U64(number),
+						};
+
+						(name.into(), value)
+					})
+					.collect(),
+			},
+		})?;
+		let invalid = |e| Error::Other(format!("Invalid VRF signature from the signer: {}", e));
+
+		Ok(VRFSignature {
+			output: VRFOutput::from_bytes(&signature.output).map_err(invalid)?,
+			proof: VRFProof::from_bytes(&signature.proof).map_err(invalid)?,
+		})
+	}
+}
+#[async_trait]
+impl CryptoStore for RemoteKeystore {
+	async fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+		self.blocking(move |keystore| SyncCryptoStore::sr25519_public_keys(keystore, id))
+			.await
+	}
+
+	async fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<sr25519::Public, Error> {
+		let seed = seed.map(ToOwned::to_owned);
+
+		self.blocking(move |keystore| {
+			SyncCryptoStore::sr25519_generate_new(keystore, id, seed.as_deref())
+		})
+		.await
+	}
+
+	async fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+		self.blocking(move |keystore| SyncCryptoStore::ed25519_public_keys(keystore, id))
+			.await
+	}
+
+	async fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ed25519::Public, Error> {
+		let seed = seed.map(ToOwned::to_owned);
+
+		self.blocking(move |keystore| {
+			SyncCryptoStore::ed25519_generate_new(keystore, id, seed.as_deref())
+		})
+		.await
+	}
+
+	async fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+		self.blocking(move |keystore| SyncCryptoStore::ecdsa_public_keys(keystore, id))
+			.await
+	}
+
+	async fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ecdsa::Public, Error> {
+		let seed = seed.map(ToOwned::to_owned);
+
+		self.blocking(move |keystore| {
+			SyncCryptoStore::ecdsa_generate_new(keystore, id, seed.as_deref())
+		})
+		.await
+	}
+
+	async fn insert_unknown(&self, id: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+		let (suri, public) = (suri.to_owned(), public.to_vec());
+
+		self.blocking(move |keystore| SyncCryptoStore::insert_unknown(keystore, id, &suri, &public))
+			.await
+	}
+
+	async fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+	) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		self.blocking(move |keystore| SyncCryptoStore::supported_keys(keystore, id, keys))
+			.await
+	}
+
+	async fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		self.blocking(move |keystore| SyncCryptoStore::keys(keystore, id))
+			.await
+	}
+
+	async fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		let public_keys = public_keys.to_vec();
+
+		self.blocking(move |keystore| SyncCryptoStore::has_keys(keystore, &public_keys))
+			.await
+	}
+
+	async fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> Result<Vec<u8>, Error> {
+		let (key, msg) = (key.clone(), msg.to_vec());
+
+		self.blocking(move |keystore| SyncCryptoStore::sign_with(keystore, id, &key, &msg))
+			.await
+	}
+
+	async fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		transcript_data: VRFTranscriptData,
+	) -> Result<VRFSignature, Error> {
+		let public = *public;
+
+		self.blocking(move |keystore| {
+			SyncCryptoStore::sr25519_vrf_sign(keystore, key_type, &public, transcript_data)
+		})
+		.await
+	}
+}
+
+/// The connection to the signer, shared by the clones of a `RemoteKeystore`.
+struct Signer {
+	endpoint: Endpoint,
+	connection: Mutex<Option<Connection>>,
+	next_id: AtomicU64,
+}
+impl Signer {
+	fn exchange(&self, request: &Request) -> io::Result<Response> {
+		let mut connection = self.connection.lock();
+		let mut retried = false;
+
+		loop {
+			if connection.is_none() {
+				*connection = Some(self.endpoint.connect()?);
+			}
+
+			let result = send(
+				connection.as_mut().expect("connected right above; qed"),
+				request,
+			);
+
+			match result {
+				Ok(response) => return Ok(response),
+				Err(e) => {
+					*connection = None;
+
+					// The signer closed the connection before reading the request, e.g. it restarted,
+					// the request is sent again. A timed out request might be in progress, it is not.
+					let closed = matches!(
+						e.kind(),
+						io::ErrorKind::BrokenPipe
+							| io::ErrorKind::ConnectionReset
+							| io::ErrorKind::UnexpectedEof
+					);
+
+					if closed && !retried {
+						debug!(target: LOG_TARGET, "Reconnecting to the signer: {}", e);
+
+						retried = true;
+					} else {
+						return Err(e);
+					}
+				}
+			}
+		}
+	}
+}
+
+fn send(connection: &mut Connection, request: &Request) -> io::Result<Response> {
+	connection.write_request(&serde_json::to_vec(request)?)?;
+
+	let message = connection
+		.read()?
+		.ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+
+	if !message.start_line.starts_with("HTTP/1.1 200 ") {
+		return Err(http::invalid_data(format!(
+			"The signer answered `{}`",
+			message.start_line
+		)));
+	}
+
+	let response = serde_json::from_slice::<Response>(&message.body)?;
+
+	if response.id != Some(request.id) {
+		return Err(http::invalid_data(format!(
+			"Expect the response to {}, got {:?}",
+			request.id, response.id
+		)));
+	}
+
+	Ok(response)
+}
+
+fn public_from_bytes<P>(bytes: &[u8]) -> Result<P, Error>
+where
+	P: Default + AsMut<[u8]>,
+{
+	let mut public = P::default();
+
+	if public.as_mut().len() != bytes.len() {
+		return Err(Error::Other(format!(
+			"Invalid public key length {} from the signer",
+			bytes.len()
+		)));
+	}
+
+	public.as_mut().copy_from_slice(bytes);
+
+	Ok(public)
+}
@@ -0,0 +1,152 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! The HTTP/1.1 framing of the JSON-RPC messages, just what the keystore and the signer need.
+//!
+//! Every message carries a `Content-Length`, the connections are kept alive.
+
+// --- std ---
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+/// The longest start line or header line.
+const MAX_LINE: u64 = 8 * 1024;
+/// The most headers of a message.
+const MAX_HEADERS: usize = 64;
+/// The largest body of a message.
+const MAX_BODY: usize = 1024 * 1024;
+
+/// An HTTP request or response, without its headers.
+pub(crate) struct Message {
+	/// `POST / HTTP/1.1` or `HTTP/1.1 200 OK`.
+	pub start_line: String,
+	pub body: Vec<u8>,
+}
+
+/// A connection between the remote keystore and the signer.
+pub(crate) struct Connection {
+	reader: BufReader<Box<dyn Read + Send>>,
+	writer: Box<dyn Write + Send>,
+}
+impl Connection {
+	pub(crate) fn new<S>(reader: S, writer: S) -> Self
+	where
+		S: 'static + Read + Write + Send,
+	{
+		Self {
+			reader: BufReader::new(Box::new(reader)),
+			writer: Box::new(writer),
+		}
+	}
+
+	/// Read the next message, `None` once the peer closed the connection.
+	pub(crate) fn read(&mut self) -> io::Result<Option<Message>> {
+		let start_line = match self.read_line()? {
+			Some(line) => line,
+			None => return Ok(None),
+		};
+		let mut content_length = None;
+
+		for _ in 0..=MAX_HEADERS {
+			let line = self
+				.read_line()?
+				.ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+
+			if line.is_empty() {
+				let content_length = content_length.unwrap_or(0);
+
+				if content_length > MAX_BODY {
+					return Err(invalid_data(format!(
+						"The body of {} bytes is too large",
+						content_length
+					)));
+				}
+
+				let mut body = vec![0; content_length];
+
+				self.reader.read_exact(&mut body)?;
+
+				return Ok(Some(Message { start_line, body }));
+			}
+
+			let colon = line
+				.find(':')
+				.ok_or_else(|| invalid_data(format!("Invalid header `{}`", line)))?;
+			let (name, value) = (&line[..colon], line[colon + 1..].trim());
+
+			if name.eq_ignore_ascii_case("content-length") {
+				content_length =
+					Some(value.parse().map_err(|_| {
+						invalid_data(format!("Invalid `Content-Length` `{}`", value))
+					})?);
+			} else if name.eq_ignore_ascii_case("transfer-encoding") {
+				return Err(invalid_data(format!(
+					"Unsupported `Transfer-Encoding` `{}`",
+					value
+				)));
+			}
+		}
+
+		Err(invalid_data(format!("More than {} headers", MAX_HEADERS)))
+	}
+
+	/// Write a JSON-RPC request.
+	pub(crate) fn write_request(&mut self, body: &[u8]) -> io::Result<()> {
+		self.write("POST / HTTP/1.1\r\nHost: localhost", body)
+	}
+
+	/// Write a response, `status` as `200 OK`.
+	pub(crate) fn write_response(&mut self, status: &str, body: &[u8]) -> io::Result<()> {
+		self.write(&format!("HTTP/1.1 {}", status), body)
+	}
+
+	fn write(&mut self, head: &str, body: &[u8]) -> io::Result<()> {
+		let mut message = format!(
+			"{}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+			head,
+			body.len()
+		)
+		.into_bytes();
+
+		message.extend_from_slice(body);
+
+		self.writer.write_all(&message)?;
+		self.writer.flush()
+	}
+
+	/// Read a line without its line break, `None` at the end of the stream.
+	fn read_line(&mut self) -> io::Result<Option<String>> {
+		let mut line = String::new();
+
+		if (&mut self.reader).take(MAX_LINE).read_line(&mut line)? == 0 {
+			return Ok(None);
+		}
+		if !line.ends_with('\n') {
+			return if line.len() as u64 == MAX_LINE {
+				Err(invalid_data(format!("Line longer than {} bytes", MAX_LINE)))
+			} else {
+				Err(io::ErrorKind::UnexpectedEof.into())
+			};
+		}
+
+		Ok(Some(line.trim_end_matches(&['\r', '\n'][..]).into()))
+	}
+}
+
+pub(crate) fn invalid_data(error: String) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, error)
+}
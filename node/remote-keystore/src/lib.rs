@@ -0,0 +1,265 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! A keystore whose keys are held by a separate signer process, see `--keystore-uri`.
+//!
+//! The node only knows the public keys, every signature is made by the signer. They talk JSON-RPC
+//! over HTTP, on a Unix socket or on the loopback. Whoever can reach the signer can sign with its
+//! keys, the Unix socket should only be accessible to the node.
+
+pub mod protocol;
+
+mod client;
+mod http;
+mod server;
+
+pub use client::RemoteKeystore;
+pub use server::Server;
+
+// --- std ---
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::{io, net::TcpStream, path::PathBuf, str::FromStr, time::Duration};
+// --- darwinia ---
+use crate::http::Connection;
+
+/// The log target of this crate.
+const LOG_TARGET: &str = "remote-keystore";
+
+/// How long the remote keystore waits for the signer, a slot must not be missed on a dead signer.
+const SIGNER_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Where the signer listens.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Endpoint {
+	/// `unix:<path>`, a Unix socket.
+	Unix(PathBuf),
+	/// `http://<host>:<port>`, the signer only listens on the loopback.
+	Http(String),
+}
+impl Endpoint {
+	/// Connect to the signer.
+	pub(crate) fn connect(&self) -> io::Result<Connection> {
+		match self {
+			#[cfg(unix)]
+			Endpoint::Unix(path) => {
+				let stream = UnixStream::connect(path)?;
+
+				stream.set_read_timeout(Some(SIGNER_TIMEOUT))?;
+				stream.set_write_timeout(Some(SIGNER_TIMEOUT))?;
+
+				Ok(Connection::new(stream.try_clone()?, stream))
+			}
+			#[cfg(not(unix))]
+			Endpoint::Unix(_) => Err(unix_unsupported()),
+			Endpoint::Http(address) => {
+				let stream = TcpStream::connect(address)?;
+
+				stream.set_read_timeout(Some(SIGNER_TIMEOUT))?;
+				stream.set_write_timeout(Some(SIGNER_TIMEOUT))?;
+				stream.set_nodelay(true)?;
+
+				Ok(Connection::new(stream.try_clone()?, stream))
+			}
+		}
+	}
+}
+impl FromStr for Endpoint {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		if let Some(path) = s.strip_prefix("unix:") {
+			let path = path.strip_prefix("//").unwrap_or(path);
+
+			if !path.is_empty() {
+				return Ok(Endpoint::Unix(path.into()));
+			}
+		} else if let Some(address) = s.strip_prefix("http://") {
+			let address = address.strip_suffix('/').unwrap_or(address);
+
+			if !address.is_empty() && !address.contains('/') {
+				return Ok(Endpoint::Http(address.into()));
+			}
+		}
+
+		Err(format!(
+			"Invalid signer URI `{}`, expect `unix:<path>` or `http://<host>:<port>`",
+			s
+		))
+	}
+}
+
+#[cfg(not(unix))]
+fn unix_unsupported() -> io::Error {
+	io::Error::new(
+		io::ErrorKind::Other,
+		"Unix sockets are not supported on this platform",
+	)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+	// --- std ---
+	use std::{sync::Arc, thread};
+	// --- substrate ---
+	use sc_keystore::LocalKeystore;
+	use sp_core::{
+		crypto::{CryptoTypePublicPair, KeyTypeId, Pair, Public},
+		ed25519, sr25519,
+	};
+	use sp_keystore::{
+		vrf::{VRFTranscriptData, VRFTranscriptValue},
+		CryptoStore, SyncCryptoStore,
+	};
+	// --- darwinia ---
+	use super::*;
+
+	const KEY_TYPE: KeyTypeId = KeyTypeId(*b"test");
+
+	fn signer() -> (tempfile::TempDir, RemoteKeystore) {
+		let dir = tempfile::tempdir().unwrap();
+		let endpoint = Endpoint::Unix(dir.path().join("signer.sock"));
+		let server = Server::bind(&endpoint, Arc::new(LocalKeystore::in_memory())).unwrap();
+
+		thread::spawn(move || server.run());
+
+		let keystore = RemoteKeystore::connect(endpoint).unwrap();
+
+		(dir, keystore)
+	}
+
+	#[test]
+	fn endpoint_should_parse() {
+		assert_eq!(
+			"unix:///run/signer.sock".parse::<Endpoint>(),
+			Ok(Endpoint::Unix("/run/signer.sock".into()))
+		);
+		assert_eq!(
+			"unix:signer.sock".parse::<Endpoint>(),
+			Ok(Endpoint::Unix("signer.sock".into()))
+		);
+		assert_eq!(
+			"http://127.0.0.1:9955".parse::<Endpoint>(),
+			Ok(Endpoint::Http("127.0.0.1:9955".into()))
+		);
+		assert_eq!(
+			"http://localhost:9955/".parse::<Endpoint>(),
+			Ok(Endpoint::Http("localhost:9955".into()))
+		);
+		assert!("tcp://127.0.0.1:9955".parse::<Endpoint>().is_err());
+		assert!("http://127.0.0.1:9955/rpc".parse::<Endpoint>().is_err());
+		assert!("unix:".parse::<Endpoint>().is_err());
+	}
+
+	#[test]
+	fn signer_should_only_listen_on_the_loopback() {
+		let keystore = Arc::new(LocalKeystore::in_memory());
+
+		assert!(Server::bind(&Endpoint::Http("127.0.0.1:0".into()), keystore.clone()).is_ok());
+		assert_eq!(
+			Server::bind(&Endpoint::Http("0.0.0.0:0".into()), keystore)
+				.err()
+				.map(|e| e.kind()),
+			Some(io::ErrorKind::PermissionDenied)
+		);
+	}
+
+	#[test]
+	fn remote_keystore_should_sign_with_the_keys_of_the_signer() {
+		let (_dir, keystore) = signer();
+		let public = SyncCryptoStore::sr25519_generate_new(&keystore, KEY_TYPE, None).unwrap();
+		let key = CryptoTypePublicPair(sr25519::CRYPTO_ID, public.to_raw_vec());
+
+		assert_eq!(
+			SyncCryptoStore::sr25519_public_keys(&keystore, KEY_TYPE),
+			vec![public]
+		);
+		assert_eq!(
+			async_std::task::block_on(CryptoStore::sr25519_public_keys(&keystore, KEY_TYPE)),
+			vec![public]
+		);
+		assert_eq!(
+			SyncCryptoStore::keys(&keystore, KEY_TYPE).unwrap(),
+			vec![key.clone()]
+		);
+		assert!(SyncCryptoStore::has_keys(
+			&keystore,
+			&[(public.to_raw_vec(), KEY_TYPE)]
+		));
+
+		let signature = SyncCryptoStore::sign_with(&keystore, KEY_TYPE, &key, b"message").unwrap();
+
+		assert!(sr25519::Pair::verify(
+			&sr25519::Signature::from_slice(&signature),
+			b"message",
+			&public
+		));
+
+		let transcript = || VRFTranscriptData {
+			label: b"BABE",
+			items: vec![
+				("slot number", VRFTranscriptValue::U64(1)),
+				("current epoch", VRFTranscriptValue::U64(0)),
+				("chain randomness", VRFTranscriptValue::Bytes(vec![0; 32])),
+			],
+		};
+		let signature =
+			SyncCryptoStore::sr25519_vrf_sign(&keystore, KEY_TYPE, &public, transcript()).unwrap();
+		let vrf_public = schnorrkel::PublicKey::from_bytes(public.as_ref()).unwrap();
+
+		assert!(vrf_public
+			.vrf_verify(
+				sp_keystore::vrf::make_transcript(transcript()),
+				&signature.output,
+				&signature.proof
+			)
+			.is_ok());
+		assert!(SyncCryptoStore::sr25519_vrf_sign(
+			&keystore,
+			KEY_TYPE,
+			&public,
+			VRFTranscriptData {
+				label: b"test",
+				items: vec![],
+			}
+		)
+		.is_err());
+	}
+
+	#[test]
+	fn remote_keystore_should_insert_keys_into_the_signer() {
+		let (_dir, keystore) = signer();
+		let pair = ed25519::Pair::from_string("//Alice", None).unwrap();
+
+		SyncCryptoStore::insert_unknown(&keystore, KEY_TYPE, "//Alice", pair.public().as_ref())
+			.unwrap();
+
+		assert_eq!(
+			SyncCryptoStore::ed25519_public_keys(&keystore, KEY_TYPE),
+			vec![pair.public()]
+		);
+		assert!(SyncCryptoStore::sr25519_public_keys(&keystore, KEY_TYPE).is_empty());
+		assert!(SyncCryptoStore::sign_with(
+			&keystore,
+			KEY_TYPE,
+			&CryptoTypePublicPair(sr25519::CRYPTO_ID, vec![0; 32]),
+			b"message"
+		)
+		.is_err());
+	}
+}
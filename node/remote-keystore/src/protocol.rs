@@ -0,0 +1,290 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! The JSON-RPC 2.0 messages exchanged between the remote keystore and the signer, one per HTTP
+//! request.
+//!
+//! The calls mirror `SyncCryptoStore`, the keys, signatures and messages are `0x` prefixed hex.
+
+// --- crates ---
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+// --- substrate ---
+use sp_core::{
+	crypto::{CryptoTypeId, CryptoTypePublicPair, KeyTypeId},
+	Bytes,
+};
+use sp_keystore::Error as KeystoreError;
+
+/// The version of the JSON-RPC messages.
+pub const JSONRPC_VERSION: &str = "2.0";
+
+/// `sp_keystore::Error::PairNotFound`.
+pub const PAIR_NOT_FOUND: i64 = 1;
+/// `sp_keystore::Error::KeyNotSupported`, with the key type as `data`.
+pub const KEY_NOT_SUPPORTED: i64 = 2;
+/// `sp_keystore::Error::ValidationError`.
+pub const VALIDATION_ERROR: i64 = 3;
+/// `sp_keystore::Error::Unavailable`.
+pub const UNAVAILABLE: i64 = 4;
+/// `sp_keystore::Error::Other`.
+pub const OTHER: i64 = 5;
+/// The request could not be parsed.
+pub const INVALID_REQUEST: i64 = -32600;
+
+/// A four bytes identifier, `KeyTypeId` or `CryptoTypeId`, as its four characters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Id(pub [u8; 4]);
+impl From<KeyTypeId> for Id {
+	fn from(key_type: KeyTypeId) -> Self {
+		Self(key_type.0)
+	}
+}
+impl From<Id> for KeyTypeId {
+	fn from(id: Id) -> Self {
+		Self(id.0)
+	}
+}
+impl From<CryptoTypeId> for Id {
+	fn from(crypto: CryptoTypeId) -> Self {
+		Self(crypto.0)
+	}
+}
+impl From<Id> for CryptoTypeId {
+	fn from(id: Id) -> Self {
+		Self(id.0)
+	}
+}
+impl Serialize for Id {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		let id = std::str::from_utf8(&self.0)
+			.map_err(|_| serde::ser::Error::custom(format!("Non UTF-8 id {:?}", self.0)))?;
+
+		serializer.serialize_str(id)
+	}
+}
+impl<'de> Deserialize<'de> for Id {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let id = String::deserialize(deserializer)?;
+		let mut raw = [0; 4];
+
+		if id.len() != raw.len() {
+			return Err(D::Error::custom(format!(
+				"Expect a 4 bytes id, got `{}`",
+				id
+			)));
+		}
+
+		raw.copy_from_slice(id.as_bytes());
+
+		Ok(Self(raw))
+	}
+}
+
+/// A public key, along with its crypto.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicKey {
+	pub crypto: Id,
+	pub public: Bytes,
+}
+impl From<CryptoTypePublicPair> for PublicKey {
+	fn from(CryptoTypePublicPair(crypto, public): CryptoTypePublicPair) -> Self {
+		Self {
+			crypto: crypto.into(),
+			public: public.into(),
+		}
+	}
+}
+impl From<PublicKey> for CryptoTypePublicPair {
+	fn from(key: PublicKey) -> Self {
+		Self(key.crypto.into(), key.public.0)
+	}
+}
+
+/// A value of a VRF transcript.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptValue {
+	Bytes(Bytes),
+	U64(u64),
+}
+
+/// `sp_keystore::vrf::VRFTranscriptData`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Transcript {
+	pub label: Bytes,
+	pub items: Vec<(String, TranscriptValue)>,
+}
+
+/// `sp_keystore::vrf::VRFSignature`, as the bytes of its output and of its proof.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VrfSignature {
+	pub output: Bytes,
+	pub proof: Bytes,
+}
+
+/// A call to the signer, the type of its result is given in the documentation of each call.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "method", content = "params")]
+pub enum Call {
+	/// The public keys of a key type and crypto, `Vec<Bytes>`.
+	#[serde(rename = "keystore_publicKeys")]
+	PublicKeys { key_type: Id, crypto: Id },
+	/// Generate a new key pair, from the seed if any, its public key, `Bytes`.
+	#[serde(rename = "keystore_generateNew")]
+	GenerateNew {
+		key_type: Id,
+		crypto: Id,
+		seed: Option<String>,
+	},
+	/// Insert a key pair from its secret URI, `()`.
+	#[serde(rename = "keystore_insertUnknown")]
+	InsertUnknown {
+		key_type: Id,
+		suri: String,
+		public: Bytes,
+	},
+	/// The public keys of a key type, of any crypto, `Vec<PublicKey>`.
+	#[serde(rename = "keystore_keys")]
+	Keys { key_type: Id },
+	/// Whether the private keys of all the given public keys are known, `bool`.
+	#[serde(rename = "keystore_hasKeys")]
+	HasKeys { keys: Vec<(Bytes, Id)> },
+	/// Sign a message, the signature, `Bytes`.
+	#[serde(rename = "keystore_sign")]
+	Sign {
+		key_type: Id,
+		key: PublicKey,
+		message: Bytes,
+	},
+	/// VRF sign a transcript with a sr25519 key, `VrfSignature`.
+	#[serde(rename = "keystore_sr25519VrfSign")]
+	Sr25519VrfSign {
+		key_type: Id,
+		public: Bytes,
+		transcript: Transcript,
+	},
+}
+impl Call {
+	/// The method name of the call, safe to log unlike the call itself.
+	pub fn method(&self) -> &'static str {
+		match self {
+			Call::PublicKeys { .. } => "keystore_publicKeys",
+			Call::GenerateNew { .. } => "keystore_generateNew",
+			Call::InsertUnknown { .. } => "keystore_insertUnknown",
+			Call::Keys { .. } => "keystore_keys",
+			Call::HasKeys { .. } => "keystore_hasKeys",
+			Call::Sign { .. } => "keystore_sign",
+			Call::Sr25519VrfSign { .. } => "keystore_sr25519VrfSign",
+		}
+	}
+}
+
+/// A request of the remote keystore.
+#[derive(Serialize, Deserialize)]
+pub struct Request {
+	pub jsonrpc: String,
+	pub id: u64,
+	#[serde(flatten)]
+	pub call: Call,
+}
+
+/// An error answered by the signer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Error {
+	pub code: i64,
+	pub message: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub data: Option<Id>,
+}
+impl From<KeystoreError> for Error {
+	fn from(error: KeystoreError) -> Self {
+		let (code, data) = match &error {
+			KeystoreError::PairNotFound(_) => (PAIR_NOT_FOUND, None),
+			KeystoreError::KeyNotSupported(key_type) => {
+				(KEY_NOT_SUPPORTED, Some((*key_type).into()))
+			}
+			KeystoreError::ValidationError(_) => (VALIDATION_ERROR, None),
+			KeystoreError::Unavailable => (UNAVAILABLE, None),
+			KeystoreError::Other(_) => (OTHER, None),
+		};
+		// Keep the inner message only, the variant is restored from the code
+		let message = match error {
+			KeystoreError::PairNotFound(message)
+			| KeystoreError::ValidationError(message)
+			| KeystoreError::Other(message) => message,
+			error => error.to_string(),
+		};
+
+		Self {
+			code,
+			message,
+			data,
+		}
+	}
+}
+impl From<Error> for KeystoreError {
+	fn from(error: Error) -> Self {
+		match (error.code, error.data) {
+			(PAIR_NOT_FOUND, _) => KeystoreError::PairNotFound(error.message),
+			(KEY_NOT_SUPPORTED, Some(key_type)) => KeystoreError::KeyNotSupported(key_type.into()),
+			(VALIDATION_ERROR, _) => KeystoreError::ValidationError(error.message),
+			(UNAVAILABLE, _) => KeystoreError::Unavailable,
+			_ => KeystoreError::Other(error.message),
+		}
+	}
+}
+
+/// The answer of the signer to a request.
+#[derive(Serialize, Deserialize)]
+pub struct Response {
+	pub jsonrpc: String,
+	/// `None` if the request could not be parsed.
+	pub id: Option<u64>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub result: Option<Value>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub error: Option<Error>,
+}
+impl Response {
+	/// A successful response.
+	pub fn result(id: u64, result: Value) -> Self {
+		Self {
+			jsonrpc: JSONRPC_VERSION.into(),
+			id: Some(id),
+			result: Some(result),
+			error: None,
+		}
+	}
+
+	/// A failed response.
+	pub fn error(id: Option<u64>, error: Error) -> Self {
+		Self {
+			jsonrpc: JSONRPC_VERSION.into(),
+			id,
+			result: None,
+			error: Some(error),
+		}
+	}
+}
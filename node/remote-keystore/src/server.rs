@@ -0,0 +1,337 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! The signer side, answering the remote keystores from a local keystore.
+
+// --- std ---
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+use std::{io, net::TcpListener, sync::Arc, thread};
+// --- crates ---
+use log::{debug, info, warn};
+use serde::Serialize;
+use serde_json::Value;
+// --- substrate ---
+use sp_core::{
+	crypto::{CryptoTypeId, KeyTypeId, Public as _},
+	ecdsa, ed25519, sr25519, Bytes,
+};
+use sp_keystore::{
+	vrf::{VRFTranscriptData, VRFTranscriptValue},
+	Error as KeystoreError, SyncCryptoStore, SyncCryptoStorePtr,
+};
+// --- darwinia ---
+use crate::{
+	http::Connection,
+	protocol::{
+		Call, Error, PublicKey, Request, Response, Transcript, TranscriptValue, VrfSignature,
+		INVALID_REQUEST,
+	},
+	Endpoint, LOG_TARGET,
+};
+
+/// The labels of the VRF transcripts the signer signs, those of BABE.
+///
+/// `VRFTranscriptData` wants `'static` labels and item names, they are taken from here.
+const TRANSCRIPT_LABELS: &[&[u8]] = &[b"BABE"];
+/// The item names of the VRF transcripts the signer signs.
+const TRANSCRIPT_ITEMS: &[&str] = &["slot number", "current epoch", "chain randomness"];
+
+enum Listener {
+	#[cfg(unix)]
+	Unix(UnixListener),
+	Tcp(TcpListener),
+}
+impl Listener {
+	fn accept(&self) -> io::Result<Connection> {
+		match self {
+			#[cfg(unix)]
+			Listener::Unix(listener) => {
+				let (stream, _) = listener.accept()?;
+
+				Ok(Connection::new(stream.try_clone()?, stream))
+			}
+			Listener::Tcp(listener) => {
+				let (stream, _) = listener.accept()?;
+
+				stream.set_nodelay(true)?;
+
+				Ok(Connection::new(stream.try_clone()?, stream))
+			}
+		}
+	}
+}
+
+/// Serve the keys of a keystore to the remote keystores, one thread per connection.
+pub struct Server {
+	listener: Listener,
+	handler: Arc<Handler>,
+}
+impl Server {
+	/// Listen on `endpoint`, an HTTP one must be on the loopback.
+	///
+	/// A Unix socket is created with the permissions of the umask, the directory holding it should
+	/// only be accessible to the node.
+	pub fn bind(endpoint: &Endpoint, keystore: SyncCryptoStorePtr) -> io::Result<Self> {
+		let listener = match endpoint {
+			#[cfg(unix)]
+			Endpoint::Unix(path) => Listener::Unix(UnixListener::bind(path)?),
+			#[cfg(not(unix))]
+			Endpoint::Unix(_) => return Err(crate::unix_unsupported()),
+			Endpoint::Http(address) => {
+				let listener = TcpListener::bind(address)?;
+
+				if !listener.local_addr()?.ip().is_loopback() {
+					return Err(io::Error::new(
+						io::ErrorKind::PermissionDenied,
+						format!(
+							"The signer only listens on the loopback, `{}` is not, use a tunnel to \
+							reach it from another host",
+							address
+						),
+					));
+				}
+
+				Listener::Tcp(listener)
+			}
+		};
+
+		Ok(Self {
+			listener,
+			handler: Arc::new(Handler { keystore }),
+		})
+	}
+
+	/// Accept connections, until the listener fails.
+	pub fn run(self) -> io::Result<()> {
+		loop {
+			let connection = self.listener.accept()?;
+			let handler = self.handler.clone();
+
+			info!(target: LOG_TARGET, "New connection");
+
+			thread::spawn(move || match handler.serve(connection) {
+				Ok(()) => info!(target: LOG_TARGET, "Connection closed"),
+				Err(e) => warn!(target: LOG_TARGET, "Connection failed: {}", e),
+			});
+		}
+	}
+}
+
+struct Handler {
+	keystore: SyncCryptoStorePtr,
+}
+impl Handler {
+	fn serve(&self, mut connection: Connection) -> io::Result<()> {
+		while let Some(message) = connection.read()? {
+			if !message.start_line.starts_with("POST ") {
+				connection.write_response("405 Method Not Allowed", &[])?;
+
+				continue;
+			}
+
+			let response = match serde_json::from_slice::<Request>(&message.body) {
+				Ok(Request { id, call, .. }) => {
+					debug!(target: LOG_TARGET, "{} #{}", call.method(), id);
+
+					match self.handle(call) {
+						Ok(result) => Response::result(id, result),
+						Err(e) => Response::error(Some(id), e),
+					}
+				}
+				Err(e) => Response::error(
+					None,
+					Error {
+						code: INVALID_REQUEST,
+						message: e.to_string(),
+						data: None,
+					},
+				),
+			};
+
+			connection.write_response("200 OK", &serde_json::to_vec(&response)?)?;
+		}
+
+		Ok(())
+	}
+
+	fn handle(&self, call: Call) -> Result<Value, Error> {
+		let keystore = &*self.keystore;
+
+		match call {
+			Call::PublicKeys { key_type, crypto } => {
+				let key_type = KeyTypeId::from(key_type);
+				let keys = match CryptoTypeId::from(crypto) {
+					sr25519::CRYPTO_ID => to_bytes(keystore.sr25519_public_keys(key_type)),
+					ed25519::CRYPTO_ID => to_bytes(keystore.ed25519_public_keys(key_type)),
+					ecdsa::CRYPTO_ID => to_bytes(keystore.ecdsa_public_keys(key_type)),
+					_ => return Err(unsupported_crypto(crypto.into())),
+				};
+
+				to_value(keys)
+			}
+			Call::GenerateNew {
+				key_type,
+				crypto,
+				seed,
+			} => {
+				let key_type = KeyTypeId::from(key_type);
+				let seed = seed.as_deref();
+				let public = match CryptoTypeId::from(crypto) {
+					sr25519::CRYPTO_ID => keystore
+						.sr25519_generate_new(key_type, seed)
+						.map(|public| public.to_raw_vec()),
+					ed25519::CRYPTO_ID => keystore
+						.ed25519_generate_new(key_type, seed)
+						.map(|public| public.to_raw_vec()),
+					ecdsa::CRYPTO_ID => keystore
+						.ecdsa_generate_new(key_type, seed)
+						.map(|public| public.to_raw_vec()),
+					_ => return Err(unsupported_crypto(crypto.into())),
+				}?;
+
+				to_value(Bytes(public))
+			}
+			Call::InsertUnknown {
+				key_type,
+				suri,
+				public,
+			} => {
+				keystore
+					.insert_unknown(key_type.into(), &suri, &public)
+					.map_err(|_| {
+						Error::from(KeystoreError::Other(
+							"Failed to insert the key into the keystore".into(),
+						))
+					})?;
+
+				Ok(Value::Null)
+			}
+			Call::Keys { key_type } => {
+				let keys = keystore
+					.keys(key_type.into())?
+					.into_iter()
+					.map(PublicKey::from)
+					.collect::<Vec<_>>();
+
+				to_value(keys)
+			}
+			Call::HasKeys { keys } => {
+				let keys = keys
+					.into_iter()
+					.map(|(public, key_type)| (public.0, key_type.into()))
+					.collect::<Vec<_>>();
+
+				to_value(keystore.has_keys(&keys))
+			}
+			Call::Sign {
+				key_type,
+				key,
+				message,
+			} => {
+				let signature = keystore.sign_with(key_type.into(), &key.into(), &message)?;
+
+				to_value(Bytes(signature))
+			}
+			Call::Sr25519VrfSign {
+				key_type,
+				public,
+				transcript,
+			} => {
+				if public.len() != 32 {
+					return Err(KeystoreError::ValidationError(format!(
+						"Invalid sr25519 public key length {}",
+						public.len()
+					))
+					.into());
+				}
+
+				let mut raw = [0; 32];
+
+				raw.copy_from_slice(&public);
+
+				let signature = keystore.sr25519_vrf_sign(
+					key_type.into(),
+					&sr25519::Public(raw),
+					transcript_data(transcript)?,
+				)?;
+
+				to_value(VrfSignature {
+					output: signature.output.to_bytes().to_vec().into(),
+					proof: signature.proof.to_bytes().to_vec().into(),
+				})
+			}
+		}
+	}
+}
+
+fn transcript_data(transcript: Transcript) -> Result<VRFTranscriptData, Error> {
+	let label = TRANSCRIPT_LABELS
+		.iter()
+		.find(|label| **label == &transcript.label[..])
+		.copied()
+		.ok_or_else(|| {
+			KeystoreError::ValidationError(format!(
+				"Unknown VRF transcript label {:?}",
+				String::from_utf8_lossy(&transcript.label)
+			))
+		})?;
+	let items = transcript
+		.items
+		.into_iter()
+		.map(|(name, value)| {
+			let name = TRANSCRIPT_ITEMS
+				.iter()
+				.find(|item| **item == name)
+				.ok_or_else(|| {
+					KeystoreError::ValidationError(format!(
+						"Unknown VRF transcript item {:?}",
+						name
+					))
+				})?;
+			let value = match value {
+				TranscriptValue::Bytes(bytes) => VRFTranscriptValue::Bytes(bytes.0),
+				TranscriptValue::U64(number) => VRFTranscriptValue::U64(number),
+			};
+
+			Ok((*name, value))
+		})
+		.collect::<Result<_, KeystoreError>>()?;
+
+	Ok(VRFTranscriptData { label, items })
+}
+
+fn to_bytes<P>(keys: Vec<P>) -> Vec<Bytes>
+where
+	P: AsRef<[u8]>,
+{
+	keys.into_iter()
+		.map(|key| key.as_ref().to_vec().into())
+		.collect()
+}
+
+fn to_value<T>(result: T) -> Result<Value, Error>
+where
+	T: Serialize,
+{
+	serde_json::to_value(result).map_err(|e| KeystoreError::Other(e.to_string()).into())
+}
+
+fn unsupported_crypto(crypto: CryptoTypeId) -> Error {
+	KeystoreError::ValidationError(format!("Unsupported crypto {:?}", crypto)).into()
+}
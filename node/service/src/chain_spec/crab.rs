@@ -26,12 +26,12 @@ use sc_service::Properties;
 use sc_telemetry::TelemetryEndpoints;
 use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
 use sp_consensus_babe::AuthorityId as BabeId;
-use sp_core::{crypto::UncheckedInto, sr25519};
+use sp_core::{crypto::UncheckedInto, sr25519, storage::Storage};
 use sp_runtime::Perbill;
 // --- darwinia ---
 use super::{
-	get_account_id_from_seed, get_authority_keys_from_seed, testnet_accounts, Extensions,
-	DEFAULT_PROTOCOL_ID,
+	fork, get_account_id_from_seed, get_authority_keys_from_seed, testnet::TestnetDescription,
+	testnet_accounts, AuthorityKeys, Extensions, DEFAULT_PROTOCOL_ID,
 };
 use crab_runtime::{constants::currency::COIN, *};
 use darwinia_primitives::{AccountId, Balance};
@@ -343,3 +343,58 @@ pub fn crab_development_config() -> CrabChainSpec {
 		Default::default(),
 	)
 }
+
+/// Crab local testnet config, built from a `TestnetDescription`
+pub fn crab_testnet_config(description: &TestnetDescription) -> Result<CrabChainSpec, String> {
+	if description.ethereum_relay_genesis_header.is_some() {
+		return Err("Crab has no Ethereum relay, remove `ethereumRelayGenesisHeader`".into());
+	}
+
+	let testnet = description.resolve(COIN)?;
+	let build_genesis = move || {
+		let mut genesis = crab_testnet_genesis(
+			testnet.root.clone(),
+			testnet.authorities.clone(),
+			Some(testnet.endowed.iter().map(|x| x.0.clone()).collect()),
+		);
+
+		genesis.darwinia_balances_Instance0.balances =
+			testnet.endowed.iter().map(|x| (x.0.clone(), x.1)).collect();
+		genesis.darwinia_balances_Instance1.balances =
+			testnet.endowed.iter().map(|x| (x.0.clone(), x.2)).collect();
+		if let Some(validator_count) = testnet.validator_count {
+			genesis.darwinia_staking.validator_count = validator_count;
+		}
+		genesis.darwinia_evm.accounts = testnet.evm_accounts.clone();
+
+		genesis
+	};
+
+	Ok(CrabChainSpec::from_genesis(
+		&description.name,
+		&description.id,
+		ChainType::Local,
+		build_genesis,
+		vec![],
+		None,
+		Some(DEFAULT_PROTOCOL_ID),
+		Some(crab_properties()),
+		Default::default(),
+	))
+}
+
+/// Rewrite a Crab state into the genesis of a local dev network, see `fork::fork_storage`
+pub fn crab_fork_storage(
+	storage: &mut Storage,
+	authorities: &[AuthorityKeys],
+	sudo: &AccountId,
+) -> Result<(), String> {
+	fork::fork_storage(
+		storage,
+		Runtime::metadata(),
+		authorities,
+		|x| crab_session_keys(x.2.clone(), x.3.clone(), x.4.clone(), x.5.clone()),
+		Forcing::ForceNone,
+		sudo,
+	)
+}
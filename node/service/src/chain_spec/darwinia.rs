@@ -26,14 +26,14 @@ use sc_service::Properties;
 use sc_telemetry::TelemetryEndpoints;
 use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
 use sp_consensus_babe::AuthorityId as BabeId;
-use sp_core::{crypto::UncheckedInto, sr25519};
+use sp_core::{crypto::UncheckedInto, sr25519, storage::Storage};
 use sp_runtime::Perbill;
 // --- darwinia ---
 use super::{
-	get_account_id_from_seed, get_authority_keys_from_seed, testnet_accounts, Extensions,
-	DEFAULT_PROTOCOL_ID,
+	fork, get_account_id_from_seed, get_authority_keys_from_seed, testnet::TestnetDescription,
+	testnet_accounts, AuthorityKeys, Extensions, DEFAULT_PROTOCOL_ID,
 };
-use darwinia_primitives::{AccountId, BlockNumber};
+use darwinia_primitives::{AccountId, Balance, BlockNumber};
 use darwinia_runtime::{
 	constants::{currency::COIN, time::DAYS},
 	*,
@@ -75,6 +75,11 @@ pub fn darwinia_properties() -> Properties {
 }
 
 pub fn darwinia_build_spec_genesis() -> GenesisConfig {
+	darwinia_build_spec_genesis_with_swap().0
+}
+
+/// Build the Darwinia genesis and return the amount of RING swapped for CRING along with it.
+pub fn darwinia_build_spec_genesis_with_swap() -> (GenesisConfig, Balance) {
 	const ROOT: &'static str = "0x0a66532a23c418cca12183fee5f6afece770a0bb8725f459d7d1b1b598f91c49";
 	const DA_CRABK: &'static str =
 		"0x6d6f646c64612f637261626b0000000000000000000000000000000000000000";
@@ -237,7 +242,7 @@ pub fn darwinia_build_spec_genesis() -> GenesisConfig {
 		.and_modify(|ring| *ring += 400_000_000 * COIN)
 		.or_insert(400_000_000 * COIN);
 
-	GenesisConfig {
+	let genesis = GenesisConfig {
 		frame_system: SystemConfig {
 			code: wasm_binary_unwrap().to_vec(),
 			changes_trie_config: Default::default(),
@@ -333,7 +338,12 @@ pub fn darwinia_build_spec_genesis() -> GenesisConfig {
 			backed_kton: 1_357_120_581_926_771_954_238_u128 / COIN + 1,
 		},
 		darwinia_democracy: Default::default(),
-	}
+		darwinia_evm: EVMConfig {
+			accounts: BTreeMap::new(),
+		},
+	};
+
+	(genesis, swapped_ring_for_crab)
 }
 
 /// Darwinia config.
@@ -457,6 +467,9 @@ pub fn darwinia_testnet_genesis(
 			backed_kton: 1 << 56,
 		},
 		darwinia_democracy: Default::default(),
+		darwinia_evm: EVMConfig {
+			accounts: BTreeMap::new(),
+		},
 	}
 }
 
@@ -487,3 +500,59 @@ pub fn darwinia_development_config() -> DarwiniaChainSpec {
 		Default::default(),
 	)
 }
+
+/// Darwinia local testnet config, built from a `TestnetDescription`
+pub fn darwinia_testnet_config(
+	description: &TestnetDescription,
+) -> Result<DarwiniaChainSpec, String> {
+	let testnet = description.resolve(COIN)?;
+	let build_genesis = move || {
+		let mut genesis = darwinia_testnet_genesis(
+			testnet.root.clone(),
+			testnet.authorities.clone(),
+			Some(testnet.endowed.iter().map(|x| x.0.clone()).collect()),
+		);
+
+		genesis.darwinia_balances_Instance0.balances =
+			testnet.endowed.iter().map(|x| (x.0.clone(), x.1)).collect();
+		genesis.darwinia_balances_Instance1.balances =
+			testnet.endowed.iter().map(|x| (x.0.clone(), x.2)).collect();
+		if let Some(validator_count) = testnet.validator_count {
+			genesis.darwinia_staking.validator_count = validator_count;
+		}
+		if let Some(genesis_header_info) = testnet.ethereum_relay_genesis_header.clone() {
+			genesis.darwinia_ethereum_relay.genesis_header_info = genesis_header_info;
+		}
+		genesis.darwinia_evm.accounts = testnet.evm_accounts.clone();
+
+		genesis
+	};
+
+	Ok(DarwiniaChainSpec::from_genesis(
+		&description.name,
+		&description.id,
+		ChainType::Local,
+		build_genesis,
+		vec![],
+		None,
+		Some(DEFAULT_PROTOCOL_ID),
+		Some(darwinia_properties()),
+		Default::default(),
+	))
+}
+
+/// Rewrite a Darwinia state into the genesis of a local dev network, see `fork::fork_storage`
+pub fn darwinia_fork_storage(
+	storage: &mut Storage,
+	authorities: &[AuthorityKeys],
+	sudo: &AccountId,
+) -> Result<(), String> {
+	fork::fork_storage(
+		storage,
+		Runtime::metadata(),
+		authorities,
+		|x| darwinia_session_keys(x.2.clone(), x.3.clone(), x.4.clone(), x.5.clone()),
+		Forcing::ForceNone,
+		sudo,
+	)
+}
@@ -0,0 +1,234 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! Rewrite the state of a live chain into the genesis of a local dev network.
+
+// --- crates ---
+use codec::{Decode, Encode};
+// --- substrate ---
+use frame_metadata::RuntimeMetadataPrefixed;
+use sp_core::{
+	crypto::{key_types, KeyTypeId},
+	storage::Storage,
+};
+use sp_finality_grandpa::{VersionedAuthorityList, GRANDPA_AUTHORITIES_KEY};
+// --- darwinia ---
+use super::{metadata::StoragePrefixes, AuthorityKeys};
+use darwinia_primitives::{AccountId, Balance, BlockNumber};
+use darwinia_staking::{ActiveEraInfo, EraIndex, Exposure, StakingLedger, ValidatorPrefs};
+
+/// The `Babe` storage items kept while the rest of the pallet is reset.
+const BABE_KEPT_ITEMS: &[&str] = &["EpochConfig"];
+/// The `Session` storage items kept while the rest of the pallet is reset.
+const SESSION_KEPT_ITEMS: &[&str] = &["CurrentIndex"];
+/// The `Staking` items of an era which are rebuilt for the authorities.
+const STAKING_ERA_ITEMS: &[&str] = &[
+	"ErasStakers",
+	"ErasStakersClipped",
+	"ErasValidatorPrefs",
+	"ErasRewardPoints",
+];
+
+/// Replace the authorities of `storage` with `authorities` and its sudo key with `sudo`.
+///
+/// - `Babe` and `Grandpa` are reset, as if `authorities` were their genesis authorities.
+/// - `Session` is rotated to `authorities`, each one is its own validator id.
+/// - `Staking` validators are replaced by `authorities`, bonded to themselves with nothing at
+///   stake, and set to `force_none`, so that no election replaces them. The exposures of the
+///   active and the planned era are rebuilt for `authorities`. Nominators, bonds, ledgers and
+///   payees of the other stakers, the old validators included, are kept.
+/// - Every other storage item is kept untouched.
+///
+/// `metadata` is used to find the storage prefix of each pallet and `session_keys` to build the
+/// `SessionKeys` of the runtime.
+pub fn fork_storage<SessionKeys, Forcing>(
+	storage: &mut Storage,
+	metadata: RuntimeMetadataPrefixed,
+	authorities: &[AuthorityKeys],
+	session_keys: impl Fn(&AuthorityKeys) -> SessionKeys,
+	force_none: Forcing,
+	sudo: &AccountId,
+) -> Result<(), String>
+where
+	SessionKeys: Encode,
+	Forcing: Encode,
+{
+	if authorities.is_empty() {
+		return Err("A fork needs at least one authority".into());
+	}
+
+	let prefixes = StoragePrefixes::new(metadata)?;
+	let validators = authorities
+		.iter()
+		.map(|keys| keys.0.clone())
+		.collect::<Vec<_>>();
+	let babe_authorities = authorities
+		.iter()
+		.map(|keys| (keys.2.clone(), 1_u64))
+		.collect::<Vec<_>>();
+	let grandpa_authorities = authorities
+		.iter()
+		.map(|keys| (keys.3.clone(), 1_u64))
+		.collect::<Vec<_>>();
+	let queued_keys = validators
+		.iter()
+		.cloned()
+		.zip(authorities.iter().map(session_keys))
+		.collect::<Vec<_>>();
+
+	prefixes.clear(storage, "Babe", BABE_KEPT_ITEMS)?;
+	storage.top.insert(
+		prefixes.item_key("Babe", "Authorities")?,
+		babe_authorities.encode(),
+	);
+	storage.top.insert(
+		prefixes.item_key("Babe", "NextAuthorities")?,
+		babe_authorities.encode(),
+	);
+
+	prefixes.clear(storage, "Grandpa", &[])?;
+	storage.top.insert(
+		GRANDPA_AUTHORITIES_KEY.to_vec(),
+		VersionedAuthorityList::from(grandpa_authorities).encode(),
+	);
+
+	prefixes.clear(storage, "Session", SESSION_KEPT_ITEMS)?;
+	storage.top.insert(
+		prefixes.item_key("Session", "Validators")?,
+		validators.encode(),
+	);
+	storage.top.insert(
+		prefixes.item_key("Session", "QueuedKeys")?,
+		queued_keys.encode(),
+	);
+	for ((validator, keys), authority_keys) in queued_keys.iter().zip(authorities) {
+		let public_keys: [(KeyTypeId, &[u8]); 4] = [
+			(key_types::BABE, authority_keys.2.as_ref()),
+			(key_types::GRANDPA, authority_keys.3.as_ref()),
+			(key_types::IM_ONLINE, authority_keys.4.as_ref()),
+			(key_types::AUTHORITY_DISCOVERY, authority_keys.5.as_ref()),
+		];
+
+		storage.top.insert(
+			prefixes.map_key("Session", "NextKeys", &validator.encode())?,
+			keys.encode(),
+		);
+		for key_owner in public_keys.iter() {
+			storage.top.insert(
+				prefixes.map_key("Session", "KeyOwner", &key_owner.encode())?,
+				validator.encode(),
+			);
+		}
+	}
+
+	if prefixes.contains("ImOnline") {
+		let im_online_keys = authorities
+			.iter()
+			.map(|keys| keys.4.clone())
+			.collect::<Vec<_>>();
+
+		storage.top.insert(
+			prefixes.item_key("ImOnline", "Keys")?,
+			im_online_keys.encode(),
+		);
+	}
+
+	// The old validators stay bonded, they only stop validating
+	prefixes.remove(storage, "Staking", &["Validators"])?;
+	for (stash, controller, ..) in authorities {
+		let ledger = StakingLedger::<AccountId, Balance, Balance, BlockNumber> {
+			stash: stash.to_owned(),
+			..Default::default()
+		};
+
+		storage.top.insert(
+			prefixes.map_key("Staking", "Validators", &stash.encode())?,
+			ValidatorPrefs::default().encode(),
+		);
+		storage.top.insert(
+			prefixes.map_key("Staking", "Bonded", &stash.encode())?,
+			controller.encode(),
+		);
+		storage.top.insert(
+			prefixes.blake2_map_key("Staking", "Ledger", &controller.encode())?,
+			ledger.encode(),
+		);
+	}
+
+	let active_era = storage
+		.top
+		.get(&prefixes.item_key("Staking", "ActiveEra")?)
+		.map(|info| ActiveEraInfo::decode(&mut &info[..]).map(|info| info.index))
+		.transpose()
+		.map_err(|e| format!("Invalid `Staking::ActiveEra`: {}", e))?;
+	let current_era = storage
+		.top
+		.get(&prefixes.item_key("Staking", "CurrentEra")?)
+		.map(|era| EraIndex::decode(&mut &era[..]))
+		.transpose()
+		.map_err(|e| format!("Invalid `Staking::CurrentEra`: {}", e))?;
+	let mut eras = active_era
+		.into_iter()
+		.chain(current_era)
+		.collect::<Vec<_>>();
+
+	eras.dedup();
+
+	for era in eras {
+		let era_prefixes = STAKING_ERA_ITEMS
+			.iter()
+			.map(|item| prefixes.map_key("Staking", item, &era.encode()))
+			.collect::<Result<Vec<_>, _>>()?;
+
+		StoragePrefixes::remove_prefixed(storage, &era_prefixes);
+
+		for (stash, ..) in authorities {
+			let exposure = Exposure::<AccountId, Balance, Balance>::default();
+
+			for item in &["ErasStakers", "ErasStakersClipped"] {
+				storage.top.insert(
+					prefixes.double_map_key("Staking", item, &era.encode(), &stash.encode())?,
+					exposure.encode(),
+				);
+			}
+			storage.top.insert(
+				prefixes.double_map_key(
+					"Staking",
+					"ErasValidatorPrefs",
+					&era.encode(),
+					&stash.encode(),
+				)?,
+				ValidatorPrefs::default().encode(),
+			);
+		}
+	}
+	storage.top.insert(
+		prefixes.item_key("Staking", "ForceEra")?,
+		force_none.encode(),
+	);
+	storage.top.insert(
+		prefixes.item_key("Staking", "Invulnerables")?,
+		validators.encode(),
+	);
+
+	storage
+		.top
+		.insert(prefixes.item_key("Sudo", "Key")?, sudo.encode());
+
+	Ok(())
+}
@@ -0,0 +1,276 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! The storage layout of the runtimes, read from their metadata.
+
+// --- crates ---
+use codec::{Decode, Encode};
+// --- substrate ---
+pub use frame_metadata::StorageHasher;
+
+use frame_metadata::{DecodeDifferent, RuntimeMetadata, RuntimeMetadataPrefixed, StorageEntryType};
+use sp_core::{
+	hashing::{blake2_128, twox_128, twox_64},
+	storage::Storage,
+};
+
+/// The storage prefixes of the pallets, by their names in `construct_runtime!`.
+pub struct StoragePrefixes(Vec<(String, String)>);
+impl StoragePrefixes {
+	/// Read the storage prefixes from the metadata of a runtime.
+	pub fn new(metadata: RuntimeMetadataPrefixed) -> Result<Self, String> {
+		let modules = match metadata.1 {
+			RuntimeMetadata::V12(metadata) => metadata.modules,
+			_ => return Err("Unsupported runtime metadata version".into()),
+		};
+		let modules = match modules {
+			DecodeDifferent::Encode(modules) => modules.to_vec(),
+			DecodeDifferent::Decoded(modules) => modules,
+		};
+
+		Ok(Self(
+			modules
+				.into_iter()
+				.filter_map(|module| {
+					let name = match module.name {
+						DecodeDifferent::Encode(name) => name.to_owned(),
+						DecodeDifferent::Decoded(name) => name,
+					};
+					let prefix = match module.storage? {
+						DecodeDifferent::Encode(storage) => match (storage.0)().prefix {
+							DecodeDifferent::Encode(prefix) => prefix.to_owned(),
+							DecodeDifferent::Decoded(prefix) => prefix,
+						},
+						DecodeDifferent::Decoded(storage) => match storage.prefix {
+							DecodeDifferent::Encode(prefix) => prefix.to_owned(),
+							DecodeDifferent::Decoded(prefix) => prefix,
+						},
+					};
+
+					Some((name, prefix))
+				})
+				.collect(),
+		))
+	}
+
+	/// Whether the pallet has storage.
+	pub fn contains(&self, pallet: &str) -> bool {
+		self.0.iter().any(|(name, _)| name == pallet)
+	}
+
+	/// The `twox_128` hashed storage prefix of the pallet.
+	pub fn prefix(&self, pallet: &str) -> Result<[u8; 16], String> {
+		self.0
+			.iter()
+			.find(|(name, _)| name == pallet)
+			.map(|(_, prefix)| twox_128(prefix.as_bytes()))
+			.ok_or_else(|| format!("Pallet `{}` has no storage in the runtime metadata", pallet))
+	}
+
+	/// The key of a storage value, or the prefix of a storage map.
+	pub fn item_key(&self, pallet: &str, item: &str) -> Result<Vec<u8>, String> {
+		Ok([&self.prefix(pallet)?[..], &twox_128(item.as_bytes())].concat())
+	}
+
+	/// The key of a `Twox64Concat` hashed map entry.
+	pub fn map_key(&self, pallet: &str, item: &str, encoded_key: &[u8]) -> Result<Vec<u8>, String> {
+		Ok([
+			&self.item_key(pallet, item)?[..],
+			&twox_64(encoded_key),
+			encoded_key,
+		]
+		.concat())
+	}
+
+	/// The key of a `Twox64Concat` hashed double map entry.
+	pub fn double_map_key(
+		&self,
+		pallet: &str,
+		item: &str,
+		encoded_key1: &[u8],
+		encoded_key2: &[u8],
+	) -> Result<Vec<u8>, String> {
+		Ok([
+			&self.map_key(pallet, item, encoded_key1)?[..],
+			&twox_64(encoded_key2),
+			encoded_key2,
+		]
+		.concat())
+	}
+
+	/// The key of a `Blake2_128Concat` hashed map entry.
+	pub fn blake2_map_key(
+		&self,
+		pallet: &str,
+		item: &str,
+		encoded_key: &[u8],
+	) -> Result<Vec<u8>, String> {
+		Ok([
+			&self.item_key(pallet, item)?[..],
+			&blake2_128(encoded_key),
+			encoded_key,
+		]
+		.concat())
+	}
+
+	/// The pallet owning a storage key, if any.
+	pub fn pallet_of(&self, key: &[u8]) -> Option<&str> {
+		self.0
+			.iter()
+			.find(|(_, prefix)| key.starts_with(&twox_128(prefix.as_bytes())))
+			.map(|(name, _)| name.as_str())
+	}
+
+	/// Remove every storage item of the pallet, except the `kept` ones.
+	pub fn clear(&self, storage: &mut Storage, pallet: &str, kept: &[&str]) -> Result<(), String> {
+		let prefix = self.prefix(pallet)?;
+		let kept = kept
+			.iter()
+			.map(|item| self.item_key(pallet, item))
+			.collect::<Result<Vec<_>, _>>()?;
+
+		let removed = storage
+			.top
+			.keys()
+			.filter(|key| key.starts_with(&prefix) && !kept.iter().any(|k| key.starts_with(k)))
+			.cloned()
+			.collect::<Vec<_>>();
+
+		for key in removed {
+			storage.top.remove(&key);
+		}
+
+		Ok(())
+	}
+
+	/// Remove every storage key starting with one of `prefixes`.
+	pub fn remove_prefixed(storage: &mut Storage, prefixes: &[Vec<u8>]) {
+		let removed = storage
+			.top
+			.keys()
+			.filter(|key| prefixes.iter().any(|prefix| key.starts_with(prefix)))
+			.cloned()
+			.collect::<Vec<_>>();
+
+		for key in removed {
+			storage.top.remove(&key);
+		}
+	}
+
+	/// Remove the `items` of the pallet, every entry of them for the maps.
+	pub fn remove(
+		&self,
+		storage: &mut Storage,
+		pallet: &str,
+		items: &[&str],
+	) -> Result<(), String> {
+		let prefixes = items
+			.iter()
+			.map(|item| self.item_key(pallet, item))
+			.collect::<Result<Vec<_>, _>>()?;
+
+		Self::remove_prefixed(storage, &prefixes);
+
+		Ok(())
+	}
+}
+
+/// A storage item, with the type names of its keys and of its value as written in the runtime.
+pub struct StorageEntry {
+	/// The name of the pallet in `construct_runtime!`.
+	pub pallet: String,
+	/// The name of the storage item.
+	pub item: String,
+	/// The hasher and the type name of each key, empty for a storage value.
+	pub keys: Vec<(StorageHasher, String)>,
+	/// The type name of the value.
+	pub value: String,
+}
+
+/// The storage items of the pallets, by their hashed pallet and item prefixes.
+pub struct StorageEntries(Vec<(Vec<u8>, StorageEntry)>);
+impl StorageEntries {
+	/// Read the storage items from the metadata of a runtime.
+	pub fn new(metadata: RuntimeMetadataPrefixed) -> Result<Self, String> {
+		// The native metadata is made of static references, a round trip turns them into owned
+		// values
+		let metadata = RuntimeMetadataPrefixed::decode(&mut &metadata.encode()[..])
+			.map_err(|e| format!("Invalid runtime metadata: {}", e))?;
+		let modules = match metadata.1 {
+			RuntimeMetadata::V12(metadata) => decoded(metadata.modules)?,
+			_ => return Err("Unsupported runtime metadata version".into()),
+		};
+		let mut entries = vec![];
+
+		for module in modules {
+			let pallet = decoded(module.name)?;
+			let storage = match module.storage {
+				Some(storage) => decoded(storage)?,
+				None => continue,
+			};
+			let prefix = twox_128(decoded(storage.prefix)?.as_bytes());
+
+			for entry in decoded(storage.entries)? {
+				let item = decoded(entry.name)?;
+				let (keys, value) = match entry.ty {
+					StorageEntryType::Plain(value) => (vec![], value),
+					StorageEntryType::Map {
+						hasher, key, value, ..
+					} => (vec![(hasher, decoded(key)?)], value),
+					StorageEntryType::DoubleMap {
+						hasher,
+						key1,
+						key2,
+						value,
+						key2_hasher,
+					} => (
+						vec![(hasher, decoded(key1)?), (key2_hasher, decoded(key2)?)],
+						value,
+					),
+				};
+
+				entries.push((
+					[&prefix[..], &twox_128(item.as_bytes())].concat(),
+					StorageEntry {
+						pallet: pallet.clone(),
+						item,
+						keys,
+						value: decoded(value)?,
+					},
+				));
+			}
+		}
+
+		Ok(Self(entries))
+	}
+
+	/// The storage item owning a storage key, if any.
+	pub fn entry_of(&self, key: &[u8]) -> Option<&StorageEntry> {
+		self.0
+			.iter()
+			.find(|(prefix, _)| key.starts_with(prefix))
+			.map(|(_, entry)| entry)
+	}
+}
+
+fn decoded<B, O>(value: DecodeDifferent<B, O>) -> Result<O, String> {
+	match value {
+		DecodeDifferent::Decoded(value) => Ok(value),
+		DecodeDifferent::Encode(_) => Err("Runtime metadata has not been decoded".into()),
+	}
+}
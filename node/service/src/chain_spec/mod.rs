@@ -20,25 +20,46 @@
 
 pub mod darwinia;
 pub use darwinia::{
-	darwinia_build_spec_config, darwinia_config, darwinia_development_config, DarwiniaChainSpec,
+	darwinia_build_spec_config, darwinia_config, darwinia_development_config,
+	darwinia_testnet_config, DarwiniaChainSpec,
 };
 
 pub mod crab;
-pub use crab::{crab_build_spec_config, crab_config, crab_development_config, CrabChainSpec};
+pub use crab::{
+	crab_build_spec_config, crab_config, crab_development_config, crab_testnet_config,
+	CrabChainSpec,
+};
+
+pub mod fork;
+
+pub mod metadata;
 
+pub mod testnet;
+pub use testnet::TestnetDescription;
+
+// --- std ---
+use std::{fmt, fs, path::PathBuf, str::FromStr, sync::Mutex, time::Duration};
 // --- crates ---
-use serde::{Deserialize, Serialize};
+use codec::Encode;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 // --- substrate ---
-use darwinia_primitives::{AccountId, AccountPublic};
 use pallet_im_online::sr25519::AuthorityId as ImOnlineId;
-use sc_chain_spec::ChainSpecExtension;
+use sc_chain_spec::{ChainSpec, ChainSpecExtension};
 use sc_finality_grandpa::AuthorityId as GrandpaId;
 use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
-use sp_consensus_babe::AuthorityId as BabeId;
-use sp_core::{sr25519, Pair, Public};
-use sp_runtime::traits::IdentifyAccount;
+use sp_consensus_babe::{
+	AuthorityId as BabeId, BabeAuthorityWeight, BabeEpochConfiguration, Epoch,
+};
+use sp_core::{crypto::Ss58Codec, sr25519, storage::Storage, Pair, Public};
+use sp_finality_grandpa::{AuthorityList, AuthorityWeight, SetId};
+use sp_runtime::traits::{BlakeTwo256, Hash as HashT, Header as HeaderT, IdentifyAccount, Zero};
+// --- darwinia ---
+use crate::service::RuntimeVariant;
+use darwinia_primitives::{AccountId, AccountPublic, BlockNumber, Hash, Header};
 
 const DEFAULT_PROTOCOL_ID: &str = "dar";
+const DEFAULT_GRANDPA_GOSSIP_DURATION_MS: u64 = 1000;
+const DEFAULT_GRANDPA_JUSTIFICATION_PERIOD: u32 = 512;
 
 /// Node `ChainSpec` extensions.
 ///
@@ -51,6 +72,291 @@ pub struct Extensions {
 	pub fork_blocks: sc_client_api::ForkBlocks<darwinia_primitives::OpaqueBlock>,
 	/// Known bad block hashes.
 	pub bad_blocks: sc_client_api::BadBlocks<darwinia_primitives::OpaqueBlock>,
+	/// GRANDPA parameters, the defaults of the node are used if missing.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub grandpa: Option<GrandpaParameters>,
+	/// GRANDPA authority sets forced at some blocks, to recover from a stalled finality.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub grandpa_hard_forks: Vec<GrandpaHardFork>,
+	/// Trusted blocks with the consensus state at them, written by `export-checkpoint`.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub checkpoints: Vec<Checkpoint>,
+	/// The runtime of the genesis, read once from its `:code`, see `IdentifyVariant`.
+	#[serde(skip)]
+	pub runtime_variant: RuntimeVariantCache,
+}
+impl Extensions {
+	/// Try to get the extensions of `chain_spec`, `None` if it is not one of our chain specs.
+	pub fn try_get(chain_spec: &dyn ChainSpec) -> Option<&Self> {
+		sc_chain_spec::get_extension(chain_spec.extensions())
+	}
+
+	/// The GRANDPA parameters, the defaults of the node for the missing ones.
+	pub fn grandpa_parameters(chain_spec: &dyn ChainSpec) -> GrandpaParameters {
+		Self::try_get(chain_spec)
+			.and_then(|extensions| extensions.grandpa.clone())
+			.unwrap_or_default()
+	}
+
+	/// The GRANDPA authority set hard forks, as taken by
+	/// `block_import_with_authority_set_hard_forks`.
+	pub fn grandpa_authority_set_hard_forks(
+		chain_spec: &dyn ChainSpec,
+	) -> Vec<(SetId, (Hash, BlockNumber), AuthorityList)> {
+		Self::try_get(chain_spec)
+			.map(|extensions| {
+				extensions
+					.grandpa_hard_forks
+					.iter()
+					.map(|hard_fork| {
+						(
+							hard_fork.set_id,
+							(hard_fork.block_hash, hard_fork.block_number),
+							hard_fork.authorities.clone(),
+						)
+					})
+					.collect()
+			})
+			.unwrap_or_default()
+	}
+
+	/// The checkpoints of `chain_spec`, the oldest first.
+	pub fn checkpoints(chain_spec: &dyn ChainSpec) -> Vec<Checkpoint> {
+		let mut checkpoints = Self::try_get(chain_spec)
+			.map(|extensions| extensions.checkpoints.clone())
+			.unwrap_or_default();
+
+		checkpoints.sort_by_key(|checkpoint| checkpoint.block_number);
+
+		checkpoints
+	}
+}
+
+/// GRANDPA parameters which used to be hard-coded in the node.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct GrandpaParameters {
+	/// The gossip round duration, in milliseconds.
+	pub gossip_duration_ms: Option<u64>,
+	/// The period, in blocks, of the justifications kept for the syncing nodes.
+	pub justification_period: Option<u32>,
+	/// The voting rules restricting the votes further than the defaults of substrate.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub voting_rules: Vec<GrandpaVotingRule>,
+}
+impl GrandpaParameters {
+	/// The gossip round duration, one second by default.
+	pub fn gossip_duration(&self) -> Duration {
+		Duration::from_millis(
+			self.gossip_duration_ms
+				.unwrap_or(DEFAULT_GRANDPA_GOSSIP_DURATION_MS),
+		)
+	}
+
+	/// The justification period, 512 blocks by default.
+	pub fn justification_period(&self) -> u32 {
+		self.justification_period
+			.unwrap_or(DEFAULT_GRANDPA_JUSTIFICATION_PERIOD)
+	}
+}
+
+/// A GRANDPA voting rule of the node, `--grandpa-voting-rule` on the command line.
+///
+/// It is written the same way in the chain spec, e.g. `"votingRules": ["behind-best-by=16"]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GrandpaVotingRule {
+	/// Vote on blocks at least this number of blocks behind the best block, `behind-best-by=<N>`.
+	BehindBestBy(BlockNumber),
+	/// Never vote past the last block which confirmed an Ethereum relay header parcel,
+	/// `ethereum-relay-confirmed`.
+	EthereumRelayConfirmed,
+}
+impl fmt::Display for GrandpaVotingRule {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			GrandpaVotingRule::BehindBestBy(n) => write!(f, "behind-best-by={}", n),
+			GrandpaVotingRule::EthereumRelayConfirmed => write!(f, "ethereum-relay-confirmed"),
+		}
+	}
+}
+impl FromStr for GrandpaVotingRule {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut parts = s.splitn(2, '=');
+
+		match (parts.next(), parts.next()) {
+			(Some("behind-best-by"), Some(n)) => n
+				.parse()
+				.map(GrandpaVotingRule::BehindBestBy)
+				.map_err(|e| format!("Invalid block count `{}`: {}", n, e)),
+			(Some("ethereum-relay-confirmed"), None) => {
+				Ok(GrandpaVotingRule::EthereumRelayConfirmed)
+			}
+			_ => Err(format!(
+				"Unknown voting rule `{}`, expected `behind-best-by=<N>` or `ethereum-relay-confirmed`",
+				s
+			)),
+		}
+	}
+}
+impl Serialize for GrandpaVotingRule {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.collect_str(self)
+	}
+}
+impl<'de> Deserialize<'de> for GrandpaVotingRule {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		String::deserialize(deserializer)?
+			.parse()
+			.map_err(D::Error::custom)
+	}
+}
+
+/// A GRANDPA authority set forced at a block, regardless of the on-chain authority set changes.
+///
+/// Every node of the network must know the same hard forks, or they will not finalize together.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct GrandpaHardFork {
+	/// The id of the forced set.
+	pub set_id: SetId,
+	/// The hash of the block the set is forced at.
+	pub block_hash: Hash,
+	/// The number of the block the set is forced at.
+	pub block_number: BlockNumber,
+	/// The authorities of the forced set, with their weights.
+	pub authorities: Vec<(GrandpaId, AuthorityWeight)>,
+}
+
+/// A trusted finalized block of the chain, with the GRANDPA and BABE state at it.
+///
+/// The node refuses to start on a chain which disagrees with a checkpoint. Nodes still import
+/// every block from genesis, starting from a checkpoint needs the client side of warp sync.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Checkpoint {
+	/// The hash of the block.
+	pub block_hash: Hash,
+	/// The number of the block.
+	pub block_number: BlockNumber,
+	/// The id of the GRANDPA authority set at the block.
+	pub grandpa_set_id: SetId,
+	/// The GRANDPA authorities at the block, with their weights.
+	pub grandpa_authorities: Vec<(GrandpaId, AuthorityWeight)>,
+	/// The BABE epoch of the block.
+	pub babe_epoch: BabeEpoch,
+}
+
+/// A BABE epoch, as `sp_consensus_babe::Epoch` without the consensus specific types.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct BabeEpoch {
+	/// The index of the epoch.
+	pub epoch_index: u64,
+	/// The first slot of the epoch.
+	pub start_slot: u64,
+	/// The number of slots of the epoch.
+	pub duration: u64,
+	/// The BABE authorities of the epoch, with their weights.
+	pub authorities: Vec<(BabeId, BabeAuthorityWeight)>,
+	/// The randomness of the epoch.
+	pub randomness: Hash,
+	/// The BABE configuration of the epoch.
+	pub config: BabeEpochConfiguration,
+}
+impl From<Epoch> for BabeEpoch {
+	fn from(epoch: Epoch) -> Self {
+		Self {
+			epoch_index: epoch.epoch_index,
+			start_slot: epoch.start_slot.into(),
+			duration: epoch.duration,
+			authorities: epoch.authorities,
+			randomness: epoch.randomness.into(),
+			config: epoch.config,
+		}
+	}
+}
+
+/// The runtime variant of a chain spec, computed once, see `Extensions::runtime_variant`.
+#[derive(Default)]
+pub struct RuntimeVariantCache(Mutex<Option<RuntimeVariant>>);
+impl RuntimeVariantCache {
+	/// The cached variant, computed by `init` on the first successful call.
+	pub fn get_or_try_init(
+		&self,
+		init: impl FnOnce() -> Result<RuntimeVariant, String>,
+	) -> Result<RuntimeVariant, String> {
+		let mut variant = self.0.lock().expect("Never poisoned; qed");
+
+		match *variant {
+			Some(variant) => Ok(variant),
+			None => {
+				let initialized = init()?;
+
+				*variant = Some(initialized);
+
+				Ok(initialized)
+			}
+		}
+	}
+}
+impl Clone for RuntimeVariantCache {
+	fn clone(&self) -> Self {
+		Self(Mutex::new(*self.0.lock().expect("Never poisoned; qed")))
+	}
+}
+
+/// Load a chain spec from a JSON file.
+///
+/// The genesis config of a plain spec only parses with the one of its runtime. A raw spec parses
+/// with both and is kept as a Darwinia spec, its genesis being the same storage whatever the
+/// type, the runtime is then told by `IdentifyVariant`.
+pub fn load_spec_from_json_file(path: PathBuf) -> Result<Box<dyn ChainSpec>, String> {
+	let json = fs::read(&path)
+		.map_err(|e| format!("Error opening spec file `{}`: {}", path.display(), e))?;
+
+	match DarwiniaChainSpec::from_json_bytes(json.clone()) {
+		Ok(chain_spec) => Ok(Box::new(chain_spec)),
+		Err(darwinia_error) => match CrabChainSpec::from_json_bytes(json) {
+			Ok(chain_spec) => Ok(Box::new(chain_spec)),
+			Err(crab_error) => Err(format!(
+				"Spec file `{}` is neither a Darwinia nor a Crab chain spec.\n\
+				As a Darwinia spec: {}\n\
+				As a Crab spec: {}",
+				path.display(),
+				darwinia_error,
+				crab_error
+			)),
+		},
+	}
+}
+
+/// The state root and the hash of the genesis block built from `storage`.
+pub fn genesis_state_root_and_hash(storage: &Storage) -> (Hash, Hash) {
+	let child_roots = storage.children_default.values().map(|child| {
+		(
+			child.child_info.prefixed_storage_key().into_inner(),
+			BlakeTwo256::trie_root(child.data.clone().into_iter().collect()).encode(),
+		)
+	});
+	let state_root =
+		BlakeTwo256::trie_root(storage.top.clone().into_iter().chain(child_roots).collect());
+	let header = Header::new(
+		Zero::zero(),
+		BlakeTwo256::trie_root(vec![]),
+		state_root,
+		Default::default(),
+		Default::default(),
+	);
+
+	(state_root, header.hash())
 }
 
 /// Generate a crypto pair from seed.
@@ -68,27 +374,123 @@ where
 	AccountPublic::from(get_from_seed::<TPublic>(seed)).into_account()
 }
 
-/// Helper function to generate stash, controller and session key from seed
-pub fn get_authority_keys_from_seed(
-	seed: &str,
-) -> (
+/// The stash, controller and session keys of an authority.
+pub type AuthorityKeys = (
 	AccountId,
 	AccountId,
 	BabeId,
 	GrandpaId,
 	ImOnlineId,
 	AuthorityDiscoveryId,
-) {
-	(
-		get_account_id_from_seed::<sr25519::Public>(&format!("{}//stash", seed)),
-		get_account_id_from_seed::<sr25519::Public>(seed),
-		get_from_seed::<BabeId>(seed),
-		get_from_seed::<GrandpaId>(seed),
-		get_from_seed::<ImOnlineId>(seed),
-		get_from_seed::<AuthorityDiscoveryId>(seed),
+);
+
+/// Helper function to generate stash, controller and session key from seed
+pub fn get_authority_keys_from_seed(seed: &str) -> AuthorityKeys {
+	try_get_authority_keys_from_seed(seed).expect("static values are valid; qed")
+}
+
+/// Fallible `get_authority_keys_from_seed`, for the seeds given by the users
+///
+/// The stash is derived at `//<seed>//stash`, the controller and the session keys at `//<seed>`,
+/// as for the dev chain specs.
+pub fn try_get_authority_keys_from_seed(seed: &str) -> sc_cli::Result<AuthorityKeys> {
+	let suri = format!("//{}", seed);
+
+	derive_authority_keys(&derive_suri(&suri, "stash"), &suri, &suri, None)
+}
+
+/// Helper function to derive stash, controller and session key from a secret URI
+///
+/// The stash is derived at `<suri>//stash`, the controller at `<suri>//controller` and the session
+/// keys at `<suri>`.
+pub fn get_authority_keys_from_suri(
+	suri: &str,
+	password: Option<&str>,
+) -> sc_cli::Result<AuthorityKeys> {
+	derive_authority_keys(
+		&derive_suri(suri, "stash"),
+		&derive_suri(suri, "controller"),
+		suri,
+		password,
 	)
 }
 
+fn derive_authority_keys(
+	stash_suri: &str,
+	controller_suri: &str,
+	session_suri: &str,
+	password: Option<&str>,
+) -> sc_cli::Result<AuthorityKeys> {
+	fn public<TPublic: Public>(
+		suri: &str,
+		password: Option<&str>,
+	) -> sc_cli::Result<<TPublic::Pair as Pair>::Public> {
+		TPublic::Pair::from_string(suri, password)
+			.map(|pair| pair.public())
+			.map_err(|e| sc_cli::Error::Input(format!("Invalid secret URI: {:?}", e)))
+	}
+
+	Ok((
+		AccountPublic::from(public::<sr25519::Public>(stash_suri, password)?).into_account(),
+		AccountPublic::from(public::<sr25519::Public>(controller_suri, password)?).into_account(),
+		public::<BabeId>(session_suri, password)?,
+		public::<GrandpaId>(session_suri, password)?,
+		public::<ImOnlineId>(session_suri, password)?,
+		public::<AuthorityDiscoveryId>(session_suri, password)?,
+	))
+}
+
+/// Append a hard junction to a secret URI.
+fn derive_suri(suri: &str, junction: &str) -> String {
+	match suri.find("///") {
+		// The password part of the URI must stay at the end
+		Some(i) => format!("{}//{}{}", &suri[..i], junction, &suri[i..]),
+		None => format!("{}//{}", suri, junction),
+	}
+}
+
+/// The stash, controller and session keys of `count` dev authorities.
+///
+/// The authorities are `Alice`, `Bob`, ..., `Ferdie`, then `Validator<N>`.
+pub fn get_dev_authority_keys(count: u32) -> Vec<AuthorityKeys> {
+	const SEEDS: [&str; 6] = ["Alice", "Bob", "Charlie", "Dave", "Eve", "Ferdie"];
+
+	(0..count as usize)
+		.map(|i| {
+			SEEDS
+				.get(i)
+				.map(ToString::to_string)
+				.unwrap_or_else(|| format!("Validator{}", i + 1))
+		})
+		.map(|seed| get_authority_keys_from_seed(&seed))
+		.collect()
+}
+
+/// Parse an account from an SS58 address, a hex public key or a development seed such as
+/// `//Alice`.
+pub fn parse_account_id(account: &str) -> Result<AccountId, String> {
+	if account.starts_with("//") {
+		sr25519::Pair::from_string(account, None)
+			.map(|pair| AccountPublic::from(pair.public()).into_account())
+			.map_err(|e| format!("Invalid account `{}`: {:?}", account, e))
+	} else if account.starts_with("0x") {
+		let mut raw = [0; 32];
+		let bytes = sp_core::bytes::from_hex(account)
+			.map_err(|e| format!("Invalid account `{}`: {}", account, e))?;
+
+		if bytes.len() != 32 {
+			return Err(format!("Invalid account `{}`: expected 32 bytes", account));
+		}
+
+		raw.copy_from_slice(&bytes);
+
+		Ok(raw.into())
+	} else {
+		AccountId::from_ss58check(account)
+			.map_err(|e| format!("Invalid account `{}`: {:?}", account, e))
+	}
+}
+
 fn testnet_accounts() -> Vec<AccountId> {
 	vec![
 		get_account_id_from_seed::<sr25519::Public>("Alice"),
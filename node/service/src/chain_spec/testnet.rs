@@ -0,0 +1,356 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! Declarative description of a local testnet, see `crab_testnet_config` and
+//! `darwinia_testnet_config`.
+//!
+//! ```toml
+//! name = "Staging"
+//! id = "staging"
+//! validators = 3
+//! sudo = "//Alice"
+//! validatorCount = 3
+//!
+//! [[endowed]]
+//! account = "//Alice"
+//! ring = 1000000
+//! kton = 1000
+//!
+//! [[evmAccounts]]
+//! address = "0x6be02d1d3665660d22ff9624b7be0551ee1ac91b"
+//! balance = "0xffffffffffffffffffffffffffffffff"
+//! ```
+
+// --- std ---
+use std::{collections::BTreeMap, fs, path::Path};
+// --- crates ---
+use serde::Deserialize;
+// --- substrate ---
+use sp_core::{Bytes, H160, H256, U256};
+// --- darwinia ---
+use super::{get_dev_authority_keys, parse_account_id, AuthorityKeys};
+use darwinia_evm::GenesisAccount;
+use darwinia_primitives::{AccountId, Balance};
+
+/// The testnet genesis builders bond this amount of RING for every validator.
+const VALIDATOR_BOND: Balance = 1 << 56;
+
+/// A local testnet, read from a TOML or JSON file.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct TestnetDescription {
+	/// The human readable name of the chain.
+	#[serde(default = "default_name")]
+	pub name: String,
+	/// The id of the chain, which separates its database from the other chains.
+	#[serde(default = "default_id")]
+	pub id: String,
+	/// The number of genesis validators, their keys are derived from the development seeds.
+	pub validators: u32,
+	/// The accounts endowed at genesis.
+	#[serde(default)]
+	pub endowed: Vec<Endowment>,
+	/// The sudo key.
+	pub sudo: String,
+	/// The ideal number of validators of the staking pallet.
+	pub validator_count: Option<u32>,
+	/// The accounts of the EVM at genesis.
+	#[serde(default)]
+	pub evm_accounts: Vec<EvmAccount>,
+	/// The genesis header of the Ethereum relay, Darwinia only.
+	pub ethereum_relay_genesis_header: Option<EthereumRelayGenesisHeader>,
+}
+impl TestnetDescription {
+	/// Read a description from a `.toml` or a `.json` file.
+	pub fn from_file(path: &Path) -> Result<Self, String> {
+		let content = fs::read_to_string(path)
+			.map_err(|e| format!("Failed to read `{}`: {}", path.display(), e))?;
+
+		Self::parse(
+			&content,
+			path.extension().and_then(|extension| extension.to_str()),
+		)
+		.map_err(|e| format!("Invalid testnet description `{}`: {}", path.display(), e))
+	}
+
+	/// Parse a description written in the format of the file extension, `toml` or `json`.
+	pub fn parse(content: &str, extension: Option<&str>) -> Result<Self, String> {
+		match extension {
+			Some("toml") => toml::from_str(content).map_err(|e| e.to_string()),
+			Some("json") => serde_json::from_str(content).map_err(|e| e.to_string()),
+			_ => Err("Unknown description format, expected a `.toml` or a `.json` file".into()),
+		}
+	}
+
+	/// Resolve the accounts and the keys of the description.
+	///
+	/// `coin` is the number of planck of a RING or a KTON.
+	pub fn resolve(&self, coin: Balance) -> Result<TestnetGenesis, String> {
+		if self.validators == 0 {
+			return Err("A testnet needs at least one validator".into());
+		}
+
+		let authorities = get_dev_authority_keys(self.validators);
+		let mut endowed = BTreeMap::new();
+
+		for endowment in &self.endowed {
+			let (ring, kton) = endowed
+				.entry(parse_account_id(&endowment.account)?)
+				.or_insert((0, 0));
+
+			*ring += endowment.ring as Balance * coin;
+			*kton += endowment.kton as Balance * coin;
+		}
+		// Make sure the validators are able to bond
+		for (stash, controller, ..) in &authorities {
+			for account in [stash, controller].iter().cloned() {
+				let (ring, kton) = endowed.entry(account.to_owned()).or_insert((0, 0));
+
+				*ring = (*ring).max(VALIDATOR_BOND);
+				*kton = (*kton).max(VALIDATOR_BOND);
+			}
+		}
+
+		Ok(TestnetGenesis {
+			root: parse_account_id(&self.sudo)?,
+			authorities,
+			endowed: endowed
+				.into_iter()
+				.map(|(account, (ring, kton))| (account, ring, kton))
+				.collect(),
+			validator_count: self.validator_count,
+			evm_accounts: self
+				.evm_accounts
+				.iter()
+				.cloned()
+				.map(|account| {
+					(
+						account.address,
+						GenesisAccount {
+							nonce: account.nonce,
+							balance: account.balance,
+							storage: account.storage,
+							code: account.code.0,
+						},
+					)
+				})
+				.collect(),
+			ethereum_relay_genesis_header: self
+				.ethereum_relay_genesis_header
+				.clone()
+				.map(|genesis_header| (genesis_header.header.0, genesis_header.mmr_root)),
+		})
+	}
+}
+
+/// An account endowed at genesis.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Endowment {
+	/// An SS58 address, a hex public key or a development seed such as `//Alice`.
+	pub account: String,
+	/// The amount of RING, in RING.
+	#[serde(default)]
+	pub ring: u64,
+	/// The amount of KTON, in KTON.
+	#[serde(default)]
+	pub kton: u64,
+}
+
+/// An account of the EVM at genesis.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct EvmAccount {
+	/// The address of the account.
+	pub address: H160,
+	/// The balance of the account, in wei.
+	pub balance: U256,
+	/// The nonce of the account.
+	#[serde(default)]
+	pub nonce: U256,
+	/// The code of the account.
+	#[serde(default)]
+	pub code: Bytes,
+	/// The storage of the account.
+	#[serde(default)]
+	pub storage: BTreeMap<H256, H256>,
+}
+
+/// The genesis header of the Ethereum relay.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct EthereumRelayGenesisHeader {
+	/// The SCALE encoded header.
+	pub header: Bytes,
+	/// The MMR root at the header.
+	pub mmr_root: H256,
+}
+
+/// The resolved parameters of a testnet genesis.
+#[derive(Clone)]
+pub struct TestnetGenesis {
+	/// The sudo key.
+	pub root: AccountId,
+	/// The genesis validators.
+	pub authorities: Vec<AuthorityKeys>,
+	/// The endowed accounts with their RING and KTON balances.
+	pub endowed: Vec<(AccountId, Balance, Balance)>,
+	/// The ideal number of validators of the staking pallet.
+	pub validator_count: Option<u32>,
+	/// The accounts of the EVM at genesis.
+	pub evm_accounts: BTreeMap<H160, GenesisAccount>,
+	/// The genesis header info of the Ethereum relay.
+	pub ethereum_relay_genesis_header: Option<(Vec<u8>, H256)>,
+}
+
+fn default_name() -> String {
+	"Local Testnet".into()
+}
+
+fn default_id() -> String {
+	"local_testnet".into()
+}
+
+#[cfg(test)]
+mod tests {
+	// --- substrate ---
+	use sp_core::{crypto::Ss58Codec, sr25519};
+	// --- darwinia ---
+	use super::*;
+	use crate::chain_spec::get_account_id_from_seed;
+
+	const COIN: Balance = 1_000_000_000;
+
+	// Not one of the first validators, whose balances are raised to bond
+	fn ferdie() -> AccountId {
+		get_account_id_from_seed::<sr25519::Public>("Ferdie")
+	}
+
+	#[test]
+	fn toml_description_should_parse() {
+		let description = TestnetDescription::parse(
+			r#"
+				name = "Staging"
+				id = "staging"
+				validators = 3
+				sudo = "//Alice"
+				validatorCount = 3
+
+				[[endowed]]
+				account = "//Alice"
+				ring = 1000000
+				kton = 1000
+
+				[[evmAccounts]]
+				address = "0x6be02d1d3665660d22ff9624b7be0551ee1ac91b"
+				balance = "0xffffffffffffffffffffffffffffffff"
+			"#,
+			Some("toml"),
+		)
+		.unwrap();
+
+		assert_eq!(description.name, "Staging");
+		assert_eq!(description.id, "staging");
+		assert_eq!(description.validators, 3);
+		assert_eq!(description.validator_count, Some(3));
+		assert_eq!(description.endowed.len(), 1);
+		assert_eq!(description.endowed[0].ring, 1_000_000);
+		assert_eq!(description.evm_accounts.len(), 1);
+		assert_eq!(description.evm_accounts[0].nonce, U256::zero());
+		assert!(description.ethereum_relay_genesis_header.is_none());
+	}
+
+	#[test]
+	fn json_description_should_parse_with_defaults() {
+		let description =
+			TestnetDescription::parse(r#"{ "validators": 1, "sudo": "//Alice" }"#, Some("json"))
+				.unwrap();
+
+		assert_eq!(description.name, "Local Testnet");
+		assert_eq!(description.id, "local_testnet");
+		assert!(description.endowed.is_empty());
+		assert!(description.validator_count.is_none());
+	}
+
+	#[test]
+	fn invalid_description_should_be_rejected() {
+		// Unknown field
+		assert!(TestnetDescription::parse(
+			"validators = 1\nsudo = \"//Alice\"\nvalidator_count = 1",
+			Some("toml")
+		)
+		.is_err());
+		// Missing sudo
+		assert!(TestnetDescription::parse(r#"{ "validators": 1 }"#, Some("json")).is_err());
+		// Unknown format
+		assert!(TestnetDescription::parse("", Some("yaml")).is_err());
+	}
+
+	#[test]
+	fn accounts_should_resolve_from_seed_hex_and_ss58() {
+		let ferdie = ferdie();
+		let description = TestnetDescription::parse(
+			&format!(
+				r#"{{
+					"validators": 2,
+					"sudo": "{}",
+					"endowed": [
+						{{ "account": "//Ferdie", "ring": 1 }},
+						{{ "account": "{}", "ring": 2 }},
+						{{ "account": "{}", "kton": 3 }}
+					]
+				}}"#,
+				ferdie.to_ss58check(),
+				sp_core::bytes::to_hex(ferdie.as_ref(), false),
+				ferdie.to_ss58check(),
+			),
+			Some("json"),
+		)
+		.unwrap();
+		let genesis = description.resolve(COIN).unwrap();
+
+		assert_eq!(genesis.root, ferdie);
+		assert_eq!(genesis.authorities.len(), 2);
+		assert!(genesis
+			.endowed
+			.contains(&(ferdie.clone(), 3 * COIN, 3 * COIN)));
+		// Every stash and controller can bond
+		for (stash, controller, ..) in &genesis.authorities {
+			for account in [stash, controller].iter() {
+				assert!(genesis.endowed.iter().any(|(endowed, ring, kton)| {
+					&endowed == account && *ring >= VALIDATOR_BOND && *kton >= VALIDATOR_BOND
+				}));
+			}
+		}
+	}
+
+	#[test]
+	fn invalid_accounts_and_validators_should_fail_to_resolve() {
+		let resolve = |json: &str| {
+			TestnetDescription::parse(json, Some("json"))
+				.unwrap()
+				.resolve(COIN)
+		};
+
+		assert!(resolve(r#"{ "validators": 0, "sudo": "//Alice" }"#).is_err());
+		assert!(resolve(r#"{ "validators": 1, "sudo": "//" }"#).is_err());
+		assert!(resolve(r#"{ "validators": 1, "sudo": "//Alice//" }"#).is_err());
+		assert!(resolve(r#"{ "validators": 1, "sudo": "0x1234" }"#).is_err());
+		assert!(resolve(r#"{ "validators": 1, "sudo": "not an address" }"#).is_err());
+	}
+}
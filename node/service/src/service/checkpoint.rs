@@ -0,0 +1,138 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! The checkpoints of the chain spec, see `Checkpoint`.
+
+// --- crates ---
+use codec::Decode;
+use log::info;
+// --- substrate ---
+use sc_client_api::{Backend, StorageProvider};
+use sc_service::ChainSpec;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus_babe::BabeApi;
+use sp_core::{hashing::twox_128, storage::StorageKey};
+use sp_finality_grandpa::{GrandpaApi, SetId};
+use sp_runtime::generic::BlockId;
+// --- darwinia ---
+use crate::chain_spec::{Checkpoint, Extensions};
+use darwinia_primitives::{Hash, OpaqueBlock as Block};
+
+const LOG_TARGET: &str = "checkpoint";
+
+/// The checkpoint of a finalized block of the local chain, the last finalized one by default.
+///
+/// The GRANDPA set id is the one of the runtime, it differs from the one of the node only after a
+/// GRANDPA hard fork.
+pub fn export<C, BE>(client: &C, id: Option<BlockId<Block>>) -> Result<Checkpoint, String>
+where
+	C: HeaderBackend<Block> + ProvideRuntimeApi<Block> + StorageProvider<Block, BE>,
+	C::Api: GrandpaApi<Block> + BabeApi<Block>,
+	BE: Backend<Block>,
+{
+	let hash = match id {
+		Some(id) => client
+			.block_hash_from_id(&id)
+			.map_err(|e| format!("{:?}", e))?
+			.ok_or_else(|| format!("Block {} not found in the database", id))?,
+		None => client.info().finalized_hash,
+	};
+	let block_number = client
+		.number(hash)
+		.map_err(|e| format!("{:?}", e))?
+		.ok_or_else(|| format!("Block {} not found in the database", hash))?;
+	let info = client.info();
+
+	if block_number > info.finalized_number {
+		return Err(format!(
+			"Block #{} is not finalized yet, the last finalized block is #{}",
+			block_number, info.finalized_number
+		));
+	}
+	if client.hash(block_number).map_err(|e| format!("{:?}", e))? != Some(hash) {
+		return Err(format!("Block {} is not in the finalized chain", hash));
+	}
+
+	let at = BlockId::Hash(hash);
+	let runtime_api = client.runtime_api();
+	let grandpa_authorities = runtime_api
+		.grandpa_authorities(&at)
+		.map_err(|e| format!("Failed to read the GRANDPA authorities: {:?}", e))?;
+	let grandpa_set_id = client
+		.storage(&at, &grandpa_set_id_key())
+		.map_err(|e| format!("Failed to read the GRANDPA set id: {:?}", e))?
+		.map(|data| SetId::decode(&mut &*data.0))
+		.transpose()
+		.map_err(|e| format!("Failed to decode the GRANDPA set id: {}", e))?
+		.unwrap_or_default();
+	let babe_epoch = runtime_api
+		.current_epoch(&at)
+		.map_err(|e| format!("Failed to read the BABE epoch: {:?}", e))?;
+
+	Ok(Checkpoint {
+		block_hash: hash,
+		block_number,
+		grandpa_set_id,
+		grandpa_authorities,
+		babe_epoch: babe_epoch.into(),
+	})
+}
+
+/// Make sure the local chain is the one of the checkpoints of `chain_spec`, up to its best block.
+pub fn verify<C>(client: &C, chain_spec: &dyn ChainSpec) -> Result<(), String>
+where
+	C: HeaderBackend<Block>,
+{
+	let checkpoints = Extensions::checkpoints(chain_spec);
+	let best_number = client.info().best_number;
+
+	for checkpoint in checkpoints
+		.iter()
+		.filter(|checkpoint| checkpoint.block_number <= best_number)
+	{
+		let hash = client
+			.hash(checkpoint.block_number)
+			.map_err(|e| format!("{:?}", e))?;
+
+		if hash != Some(checkpoint.block_hash) {
+			return Err(format!(
+				"The local chain has block {:?} at #{} instead of the checkpoint {}, \
+				purge the chain to sync again",
+				hash, checkpoint.block_number, checkpoint.block_hash
+			));
+		}
+	}
+
+	if let Some(checkpoint) = checkpoints.last() {
+		info!(
+			target: LOG_TARGET,
+			"Checkpoint #{} ({}), GRANDPA set {}",
+			checkpoint.block_number,
+			checkpoint.block_hash,
+			checkpoint.grandpa_set_id
+		);
+	}
+
+	Ok(())
+}
+
+// `CurrentSetId` of `pallet_grandpa`, stored under its historical `GrandpaFinality` prefix.
+fn grandpa_set_id_key() -> StorageKey {
+	StorageKey([twox_128(b"GrandpaFinality"), twox_128(b"CurrentSetId")].concat())
+}
@@ -21,29 +21,28 @@
 pub use crab_runtime;
 
 // --- std ---
-use std::{
-	collections::{BTreeMap, HashMap},
-	sync::{Arc, Mutex},
-	time::Duration,
-};
+use std::sync::Arc;
 // --- crates ---
-use futures::stream::StreamExt;
+use futures::{channel::mpsc, stream::StreamExt};
 // --- substrate ---
 use sc_basic_authorship::ProposerFactory;
-use sc_client_api::{BlockchainEvents, ExecutorProvider, RemoteBackend, StateBackendFor};
+use sc_client_api::{ExecutorProvider, RemoteBackend, StateBackendFor};
 use sc_consensus::LongestChain;
 use sc_consensus_babe::{
 	BabeBlockImport, BabeLink, BabeParams, Config as BabeConfig, SlotProportion,
 };
+use sc_consensus_manual_seal::{
+	consensus::babe::{BabeConsensusDataProvider, SlotTimestampProvider},
+	ManualSealParams,
+};
 use sc_executor::{native_executor_instance, NativeExecutionDispatch};
 use sc_finality_grandpa::{
 	Config as GrandpaConfig, FinalityProofProvider as GrandpaFinalityProofProvider, GrandpaParams,
 	LinkHalf, SharedVoterState as GrandpaSharedVoterState,
-	VotingRulesBuilder as GrandpaVotingRulesBuilder,
 };
 use sc_network::{Event, NetworkService};
 use sc_service::{
-	config::KeystoreConfig, BasePath, BuildNetworkParams, Configuration, Error as ServiceError,
+	config::KeystoreConfig, BuildNetworkParams, Configuration, Error as ServiceError,
 	NoopRpcExtensionBuilder, PartialComponents, RpcHandlers, SpawnTasksParams, TaskManager,
 };
 use sc_telemetry::{Telemetry, TelemetryWorker};
@@ -56,17 +55,35 @@ use sp_inherents::InherentDataProviders;
 use sp_runtime::traits::BlakeTwo256;
 use sp_trie::PrefixedMemoryDB;
 // --- darwinia ---
-use crate::{client::CrabClient, service::*};
+use crate::{
+	chain_spec::{Extensions, GrandpaVotingRule},
+	client::CrabClient,
+	service::{
+		checkpoint,
+		dvm::{DvmComponents, DvmConfig},
+		health::{self, HealthConfig},
+		indexer,
+		metrics::{self, ChainMetrics},
+		sealing::{self, Sealing},
+		voting_rules::{self, ClientRelayConfirmations},
+		*,
+	},
+};
+use darwinia_indexer::Indexer;
 use darwinia_primitives::OpaqueBlock as Block;
 use darwinia_rpc::{
-	crab::{FullDeps, LightDeps},
+	crab::{DevDeps, FullDeps, LightDeps},
 	BabeDeps, DenyUnsafe, GrandpaDeps, RpcExtension, SubscriptionTaskExecutor,
 };
-use dc_db::{Backend, DatabaseSettings, DatabaseSettingsSrc};
-use dc_mapping_sync::MappingSyncWorker;
-use dc_rpc::EthTask;
-use dp_rpc::{FilterPool, PendingTransactions};
 
+#[cfg(feature = "runtime-benchmarks")]
+native_executor_instance!(
+	pub CrabExecutor,
+	crab_runtime::api::dispatch,
+	crab_runtime::native_version,
+	frame_benchmarking::benchmarking::HostFunctions,
+);
+#[cfg(not(feature = "runtime-benchmarks"))]
 native_executor_instance!(
 	pub CrabExecutor,
 	crab_runtime::api::dispatch,
@@ -75,27 +92,12 @@ native_executor_instance!(
 
 impl_runtime_apis!(dvm_rpc_runtime_api::EthereumRuntimeRPCApi<Block>);
 
-fn open_frontier_backend(config: &Configuration) -> Result<Arc<Backend<Block>>, String> {
-	let config_dir = config
-		.base_path
-		.as_ref()
-		.map(|base_path| base_path.config_dir(config.chain_spec.id()))
-		.unwrap_or_else(|| {
-			BasePath::from_project("", "", "crab").config_dir(config.chain_spec.id())
-		});
-	let database_dir = config_dir.join("dvm").join("db");
-
-	Ok(Arc::new(Backend::<Block>::new(&DatabaseSettings {
-		source: DatabaseSettingsSrc::RocksDb {
-			path: database_dir,
-			cache_size: 0,
-		},
-	})?))
-}
-
 #[cfg(feature = "full-node")]
 fn new_partial<RuntimeApi, Executor>(
 	config: &mut Configuration,
+	dvm_config: DvmConfig,
+	indexer: Option<Arc<Indexer>>,
+	remote_keystore: Option<Arc<RemoteKeystore>>,
 ) -> Result<
 	PartialComponents<
 		FullClient<RuntimeApi, Executor>,
@@ -121,9 +123,7 @@ fn new_partial<RuntimeApi, Executor>(
 			),
 			GrandpaSharedVoterState,
 			Option<Telemetry>,
-			PendingTransactions,
-			Arc<Backend<Block>>,
-			Option<FilterPool>,
+			DvmComponents,
 		),
 	>,
 	ServiceError,
@@ -135,12 +135,6 @@ where
 	RuntimeApi::RuntimeApi:
 		RuntimeApiCollection<StateBackend = StateBackendFor<FullBackend, Block>>,
 {
-	if config.keystore_remote.is_some() {
-		return Err(ServiceError::Other(format!(
-			"Remote Keystores are not supported."
-		)));
-	}
-
 	set_prometheus_registry(config)?;
 
 	let inherent_data_providers = InherentDataProviders::new();
@@ -154,11 +148,17 @@ where
 			Ok((worker, telemetry))
 		})
 		.transpose()?;
-	let (client, backend, keystore_container, task_manager) =
+	let (client, backend, mut keystore_container, task_manager) =
 		sc_service::new_full_parts::<Block, RuntimeApi, Executor>(
 			&config,
 			telemetry.as_ref().map(|(_, telemetry)| telemetry.handle()),
 		)?;
+
+	// Before anything takes the keystore, so that every key is looked up on the signer
+	if let Some(remote_keystore) = remote_keystore {
+		keystore_container.set_remote_keystore(remote_keystore);
+	}
+
 	let telemetry = telemetry.map(|(worker, telemetry)| {
 		task_manager.spawn_handle().spawn("telemetry", worker.run());
 		telemetry
@@ -172,7 +172,7 @@ where
 		task_manager.spawn_handle(),
 		client.clone(),
 	);
-	let grandpa_hard_forks = vec![];
+	let grandpa_hard_forks = Extensions::grandpa_authority_set_hard_forks(&*config.chain_spec);
 	let (grandpa_block_import, grandpa_link) =
 		sc_finality_grandpa::block_import_with_authority_set_hard_forks(
 			client.clone(),
@@ -211,26 +211,22 @@ where
 	let babe_config = babe_link.config().clone();
 	let shared_epoch_changes = babe_link.epoch_changes().clone();
 	// <--- dvm ---
-	let frontier_backend = open_frontier_backend(config)?;
+	let dvm_components = DvmComponents::new(config, dvm_config)?;
 	let subscription_task_executor = SubscriptionTaskExecutor::new(task_manager.spawn_handle());
-	let pending_transactions: PendingTransactions = Some(Arc::new(Mutex::new(HashMap::new())));
-	let filter_pool: Option<FilterPool> = Some(Arc::new(Mutex::new(BTreeMap::new())));
 	// --- dvm --->
 	let rpc_extensions_builder = {
 		let client = client.clone();
+		let backend = backend.clone();
 		let keystore = keystore_container.sync_keystore();
 		let transaction_pool = transaction_pool.clone();
 		let select_chain = select_chain.clone();
 		let chain_spec = config.chain_spec.cloned_box();
-		// <--- dvm ---
-		let pending_transactions = pending_transactions.clone();
-		let frontier_backend = frontier_backend.clone();
-		let filter_pool = filter_pool.clone();
-		// --- dvm --->
+		let dvm_components = dvm_components.clone();
 
 		move |deny_unsafe, is_authority, network, subscription_executor| -> RpcExtension {
 			let deps = FullDeps {
 				client: client.clone(),
+				backend: backend.clone(),
 				pool: transaction_pool.clone(),
 				select_chain: select_chain.clone(),
 				chain_spec: chain_spec.cloned_box(),
@@ -247,16 +243,15 @@ where
 					subscription_executor,
 					finality_provider: finality_proof_provider.clone(),
 				},
-				// <--- dvm ---
-				is_authority,
-				network,
-				pending_transactions: pending_transactions.clone(),
-				backend: frontier_backend.clone(),
-				filter_pool: filter_pool.clone(),
-				// --- dvm --->
+				dvm: dvm_components.rpc_deps(
+					is_authority,
+					network,
+					subscription_task_executor.clone(),
+				),
+				indexer: indexer.clone(),
 			};
 
-			darwinia_rpc::crab::create_full(deps, subscription_task_executor.clone())
+			darwinia_rpc::crab::create_full(deps)
 		}
 	};
 
@@ -274,9 +269,7 @@ where
 			import_setup,
 			rpc_setup,
 			telemetry,
-			pending_transactions,
-			frontier_backend,
-			filter_pool,
+			dvm_components,
 		),
 	})
 }
@@ -285,6 +278,10 @@ where
 fn new_full<RuntimeApi, Executor>(
 	mut config: Configuration,
 	authority_discovery_disabled: bool,
+	grandpa_voting_rules: Vec<GrandpaVotingRule>,
+	dvm_config: DvmConfig,
+	health_config: Option<HealthConfig>,
+	indexer_enabled: bool,
 ) -> Result<
 	(
 		TaskManager,
@@ -300,6 +297,8 @@ where
 	RuntimeApi::RuntimeApi:
 		RuntimeApiCollection<StateBackend = StateBackendFor<FullBackend, Block>>,
 {
+	warn_offchain_indexing(&config);
+
 	let role = config.role.clone();
 	let is_authority = role.is_authority();
 	let force_authoring = config.force_authoring;
@@ -307,38 +306,51 @@ where
 		Some(sc_consensus_slots::BackoffAuthoringOnFinalizedHeadLagging::default());
 	let disable_grandpa = config.disable_grandpa;
 	let name = config.network.node_name.clone();
+	let grandpa_parameters = Extensions::grandpa_parameters(&*config.chain_spec);
+	// The command line ones replace the ones of the chain spec
+	let grandpa_voting_rules = if grandpa_voting_rules.is_empty() {
+		grandpa_parameters.voting_rules.clone()
+	} else {
+		grandpa_voting_rules
+	};
+
+	if grandpa_voting_rules.contains(&GrandpaVotingRule::EthereumRelayConfirmed) {
+		return Err(ServiceError::Other(
+			"Crab has no Ethereum relay, `ethereum-relay-confirmed` is not a voting rule of it"
+				.into(),
+		));
+	}
+
+	let indexer = if indexer_enabled {
+		Some(indexer::open(&config)?)
+	} else {
+		None
+	};
+
+	// Only a full node signs, the chain operations never reach the signer
+	let remote_keystore = config
+		.keystore_remote
+		.as_deref()
+		.map(remote_keystore)
+		.transpose()?;
 	let PartialComponents {
 		client,
 		backend,
 		mut task_manager,
-		mut keystore_container,
+		keystore_container,
 		select_chain,
 		import_queue,
 		transaction_pool,
 		inherent_data_providers,
-		other:
-			(
-				rpc_extensions_builder,
-				import_setup,
-				rpc_setup,
-				mut telemetry,
-				pending_transactions,
-				frontier_backend,
-				filter_pool,
-			),
-	} = new_partial::<RuntimeApi, Executor>(&mut config)?;
-
-	if let Some(url) = &config.keystore_remote {
-		match remote_keystore(url) {
-			Ok(k) => keystore_container.set_remote_keystore(k),
-			Err(e) => {
-				return Err(ServiceError::Other(format!(
-					"Error hooking up remote keystore for {}: {}",
-					url, e
-				)))
-			}
-		};
-	}
+		other: (rpc_extensions_builder, import_setup, rpc_setup, mut telemetry, dvm_components),
+	} = new_partial::<RuntimeApi, Executor>(
+		&mut config,
+		dvm_config,
+		indexer.clone(),
+		remote_keystore,
+	)?;
+
+	checkpoint::verify(&*client, &*config.chain_spec)?;
 
 	let prometheus_registry = config.prometheus_registry().cloned();
 	let shared_voter_state = rpc_setup;
@@ -378,6 +390,26 @@ where
 		);
 	}
 
+	if let Some(health_config) = health_config {
+		let keystore = if is_authority {
+			Some(keystore_container.sync_keystore())
+		} else {
+			None
+		};
+
+		health::spawn(
+			&task_manager,
+			health_config,
+			client.clone(),
+			keystore,
+			&network_status_sinks,
+		)?;
+	}
+
+	if let Some(indexer) = indexer {
+		indexer::spawn::<crab_runtime::Event, _, _>(&task_manager, indexer, client.clone());
+	}
+
 	let rpc_handlers = sc_service::spawn_tasks(SpawnTasksParams {
 		config,
 		backend: backend.clone(),
@@ -448,9 +480,8 @@ where
 		None
 	};
 	let grandpa_config = GrandpaConfig {
-		// FIXME substrate#1578 make this available through chainspec
-		gossip_duration: Duration::from_millis(1000),
-		justification_period: 512,
+		gossip_duration: grandpa_parameters.gossip_duration(),
+		justification_period: grandpa_parameters.justification_period(),
 		name: Some(name),
 		observer_enabled: false,
 		keystore,
@@ -465,7 +496,10 @@ where
 			link: link_half,
 			network: network.clone(),
 			telemetry: telemetry.as_ref().map(|x| x.handle()),
-			voting_rule: GrandpaVotingRulesBuilder::default().build(),
+			voting_rule: voting_rules::voting_rule(
+				&grandpa_voting_rules,
+				ClientRelayConfirmations::<_, FullBackend>::new(client.clone()),
+			),
 			prometheus_registry: prometheus_registry.clone(),
 			shared_voter_state,
 		};
@@ -476,6 +510,21 @@ where
 		);
 	}
 
+	if let Some(registry) = prometheus_registry.as_ref() {
+		let keystore = if is_authority {
+			Some(keystore_container.sync_keystore())
+		} else {
+			None
+		};
+		let chain_metrics = ChainMetrics::register(registry)
+			.map_err(|e| format!("Failed to register the chain metrics: {}", e))?;
+
+		task_manager.spawn_handle().spawn(
+			"chain-metrics",
+			metrics::chain_metrics_task(client.clone(), keystore, chain_metrics),
+		);
+	}
+
 	if role.is_authority() && !authority_discovery_disabled {
 		let authority_discovery_role =
 			sc_authority_discovery::Role::PublishAndDiscover(keystore_container.keystore());
@@ -502,42 +551,177 @@ where
 		);
 	}
 
-	// <--- dvm ---
-	// Spawn Frontier pending transactions maintenance task (as essential, otherwise we leak).
-	if let Some(pending_transactions) = pending_transactions {
-		const TRANSACTION_RETAIN_THRESHOLD: u64 = 5;
-		task_manager.spawn_essential_handle().spawn(
-			"frontier-pending-transactions",
-			EthTask::pending_transaction_task(
-				Arc::clone(&client),
-				pending_transactions,
-				TRANSACTION_RETAIN_THRESHOLD,
-			),
-		);
+	dvm::spawn_tasks(&task_manager, client.clone(), backend, dvm_components);
+
+	network_starter.start_network();
+
+	Ok((task_manager, client, rpc_handlers))
+}
+
+/// A development node sealing its blocks with the manual seal, without BABE and GRANDPA.
+///
+/// The blocks still carry BABE pre-digests, the runtime requiring them, and the timestamps advance
+/// by one slot per block, however fast they are sealed.
+#[cfg(feature = "full-node")]
+fn new_dev<RuntimeApi, Executor>(
+	mut config: Configuration,
+	sealing: Sealing,
+	dvm_config: DvmConfig,
+) -> Result<
+	(
+		TaskManager,
+		Arc<FullClient<RuntimeApi, Executor>>,
+		RpcHandlers,
+	),
+	ServiceError,
+>
+where
+	Executor: 'static + NativeExecutionDispatch,
+	RuntimeApi:
+		'static + Send + Sync + ConstructRuntimeApi<Block, FullClient<RuntimeApi, Executor>>,
+	RuntimeApi::RuntimeApi:
+		RuntimeApiCollection<StateBackend = StateBackendFor<FullBackend, Block>>,
+{
+	set_prometheus_registry(&mut config)?;
+	warn_offchain_indexing(&config);
+
+	let remote_keystore = config
+		.keystore_remote
+		.as_deref()
+		.map(remote_keystore)
+		.transpose()?;
+	let (client, backend, mut keystore_container, mut task_manager) =
+		sc_service::new_full_parts::<Block, RuntimeApi, Executor>(&config, None)?;
+
+	// Before anything takes the keystore, so that every key is looked up on the signer
+	if let Some(remote_keystore) = remote_keystore {
+		keystore_container.set_remote_keystore(remote_keystore);
 	}
 
-	task_manager.spawn_essential_handle().spawn(
-		"frontier-mapping-sync-worker",
-		MappingSyncWorker::new(
-			client.import_notification_stream(),
-			Duration::new(6, 0),
-			client.clone(),
-			backend.clone(),
-			frontier_backend.clone(),
-		)
-		.for_each(|()| futures::future::ready(())),
+	let client = Arc::new(client);
+	let select_chain = LongestChain::new(backend.clone());
+	let transaction_pool = BasicPool::new_full(
+		config.transaction_pool.clone(),
+		config.role.is_authority().into(),
+		config.prometheus_registry(),
+		task_manager.spawn_handle(),
+		client.clone(),
 	);
+	let (block_import, babe_link) = sc_consensus_babe::block_import(
+		BabeConfig::get_or_compute(&*client)?,
+		client.clone(),
+		client.clone(),
+	)?;
+	let import_queue = sc_consensus_manual_seal::import_queue(
+		Box::new(block_import.clone()),
+		&task_manager.spawn_essential_handle(),
+		config.prometheus_registry(),
+	);
+	let prometheus_registry = config.prometheus_registry().cloned();
+	let is_authority = config.role.is_authority();
+	let dvm_components = DvmComponents::new(&config, dvm_config)?;
+	let subscription_task_executor = SubscriptionTaskExecutor::new(task_manager.spawn_handle());
+	let (network, network_status_sinks, system_rpc_tx, network_starter) =
+		sc_service::build_network(BuildNetworkParams {
+			config: &config,
+			client: client.clone(),
+			transaction_pool: transaction_pool.clone(),
+			spawn_handle: task_manager.spawn_handle(),
+			import_queue,
+			on_demand: None,
+			block_announce_validator_builder: None,
+		})?;
 
-	// Spawn Frontier EthFilterApi maintenance task.
-	if let Some(filter_pool) = filter_pool {
-		// Each filter is allowed to stay in the pool for 100 blocks.
-		const FILTER_RETAIN_THRESHOLD: u64 = 100;
-		task_manager.spawn_essential_handle().spawn(
-			"frontier-filter-pool",
-			EthTask::filter_pool_task(Arc::clone(&client), filter_pool, FILTER_RETAIN_THRESHOLD),
+	if config.offchain_worker.enabled {
+		sc_service::build_offchain_workers(
+			&config,
+			task_manager.spawn_handle(),
+			client.clone(),
+			network.clone(),
 		);
 	}
-	// --- dvm --->
+
+	let (command_sink, rpc_commands) = mpsc::channel(1000);
+	let rpc_extensions_builder = {
+		let client = client.clone();
+		let backend = backend.clone();
+		let transaction_pool = transaction_pool.clone();
+		let network = network.clone();
+		let dvm_components = dvm_components.clone();
+
+		move |deny_unsafe, _| -> RpcExtension {
+			let deps = DevDeps {
+				client: client.clone(),
+				backend: backend.clone(),
+				pool: transaction_pool.clone(),
+				deny_unsafe,
+				dvm: dvm_components.rpc_deps(
+					is_authority,
+					network.clone(),
+					subscription_task_executor.clone(),
+				),
+				command_sink: command_sink.clone(),
+			};
+
+			darwinia_rpc::crab::create_dev(deps)
+		}
+	};
+	let rpc_handlers = sc_service::spawn_tasks(SpawnTasksParams {
+		config,
+		backend: backend.clone(),
+		client: client.clone(),
+		keystore: keystore_container.sync_keystore(),
+		network,
+		rpc_extensions_builder: Box::new(rpc_extensions_builder),
+		transaction_pool: transaction_pool.clone(),
+		task_manager: &mut task_manager,
+		on_demand: None,
+		remote_blockchain: None,
+		network_status_sinks,
+		system_rpc_tx,
+		telemetry: None,
+	})?;
+
+	let inherent_data_providers = InherentDataProviders::new();
+
+	inherent_data_providers
+		.register_provider(
+			SlotTimestampProvider::new(client.clone())
+				.map_err(|e| format!("Failed to create the timestamp provider: {}", e))?,
+		)
+		.map_err(|e| format!("Failed to register the timestamp provider: {:?}", e))?;
+
+	let consensus_data_provider = BabeConsensusDataProvider::new(
+		client.clone(),
+		keystore_container.sync_keystore(),
+		&inherent_data_providers,
+		babe_link.epoch_changes().clone(),
+		babe_link.config().genesis_authorities.clone(),
+	)
+	.map_err(|e| format!("Failed to create the BABE digest provider: {}", e))?;
+	let proposer = ProposerFactory::new(
+		task_manager.spawn_handle(),
+		client.clone(),
+		transaction_pool.clone(),
+		prometheus_registry.as_ref(),
+		None,
+	);
+	let manual_seal = sc_consensus_manual_seal::run_manual_seal(ManualSealParams {
+		block_import,
+		env: proposer,
+		client: client.clone(),
+		pool: transaction_pool.pool().clone(),
+		commands_stream: sealing::commands_stream(sealing, transaction_pool, rpc_commands),
+		select_chain,
+		consensus_data_provider: Some(Box::new(consensus_data_provider)),
+		inherent_data_providers,
+	});
+
+	task_manager
+		.spawn_essential_handle()
+		.spawn_blocking("manual-seal", manual_seal);
+
+	dvm::spawn_tasks(&task_manager, client.clone(), backend, dvm_components);
 
 	network_starter.start_network();
 
@@ -596,12 +780,15 @@ where
 		client.clone(),
 		on_demand.clone(),
 	));
-	let (grandpa_block_import, _) = sc_finality_grandpa::block_import(
-		client.clone(),
-		&(client.clone() as Arc<_>),
-		select_chain.clone(),
-		telemetry.as_ref().map(|x| x.handle()),
-	)?;
+	let grandpa_hard_forks = Extensions::grandpa_authority_set_hard_forks(&*config.chain_spec);
+	let (grandpa_block_import, _) =
+		sc_finality_grandpa::block_import_with_authority_set_hard_forks(
+			client.clone(),
+			&(client.clone() as Arc<_>),
+			select_chain.clone(),
+			grandpa_hard_forks,
+			telemetry.as_ref().map(|x| x.handle()),
+		)?;
 	let justification_import = grandpa_block_import.clone();
 	let (babe_block_import, babe_link) = sc_consensus_babe::block_import(
 		BabeConfig::get_or_compute(&*client)?,
@@ -697,7 +884,7 @@ where
 		import_queue,
 		task_manager,
 		..
-	} = new_partial::<Runtime, Dispatch>(config)?;
+	} = new_partial::<Runtime, Dispatch>(config, DvmConfig::default(), None, None)?;
 
 	Ok((client, backend, import_queue, task_manager))
 }
@@ -707,6 +894,10 @@ where
 pub fn crab_new_full(
 	config: Configuration,
 	authority_discovery_disabled: bool,
+	grandpa_voting_rules: Vec<GrandpaVotingRule>,
+	dvm_config: DvmConfig,
+	health_config: Option<HealthConfig>,
+	indexer_enabled: bool,
 ) -> Result<
 	(
 		TaskManager,
@@ -715,12 +906,35 @@ pub fn crab_new_full(
 	),
 	ServiceError,
 > {
-	let (components, client, rpc_handlers) =
-		new_full::<crab_runtime::RuntimeApi, CrabExecutor>(config, authority_discovery_disabled)?;
+	let (components, client, rpc_handlers) = new_full::<crab_runtime::RuntimeApi, CrabExecutor>(
+		config,
+		authority_discovery_disabled,
+		grandpa_voting_rules,
+		dvm_config,
+		health_config,
+		indexer_enabled,
+	)?;
 
 	Ok((components, client, rpc_handlers))
 }
 
+/// Create a new Crab service for a development node sealing its own blocks.
+#[cfg(feature = "full-node")]
+pub fn crab_new_dev(
+	config: Configuration,
+	sealing: Sealing,
+	dvm_config: DvmConfig,
+) -> Result<
+	(
+		TaskManager,
+		Arc<impl CrabClient<Block, FullBackend, crab_runtime::RuntimeApi>>,
+		RpcHandlers,
+	),
+	ServiceError,
+> {
+	new_dev::<crab_runtime::RuntimeApi, CrabExecutor>(config, sealing, dvm_config)
+}
+
 /// Create a new Crab service for a light client.
 pub fn crab_new_light(config: Configuration) -> Result<(TaskManager, RpcHandlers), ServiceError> {
 	new_light::<crab_runtime::RuntimeApi, CrabExecutor>(config)
@@ -21,7 +21,7 @@
 pub use darwinia_runtime;
 
 // --- std ---
-use std::{sync::Arc, time::Duration};
+use std::sync::Arc;
 // --- crates ---
 use futures::stream::StreamExt;
 // --- substrate ---
@@ -35,9 +35,8 @@ use sc_executor::{native_executor_instance, NativeExecutionDispatch};
 use sc_finality_grandpa::{
 	Config as GrandpaConfig, FinalityProofProvider as GrandpaFinalityProofProvider, GrandpaParams,
 	LinkHalf, SharedVoterState as GrandpaSharedVoterState,
-	VotingRulesBuilder as GrandpaVotingRulesBuilder,
 };
-use sc_network::Event;
+use sc_network::{Event, NetworkService};
 use sc_service::{
 	config::KeystoreConfig, BuildNetworkParams, Configuration, Error as ServiceError,
 	NoopRpcExtensionBuilder, PartialComponents, RpcHandlers, SpawnTasksParams, TaskManager,
@@ -52,24 +51,48 @@ use sp_inherents::InherentDataProviders;
 use sp_runtime::traits::BlakeTwo256;
 use sp_trie::PrefixedMemoryDB;
 // --- darwinia ---
-use crate::{client::DarwiniaClient, service::*};
+use crate::{
+	chain_spec::{Extensions, GrandpaVotingRule},
+	client::DarwiniaClient,
+	service::{
+		checkpoint,
+		dvm::{DvmComponents, DvmConfig},
+		health::{self, HealthConfig},
+		indexer,
+		metrics::{self, ChainMetrics},
+		voting_rules::{self, ClientRelayConfirmations},
+		*,
+	},
+};
+use darwinia_indexer::Indexer;
 use darwinia_primitives::OpaqueBlock as Block;
 use darwinia_rpc::{
 	darwinia::{FullDeps, LightDeps},
 	BabeDeps, DenyUnsafe, GrandpaDeps, RpcExtension, SubscriptionTaskExecutor,
 };
 
+#[cfg(feature = "runtime-benchmarks")]
+native_executor_instance!(
+	pub DarwiniaExecutor,
+	darwinia_runtime::api::dispatch,
+	darwinia_runtime::native_version,
+	frame_benchmarking::benchmarking::HostFunctions,
+);
+#[cfg(not(feature = "runtime-benchmarks"))]
 native_executor_instance!(
 	pub DarwiniaExecutor,
 	darwinia_runtime::api::dispatch,
 	darwinia_runtime::native_version,
 );
 
-impl_runtime_apis!();
+impl_runtime_apis!(dvm_rpc_runtime_api::EthereumRuntimeRPCApi<Block>);
 
 #[cfg(feature = "full-node")]
 fn new_partial<RuntimeApi, Executor>(
 	config: &mut Configuration,
+	dvm_config: DvmConfig,
+	indexer: Option<Arc<Indexer>>,
+	remote_keystore: Option<Arc<RemoteKeystore>>,
 ) -> Result<
 	PartialComponents<
 		FullClient<RuntimeApi, Executor>,
@@ -78,7 +101,12 @@ fn new_partial<RuntimeApi, Executor>(
 		DefaultImportQueue<Block, FullClient<RuntimeApi, Executor>>,
 		FullPool<Block, FullClient<RuntimeApi, Executor>>,
 		(
-			impl Fn(DenyUnsafe, SubscriptionTaskExecutor) -> RpcExtension,
+			impl Fn(
+				DenyUnsafe,
+				bool,
+				Arc<NetworkService<Block, Hash>>,
+				SubscriptionTaskExecutor,
+			) -> RpcExtension,
 			(
 				BabeBlockImport<
 					Block,
@@ -90,6 +118,7 @@ fn new_partial<RuntimeApi, Executor>(
 			),
 			GrandpaSharedVoterState,
 			Option<Telemetry>,
+			DvmComponents,
 		),
 	>,
 	ServiceError,
@@ -101,12 +130,6 @@ where
 	RuntimeApi::RuntimeApi:
 		RuntimeApiCollection<StateBackend = StateBackendFor<FullBackend, Block>>,
 {
-	if config.keystore_remote.is_some() {
-		return Err(ServiceError::Other(format!(
-			"Remote Keystores are not supported."
-		)));
-	}
-
 	set_prometheus_registry(config)?;
 
 	let inherent_data_providers = InherentDataProviders::new();
@@ -120,11 +143,17 @@ where
 			Ok((worker, telemetry))
 		})
 		.transpose()?;
-	let (client, backend, keystore_container, task_manager) =
+	let (client, backend, mut keystore_container, task_manager) =
 		sc_service::new_full_parts::<Block, RuntimeApi, Executor>(
 			&config,
 			telemetry.as_ref().map(|(_, telemetry)| telemetry.handle()),
 		)?;
+
+	// Before anything takes the keystore, so that every key is looked up on the signer
+	if let Some(remote_keystore) = remote_keystore {
+		keystore_container.set_remote_keystore(remote_keystore);
+	}
+
 	let telemetry = telemetry.map(|(worker, telemetry)| {
 		task_manager.spawn_handle().spawn("telemetry", worker.run());
 		telemetry
@@ -138,7 +167,7 @@ where
 		task_manager.spawn_handle(),
 		client.clone(),
 	);
-	let grandpa_hard_forks = vec![];
+	let grandpa_hard_forks = Extensions::grandpa_authority_set_hard_forks(&*config.chain_spec);
 	let (grandpa_block_import, grandpa_link) =
 		sc_finality_grandpa::block_import_with_authority_set_hard_forks(
 			client.clone(),
@@ -176,16 +205,23 @@ where
 	let rpc_setup = shared_voter_state.clone();
 	let babe_config = babe_link.config().clone();
 	let shared_epoch_changes = babe_link.epoch_changes().clone();
+	// <--- dvm ---
+	let dvm_components = DvmComponents::new(config, dvm_config)?;
+	let subscription_task_executor = SubscriptionTaskExecutor::new(task_manager.spawn_handle());
+	// --- dvm --->
 	let rpc_extensions_builder = {
 		let client = client.clone();
+		let backend = backend.clone();
 		let keystore = keystore_container.sync_keystore();
 		let transaction_pool = transaction_pool.clone();
 		let select_chain = select_chain.clone();
 		let chain_spec = config.chain_spec.cloned_box();
+		let dvm_components = dvm_components.clone();
 
-		move |deny_unsafe, subscription_executor| -> RpcExtension {
+		move |deny_unsafe, is_authority, network, subscription_executor| -> RpcExtension {
 			let deps = FullDeps {
 				client: client.clone(),
+				backend: backend.clone(),
 				pool: transaction_pool.clone(),
 				select_chain: select_chain.clone(),
 				chain_spec: chain_spec.cloned_box(),
@@ -202,6 +238,12 @@ where
 					subscription_executor,
 					finality_provider: finality_proof_provider.clone(),
 				},
+				dvm: dvm_components.rpc_deps(
+					is_authority,
+					network,
+					subscription_task_executor.clone(),
+				),
+				indexer: indexer.clone(),
 			};
 
 			darwinia_rpc::darwinia::create_full(deps)
@@ -217,7 +259,13 @@ where
 		import_queue,
 		transaction_pool,
 		inherent_data_providers,
-		other: (rpc_extensions_builder, import_setup, rpc_setup, telemetry),
+		other: (
+			rpc_extensions_builder,
+			import_setup,
+			rpc_setup,
+			telemetry,
+			dvm_components,
+		),
 	})
 }
 
@@ -225,6 +273,10 @@ where
 fn new_full<RuntimeApi, Executor>(
 	mut config: Configuration,
 	authority_discovery_disabled: bool,
+	grandpa_voting_rules: Vec<GrandpaVotingRule>,
+	dvm_config: DvmConfig,
+	health_config: Option<HealthConfig>,
+	indexer_enabled: bool,
 ) -> Result<
 	(
 		TaskManager,
@@ -240,6 +292,8 @@ where
 	RuntimeApi::RuntimeApi:
 		RuntimeApiCollection<StateBackend = StateBackendFor<FullBackend, Block>>,
 {
+	warn_offchain_indexing(&config);
+
 	let role = config.role.clone();
 	let is_authority = role.is_authority();
 	let force_authoring = config.force_authoring;
@@ -247,29 +301,43 @@ where
 		Some(sc_consensus_slots::BackoffAuthoringOnFinalizedHeadLagging::default());
 	let disable_grandpa = config.disable_grandpa;
 	let name = config.network.node_name.clone();
+	let grandpa_parameters = Extensions::grandpa_parameters(&*config.chain_spec);
+	// The command line ones replace the ones of the chain spec
+	let grandpa_voting_rules = if grandpa_voting_rules.is_empty() {
+		grandpa_parameters.voting_rules.clone()
+	} else {
+		grandpa_voting_rules
+	};
+	let indexer = if indexer_enabled {
+		Some(indexer::open(&config)?)
+	} else {
+		None
+	};
+
+	// Only a full node signs, the chain operations never reach the signer
+	let remote_keystore = config
+		.keystore_remote
+		.as_deref()
+		.map(remote_keystore)
+		.transpose()?;
 	let PartialComponents {
 		client,
 		backend,
 		mut task_manager,
-		mut keystore_container,
+		keystore_container,
 		select_chain,
 		import_queue,
 		transaction_pool,
 		inherent_data_providers,
-		other: (rpc_extensions_builder, import_setup, rpc_setup, mut telemetry),
-	} = new_partial::<RuntimeApi, Executor>(&mut config)?;
-
-	if let Some(url) = &config.keystore_remote {
-		match remote_keystore(url) {
-			Ok(k) => keystore_container.set_remote_keystore(k),
-			Err(e) => {
-				return Err(ServiceError::Other(format!(
-					"Error hooking up remote keystore for {}: {}",
-					url, e
-				)))
-			}
-		};
-	}
+		other: (rpc_extensions_builder, import_setup, rpc_setup, mut telemetry, dvm_components),
+	} = new_partial::<RuntimeApi, Executor>(
+		&mut config,
+		dvm_config,
+		indexer.clone(),
+		remote_keystore,
+	)?;
+
+	checkpoint::verify(&*client, &*config.chain_spec)?;
 
 	let prometheus_registry = config.prometheus_registry().cloned();
 	let shared_voter_state = rpc_setup;
@@ -309,13 +377,48 @@ where
 		);
 	}
 
+	if let Some(health_config) = health_config {
+		let keystore = if is_authority {
+			Some(keystore_container.sync_keystore())
+		} else {
+			None
+		};
+
+		health::spawn(
+			&task_manager,
+			health_config,
+			client.clone(),
+			keystore,
+			&network_status_sinks,
+		)?;
+	}
+
+	if let Some(indexer) = indexer {
+		indexer::spawn::<darwinia_runtime::Event, _, _>(&task_manager, indexer, client.clone());
+	}
+
 	let rpc_handlers = sc_service::spawn_tasks(SpawnTasksParams {
 		config,
 		backend: backend.clone(),
 		client: client.clone(),
 		keystore: keystore_container.sync_keystore(),
 		network: network.clone(),
-		rpc_extensions_builder: Box::new(rpc_extensions_builder),
+		rpc_extensions_builder: {
+			let wrap_rpc_extensions_builder = {
+				let network = network.clone();
+
+				move |deny_unsafe, subscription_executor| -> RpcExtension {
+					rpc_extensions_builder(
+						deny_unsafe,
+						is_authority,
+						network.clone(),
+						subscription_executor,
+					)
+				}
+			};
+
+			Box::new(wrap_rpc_extensions_builder)
+		},
 		transaction_pool: transaction_pool.clone(),
 		task_manager: &mut task_manager,
 		on_demand: None,
@@ -364,9 +467,8 @@ where
 		None
 	};
 	let grandpa_config = GrandpaConfig {
-		// FIXME substrate#1578 make this available through chainspec
-		gossip_duration: Duration::from_millis(1000),
-		justification_period: 512,
+		gossip_duration: grandpa_parameters.gossip_duration(),
+		justification_period: grandpa_parameters.justification_period(),
 		name: Some(name),
 		observer_enabled: false,
 		keystore,
@@ -381,7 +483,10 @@ where
 			link: link_half,
 			network: network.clone(),
 			telemetry: telemetry.as_ref().map(|x| x.handle()),
-			voting_rule: GrandpaVotingRulesBuilder::default().build(),
+			voting_rule: voting_rules::voting_rule(
+				&grandpa_voting_rules,
+				ClientRelayConfirmations::<_, FullBackend>::new(client.clone()),
+			),
 			prometheus_registry: prometheus_registry.clone(),
 			shared_voter_state,
 		};
@@ -392,6 +497,21 @@ where
 		);
 	}
 
+	if let Some(registry) = prometheus_registry.as_ref() {
+		let keystore = if is_authority {
+			Some(keystore_container.sync_keystore())
+		} else {
+			None
+		};
+		let chain_metrics = ChainMetrics::register(registry)
+			.map_err(|e| format!("Failed to register the chain metrics: {}", e))?;
+
+		task_manager.spawn_handle().spawn(
+			"chain-metrics",
+			metrics::chain_metrics_task(client.clone(), keystore, chain_metrics),
+		);
+	}
+
 	if role.is_authority() && !authority_discovery_disabled {
 		let authority_discovery_role =
 			sc_authority_discovery::Role::PublishAndDiscover(keystore_container.keystore());
@@ -418,6 +538,8 @@ where
 		);
 	}
 
+	dvm::spawn_tasks(&task_manager, client.clone(), backend, dvm_components);
+
 	network_starter.start_network();
 
 	Ok((task_manager, client, rpc_handlers))
@@ -475,12 +597,15 @@ where
 		client.clone(),
 		on_demand.clone(),
 	));
-	let (grandpa_block_import, _) = sc_finality_grandpa::block_import(
-		client.clone(),
-		&(client.clone() as Arc<_>),
-		select_chain.clone(),
-		telemetry.as_ref().map(|x| x.handle()),
-	)?;
+	let grandpa_hard_forks = Extensions::grandpa_authority_set_hard_forks(&*config.chain_spec);
+	let (grandpa_block_import, _) =
+		sc_finality_grandpa::block_import_with_authority_set_hard_forks(
+			client.clone(),
+			&(client.clone() as Arc<_>),
+			select_chain.clone(),
+			grandpa_hard_forks,
+			telemetry.as_ref().map(|x| x.handle()),
+		)?;
 	let justification_import = grandpa_block_import.clone();
 	let (babe_block_import, babe_link) = sc_consensus_babe::block_import(
 		BabeConfig::get_or_compute(&*client)?,
@@ -576,7 +701,7 @@ where
 		import_queue,
 		task_manager,
 		..
-	} = new_partial::<Runtime, Dispatch>(config)?;
+	} = new_partial::<Runtime, Dispatch>(config, DvmConfig::default(), None, None)?;
 
 	Ok((client, backend, import_queue, task_manager))
 }
@@ -586,6 +711,10 @@ where
 pub fn darwinia_new_full(
 	config: Configuration,
 	authority_discovery_disabled: bool,
+	grandpa_voting_rules: Vec<GrandpaVotingRule>,
+	dvm_config: DvmConfig,
+	health_config: Option<HealthConfig>,
+	indexer_enabled: bool,
 ) -> Result<
 	(
 		TaskManager,
@@ -594,10 +723,15 @@ pub fn darwinia_new_full(
 	),
 	ServiceError,
 > {
-	let (components, client, rpc_handlers) = new_full::<
-		darwinia_runtime::RuntimeApi,
-		DarwiniaExecutor,
-	>(config, authority_discovery_disabled)?;
+	let (components, client, rpc_handlers) =
+		new_full::<darwinia_runtime::RuntimeApi, DarwiniaExecutor>(
+			config,
+			authority_discovery_disabled,
+			grandpa_voting_rules,
+			dvm_config,
+			health_config,
+			indexer_enabled,
+		)?;
 
 	Ok((components, client, rpc_handlers))
 }
@@ -0,0 +1,138 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! Offline execution of a runtime upgrade against an in memory copy of a state.
+
+// --- std ---
+use std::collections::BTreeMap;
+// --- crates ---
+use codec::Decode;
+// --- substrate ---
+use frame_metadata::RuntimeMetadataPrefixed;
+use sc_executor::{NativeExecutor, WasmExecutionMethod};
+use sp_core::{
+	storage::{well_known_keys, Storage},
+	testing::TaskExecutor,
+};
+use sp_runtime::traits::BlakeTwo256;
+use sp_state_machine::{
+	backend::BackendRuntimeCode, Backend, ExecutionStrategy, InMemoryBackend, OverlayedChanges,
+	StateMachine,
+};
+// --- darwinia ---
+use super::darwinia::DarwiniaExecutor;
+use darwinia_primitives::BlockNumber;
+
+/// The result of `TryRuntime_on_runtime_upgrade` on a copy of a state.
+pub struct UpgradeDryRun {
+	/// The weight consumed by the upgrade and the maximum weight of a block, or the error the
+	/// upgrade, its `pre_upgrade` or its `post_upgrade` failed with.
+	pub result: Result<(u64, u64), String>,
+	/// The top storage items written by the upgrade, with their values before and after it.
+	pub changes: BTreeMap<Vec<u8>, (Option<Vec<u8>>, Option<Vec<u8>>)>,
+	/// The child storage items written by the upgrade, by the storage key of their child trie.
+	pub child_changes: BTreeMap<Vec<u8>, BTreeMap<Vec<u8>, (Option<Vec<u8>>, Option<Vec<u8>>)>>,
+	/// The number of heap pages the upgrade ran with, `None` for the default of the executor.
+	pub heap_pages: Option<u64>,
+	/// The metadata of the upgraded runtime.
+	pub metadata: Option<RuntimeMetadataPrefixed>,
+}
+
+/// Run the upgrade to `code` on `storage`, as the first block after a `set_code` would.
+///
+/// `code` must be built with the `try-runtime` feature. Nothing is persisted, the upgrade runs on
+/// an in memory backend, child tries included, and its changes are only collected.
+///
+/// As on a node, the `:heappages` of the state takes precedence over `heap_pages`.
+pub fn dry_run_runtime_upgrade(
+	mut storage: Storage,
+	code: Vec<u8>,
+	wasm_method: WasmExecutionMethod,
+	heap_pages: Option<u64>,
+) -> Result<UpgradeDryRun, String> {
+	storage.top.insert(well_known_keys::CODE.to_vec(), code);
+
+	let executor = NativeExecutor::<DarwiniaExecutor>::new(wasm_method, heap_pages, 1);
+	let backend = InMemoryBackend::<BlakeTwo256>::from(storage);
+	let runtime_code = BackendRuntimeCode::new(&backend).runtime_code()?;
+	let heap_pages = runtime_code.heap_pages.or(heap_pages);
+	let call = |method: &str, overlay: &mut OverlayedChanges| {
+		StateMachine::<_, _, BlockNumber, _>::new(
+			&backend,
+			None,
+			overlay,
+			&executor,
+			method,
+			&[],
+			Default::default(),
+			&runtime_code,
+			TaskExecutor::new(),
+		)
+		.execute(ExecutionStrategy::AlwaysWasm)
+		.map_err(|e| format!("{}", e))
+	};
+	let mut overlay = OverlayedChanges::default();
+	let result = call("TryRuntime_on_runtime_upgrade", &mut overlay).and_then(|encoded| {
+		<Result<(u64, u64), String>>::decode(&mut &encoded[..])
+			.map_err(|e| format!("Failed to decode the upgrade result: {}", e))?
+	});
+	let mut changes = BTreeMap::new();
+
+	for (key, value) in overlay.changes() {
+		let before = backend
+			.storage(key)
+			.map_err(|e| format!("Failed to read the state: {}", e))?;
+		let after = value.value().cloned();
+
+		if before != after {
+			changes.insert(key.to_owned(), (before, after));
+		}
+	}
+
+	let mut child_changes = BTreeMap::<_, BTreeMap<_, _>>::new();
+
+	for (child_overlay, child_info) in overlay.children() {
+		for (key, value) in child_overlay {
+			let before = backend
+				.child_storage(child_info, key)
+				.map_err(|e| format!("Failed to read the child state: {}", e))?;
+			let after = value.value().cloned();
+
+			if before != after {
+				child_changes
+					.entry(child_info.storage_key().to_vec())
+					.or_default()
+					.insert(key.to_owned(), (before, after));
+			}
+		}
+	}
+
+	// The metadata is read from the upgraded runtime, before its upgrade
+	let metadata = call("Metadata_metadata", &mut OverlayedChanges::default())
+		.ok()
+		.and_then(|encoded| <Vec<u8>>::decode(&mut &encoded[..]).ok())
+		.and_then(|metadata| RuntimeMetadataPrefixed::decode(&mut &metadata[..]).ok());
+
+	Ok(UpgradeDryRun {
+		result,
+		changes,
+		child_changes,
+		heap_pages,
+		metadata,
+	})
+}
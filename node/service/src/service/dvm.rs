@@ -0,0 +1,442 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! The DVM components of a full node, and the maintenance of its mapping database, from the
+//! Ethereum block and transaction hashes to the blocks.
+//!
+//! The `dc_db` of darwinia-common only opens RocksDB and cannot delete mappings, so the mapping
+//! database stays on RocksDB, whatever the database of the node, and is never pruned.
+
+// --- std ---
+use std::{
+	collections::{BTreeMap, HashMap},
+	fs, io,
+	path::{Path, PathBuf},
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+// --- crates ---
+use futures::stream::StreamExt;
+// --- substrate ---
+use sc_client_api::{BlockOf, BlockchainEvents};
+use sc_network::NetworkService;
+use sc_service::{config::DatabaseConfig, BasePath, Configuration, TaskManager};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Header as HeaderT};
+// --- darwinia ---
+use crate::service::{
+	metrics::{self, DvmMetrics},
+	IdentifyVariant,
+};
+use darwinia_primitives::{BlockNumber, Hash, OpaqueBlock as Block};
+use darwinia_rpc::{dvm::DvmDeps, SubscriptionTaskExecutor};
+use dc_db::{Backend, DatabaseSettings, DatabaseSettingsSrc};
+use dc_mapping_sync::MappingSyncWorker;
+use dc_rpc::EthTask;
+use dp_rpc::{FilterPool, PendingTransactions};
+use dvm_rpc_runtime_api::EthereumRuntimeRPCApi;
+
+/// The tuning of the DVM mapping database and of its maintenance tasks.
+#[derive(Clone, Debug)]
+pub struct DvmConfig {
+	/// The cache size of the mapping database, in MiB.
+	pub db_cache_size: usize,
+	/// The number of blocks a pending transaction is kept for, once imported.
+	pub transaction_retain_threshold: u64,
+	/// The number of blocks a filter stays in the filter pool.
+	pub filter_retain_threshold: u64,
+	/// The maximum number of filters in the filter pool.
+	pub max_stored_filters: usize,
+	/// The longest time between two runs of the mapping sync, without any imported block.
+	pub sync_interval: Duration,
+}
+impl Default for DvmConfig {
+	fn default() -> Self {
+		Self {
+			db_cache_size: 0,
+			transaction_retain_threshold: 5,
+			filter_retain_threshold: 100,
+			max_stored_filters: 500,
+			sync_interval: Duration::from_secs(6),
+		}
+	}
+}
+
+/// The directory of the DVM mapping database, under the config directory of the chain.
+pub fn database_dir(config: &Configuration) -> PathBuf {
+	config
+		.base_path
+		.as_ref()
+		.map(|base_path| base_path.config_dir(config.chain_spec.id()))
+		.unwrap_or_else(|| {
+			let project = if config.chain_spec.is_crab() {
+				"crab"
+			} else {
+				"darwinia"
+			};
+
+			BasePath::from_project("", "", project).config_dir(config.chain_spec.id())
+		})
+		.join("dvm")
+		.join("db")
+}
+
+/// Open, or create, the DVM mapping database.
+///
+/// The mapping database only comes in RocksDB, it stays there whatever the database of the node.
+pub fn open_backend(
+	config: &Configuration,
+	dvm_config: &DvmConfig,
+) -> Result<Arc<Backend<Block>>, String> {
+	if let DatabaseConfig::ParityDb { .. } = config.database {
+		log::warn!("The DVM mapping database does not support ParityDB, keeping it on RocksDB");
+	}
+
+	Ok(Arc::new(Backend::<Block>::new(&DatabaseSettings {
+		source: DatabaseSettingsSrc::RocksDb {
+			path: database_dir(config),
+			cache_size: dvm_config.db_cache_size,
+		},
+	})?))
+}
+
+/// The DVM components of a full node, shared by its RPCs and its maintenance tasks.
+#[derive(Clone)]
+pub struct DvmComponents {
+	/// The DVM mapping database.
+	pub backend: Arc<Backend<Block>>,
+	/// Ethereum pending transactions.
+	pub pending_transactions: PendingTransactions,
+	/// EthFilterApi pool.
+	pub filter_pool: Option<FilterPool>,
+	/// The DVM metrics, if Prometheus is enabled.
+	pub metrics: Option<DvmMetrics>,
+	/// The tuning of the mapping database and of the maintenance tasks.
+	pub config: DvmConfig,
+}
+impl DvmComponents {
+	/// Open the DVM mapping database, along with empty transaction and filter pools.
+	pub fn new(config: &Configuration, dvm_config: DvmConfig) -> Result<Self, String> {
+		Ok(Self {
+			backend: open_backend(config, &dvm_config)?,
+			pending_transactions: Some(Arc::new(Mutex::new(HashMap::new()))),
+			filter_pool: Some(Arc::new(Mutex::new(BTreeMap::new()))),
+			metrics: config
+				.prometheus_registry()
+				.map(DvmMetrics::register)
+				.transpose()
+				.map_err(|e| format!("Failed to register the DVM metrics: {}", e))?,
+			config: dvm_config,
+		})
+	}
+
+	/// The dependencies of the DVM RPCs.
+	pub fn rpc_deps(
+		&self,
+		is_authority: bool,
+		network: Arc<NetworkService<Block, Hash>>,
+		subscription_task_executor: SubscriptionTaskExecutor,
+	) -> DvmDeps {
+		DvmDeps {
+			is_authority,
+			network,
+			pending_transactions: self.pending_transactions.clone(),
+			filter_pool: self.filter_pool.clone(),
+			max_stored_filters: self.config.max_stored_filters,
+			backend: self.backend.clone(),
+			subscription_task_executor,
+			eth_api_latency: self
+				.metrics
+				.as_ref()
+				.map(|metrics| metrics.eth_api_latency.clone()),
+		}
+	}
+}
+
+/// Spawn the mapping sync worker and the maintenance tasks of the pending transactions and of the
+/// filter pool.
+pub fn spawn_tasks<C, BE>(
+	task_manager: &TaskManager,
+	client: Arc<C>,
+	backend: Arc<BE>,
+	components: DvmComponents,
+) where
+	C: 'static
+		+ Send
+		+ Sync
+		+ ProvideRuntimeApi<Block>
+		+ BlockOf
+		+ BlockchainEvents<Block>
+		+ HeaderBackend<Block>,
+	C::Api: EthereumRuntimeRPCApi<Block>,
+	BE: 'static + Send + Sync + sc_client_api::Backend<Block>,
+{
+	let DvmComponents {
+		backend: frontier_backend,
+		pending_transactions,
+		filter_pool,
+		metrics,
+		config,
+	} = components;
+
+	// Spawn Frontier pending transactions maintenance task (as essential, otherwise we leak).
+	if let Some(pending_transactions) = pending_transactions.clone() {
+		task_manager.spawn_essential_handle().spawn(
+			"frontier-pending-transactions",
+			EthTask::pending_transaction_task(
+				Arc::clone(&client),
+				pending_transactions,
+				config.transaction_retain_threshold,
+			),
+		);
+	}
+
+	if let Some(metrics) = metrics.clone() {
+		task_manager.spawn_handle().spawn(
+			"dvm-metrics",
+			metrics::dvm_metrics_task(
+				client.clone(),
+				frontier_backend.clone(),
+				pending_transactions,
+				filter_pool.clone(),
+				metrics,
+			),
+		);
+	}
+
+	task_manager.spawn_essential_handle().spawn(
+		"frontier-mapping-sync-worker",
+		MappingSyncWorker::new(
+			client.import_notification_stream(),
+			config.sync_interval,
+			client.clone(),
+			backend,
+			frontier_backend,
+		)
+		.for_each(|()| futures::future::ready(())),
+	);
+
+	// Spawn Frontier EthFilterApi maintenance task.
+	if let Some(filter_pool) = filter_pool {
+		task_manager.spawn_essential_handle().spawn(
+			"frontier-filter-pool",
+			filter_pool_task(client, filter_pool, config.filter_retain_threshold, metrics),
+		);
+	}
+}
+
+/// `EthTask::filter_pool_task`, counting the evicted filters.
+async fn filter_pool_task<C>(
+	client: Arc<C>,
+	filter_pool: FilterPool,
+	retain_threshold: u64,
+	metrics: Option<DvmMetrics>,
+) where
+	C: BlockchainEvents<Block>,
+{
+	let mut import_notifications = client.import_notification_stream();
+
+	while let Some(notification) = import_notifications.next().await {
+		if let Ok(filter_pool) = &mut filter_pool.lock() {
+			let imported_number = *notification.header.number() as u64;
+			let len = filter_pool.len();
+
+			filter_pool.retain(|_, filter| filter.at_block + retain_threshold > imported_number);
+
+			if let Some(metrics) = &metrics {
+				metrics.report_evicted_filters(len - filter_pool.len());
+			}
+		}
+	}
+}
+
+/// Remove the DVM mapping database in `database_dir`, return whether there was one.
+pub fn purge(database_dir: &Path) -> io::Result<bool> {
+	match fs::remove_dir_all(database_dir) {
+		Ok(()) => Ok(true),
+		Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+		Err(e) => Err(e),
+	}
+}
+
+/// Drop the mapping database and derive it again from the local chain, if there was one.
+///
+/// Needed after a revert: the mapping database has no way to remove the entries of the reverted
+/// blocks, which would otherwise keep answering for their Ethereum blocks and transactions.
+/// Return the number of synced blocks.
+pub fn rebuild_if_exists<C, BE>(
+	client: &C,
+	backend: &BE,
+	config: &Configuration,
+	dvm_config: &DvmConfig,
+	on_progress: impl Fn(u64),
+) -> Result<Option<u64>, String>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync,
+	C::Api: EthereumRuntimeRPCApi<Block>,
+	BE: sc_client_api::Backend<Block>,
+{
+	let database_dir = database_dir(config);
+
+	if !purge(&database_dir).map_err(|e| format!("Failed to remove {:?}: {}", database_dir, e))? {
+		return Ok(None);
+	}
+
+	let frontier_backend = open_backend(config, dvm_config)?;
+
+	rebuild(client, backend, &frontier_backend, on_progress).map(Some)
+}
+
+/// Derive the whole mapping from the DVM digests of the chain, return the number of synced blocks.
+///
+/// `frontier_backend` is expected to be empty, see `purge`.
+pub fn rebuild<C, BE>(
+	client: &C,
+	backend: &BE,
+	frontier_backend: &Backend<Block>,
+	on_progress: impl Fn(u64),
+) -> Result<u64, String>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync,
+	C::Api: EthereumRuntimeRPCApi<Block>,
+	BE: sc_client_api::Backend<Block>,
+{
+	let mut synced = 0;
+
+	while dc_mapping_sync::sync_one_block(client, backend, frontier_backend)? {
+		synced += 1;
+
+		if synced % 10_000 == 0 {
+			on_progress(synced);
+		}
+	}
+
+	Ok(synced)
+}
+
+/// The result of the verification of the mapping of a block.
+#[derive(Debug, PartialEq, Eq)]
+pub enum MappingCheck {
+	/// The mapping of the block matches its DVM digest.
+	Valid,
+	/// The block is not synced yet.
+	Unsynced,
+	/// The mapping of the block does not match its DVM digest.
+	Invalid(String),
+}
+
+/// Spot check the mapping of `samples` canonical blocks, evenly spread over the chain.
+pub fn verify<C>(
+	client: &C,
+	frontier_backend: &Backend<Block>,
+	samples: u32,
+) -> Result<Vec<(BlockNumber, Hash, MappingCheck)>, String>
+where
+	C: HeaderBackend<Block>,
+{
+	let best_number = client.info().best_number;
+	let step = (best_number / samples.max(1)).max(1);
+	let mut checks = vec![];
+	let mut number = best_number;
+
+	loop {
+		let hash = client
+			.hash(number)
+			.map_err(|e| format!("{:?}", e))?
+			.ok_or_else(|| format!("Block #{} not found", number))?;
+
+		checks.push((number, hash, verify_block(client, frontier_backend, hash)?));
+
+		if number == 0 {
+			break;
+		}
+
+		number = number.saturating_sub(step);
+	}
+
+	checks.reverse();
+
+	Ok(checks)
+}
+
+/// Check the mapping of a block against its DVM digest.
+pub fn verify_block<C>(
+	client: &C,
+	frontier_backend: &Backend<Block>,
+	hash: Hash,
+) -> Result<MappingCheck, String>
+where
+	C: HeaderBackend<Block>,
+{
+	let mapping = frontier_backend.mapping();
+
+	if !mapping.is_synced(&hash)? {
+		return Ok(MappingCheck::Unsynced);
+	}
+
+	let header = client
+		.header(BlockId::Hash(hash))
+		.map_err(|e| format!("{:?}", e))?
+		.ok_or_else(|| format!("Header of {:?} not found", hash))?;
+	let hashes = match dp_consensus::find_log(header.digest()) {
+		Ok(log) => log.into_hashes(),
+		// Before the DVM, or the genesis, whose mapping is derived from the runtime
+		Err(dp_consensus::FindLogError::NotFound) => return Ok(MappingCheck::Valid),
+		Err(e) => {
+			return Ok(MappingCheck::Invalid(format!(
+				"Invalid DVM digest: {:?}",
+				e
+			)))
+		}
+	};
+
+	match mapping.block_hash(&hashes.block_hash)? {
+		Some(block_hash) if block_hash == hash => (),
+		Some(block_hash) => {
+			return Ok(MappingCheck::Invalid(format!(
+				"Ethereum block {:?} is mapped to {:?}",
+				hashes.block_hash, block_hash
+			)))
+		}
+		None => {
+			return Ok(MappingCheck::Invalid(format!(
+				"Ethereum block {:?} is not mapped",
+				hashes.block_hash
+			)))
+		}
+	}
+
+	for (index, transaction_hash) in hashes.transaction_hashes.iter().enumerate() {
+		let mapped = mapping
+			.transaction_metadata(transaction_hash)?
+			.iter()
+			.any(|metadata| {
+				metadata.block_hash == hash && metadata.ethereum_index as usize == index
+			});
+
+		if !mapped {
+			return Ok(MappingCheck::Invalid(format!(
+				"Ethereum transaction {:?} is not mapped to index {}",
+				transaction_hash, index
+			)));
+		}
+	}
+
+	Ok(MappingCheck::Valid)
+}
@@ -0,0 +1,311 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! The HTTP health endpoints of the node, for the liveness and readiness probes of orchestrators.
+//!
+//! - `/health`, the process is up.
+//! - `/ready`, the node is synced, connected and finalizing, see `HealthConfig`.
+//! - `/validator`, the session keys are in the keystore and the validator is in the active set.
+//!
+//! The answers are JSON, with `200` when the check passes and `503` otherwise.
+
+// --- std ---
+use std::{
+	io,
+	net::{SocketAddr, TcpListener},
+	sync::{Arc, RwLock},
+	time::Duration,
+};
+// --- crates ---
+use async_std::{future, net::TcpStream};
+use futures::{AsyncReadExt, AsyncWriteExt, StreamExt};
+use log::{debug, info};
+use serde_json::{json, Value};
+// --- substrate ---
+use sc_service::{NetworkStatusSinks, TaskManager};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::crypto::{key_types, KeyTypeId};
+use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
+use sp_runtime::generic::BlockId;
+// --- darwinia ---
+use darwinia_primitives::{metrics::ChainMetricsApi, BlockNumber, OpaqueBlock as Block};
+
+const LOG_TARGET: &str = "health";
+
+/// The largest request read, the probes only send a request line and a few headers.
+const MAX_REQUEST_SIZE: usize = 4096;
+/// The number of requests answered at the same time.
+const MAX_CONCURRENT_REQUESTS: usize = 16;
+/// The longest wait for the request line, so that idle connections do not hold a request slot.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where the health endpoints listen, and the thresholds of `/ready`.
+#[derive(Clone, Debug)]
+pub struct HealthConfig {
+	/// The address of the HTTP listener.
+	pub listen_addr: SocketAddr,
+	/// The most blocks the best block may be behind the best block seen from the peers.
+	pub max_sync_lag: BlockNumber,
+	/// The least number of connected peers.
+	pub min_peers: usize,
+	/// The most blocks the finalized block may be behind the best block.
+	pub max_finality_lag: BlockNumber,
+}
+impl HealthConfig {
+	/// The config of a listener on `listen_addr`, with the default thresholds.
+	pub fn new(listen_addr: SocketAddr) -> Self {
+		Self {
+			listen_addr,
+			max_sync_lag: 10,
+			min_peers: 1,
+			max_finality_lag: 50,
+		}
+	}
+}
+
+/// The last network status, as reported by the network status sinks.
+#[derive(Clone, Copy, Default)]
+struct NetworkSnapshot {
+	best_seen_block: Option<BlockNumber>,
+	peers: usize,
+}
+
+struct Health<C> {
+	config: HealthConfig,
+	client: Arc<C>,
+	keystore: Option<SyncCryptoStorePtr>,
+	network: Arc<RwLock<NetworkSnapshot>>,
+}
+impl<C> Health<C>
+where
+	C: HeaderBackend<Block> + ProvideRuntimeApi<Block>,
+	C::Api: ChainMetricsApi<Block>,
+{
+	fn ready(&self) -> (bool, Value) {
+		let info = self.client.info();
+		let network = *self.network.read().expect("Never poisoned; qed");
+		let best_seen_block = network.best_seen_block.unwrap_or(info.best_number);
+		let sync_lag = best_seen_block.saturating_sub(info.best_number);
+		let finality_lag = info.best_number.saturating_sub(info.finalized_number);
+		let synced = sync_lag <= self.config.max_sync_lag;
+		let connected = network.peers >= self.config.min_peers;
+		let finalizing = finality_lag < self.config.max_finality_lag;
+
+		(
+			synced && connected && finalizing,
+			json!({
+				"synced": synced,
+				"connected": connected,
+				"finalizing": finalizing,
+				"bestBlock": info.best_number,
+				"bestSeenBlock": best_seen_block,
+				"finalizedBlock": info.finalized_number,
+				"peers": network.peers,
+			}),
+		)
+	}
+
+	fn validator(&self) -> (bool, Value) {
+		let keystore = match &self.keystore {
+			Some(keystore) => keystore,
+			None => return (false, json!({ "error": "The node is not a validator" })),
+		};
+		let has_sr25519_key = |key_type: KeyTypeId| {
+			!SyncCryptoStore::sr25519_public_keys(&**keystore, key_type).is_empty()
+		};
+		let has_ed25519_key = |key_type: KeyTypeId| {
+			!SyncCryptoStore::ed25519_public_keys(&**keystore, key_type).is_empty()
+		};
+		let babe = has_sr25519_key(key_types::BABE);
+		let grandpa = has_ed25519_key(key_types::GRANDPA);
+		let im_online = has_sr25519_key(key_types::IM_ONLINE);
+		let authority_discovery = has_sr25519_key(key_types::AUTHORITY_DISCOVERY);
+		// A key of the active set is in the keystore, the other session keys are checked above
+		let active = SyncCryptoStore::sr25519_public_keys(&**keystore, key_types::IM_ONLINE)
+			.into_iter()
+			.any(|key| {
+				self.client
+					.runtime_api()
+					.validator(&BlockId::Hash(self.client.info().best_hash), key)
+					.ok()
+					.flatten()
+					.is_some()
+			});
+
+		(
+			babe && grandpa && im_online && authority_discovery && active,
+			json!({
+				"keys": {
+					"babe": babe,
+					"grandpa": grandpa,
+					"imOnline": im_online,
+					"authorityDiscovery": authority_discovery,
+				},
+				"active": active,
+			}),
+		)
+	}
+
+	fn respond(&self, path: &str) -> (u16, Value) {
+		let (ok, body) = match path {
+			"/health" => (true, json!({ "status": "ok" })),
+			"/ready" => self.ready(),
+			"/validator" => self.validator(),
+			_ => return (404, json!({ "error": "Not found" })),
+		};
+
+		(if ok { 200 } else { 503 }, body)
+	}
+
+	async fn serve(&self, mut stream: TcpStream) -> io::Result<()> {
+		let mut request = vec![0; MAX_REQUEST_SIZE];
+		let read = future::timeout(READ_TIMEOUT, async {
+			let mut read = 0;
+
+			// The request line is all we need
+			while !request[..read].contains(&b'\n') && read < request.len() {
+				match stream.read(&mut request[read..]).await? {
+					0 => break,
+					n => read += n,
+				}
+			}
+
+			Ok::<_, io::Error>(read)
+		})
+		.await
+		.map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "No request line in time"))??;
+		let request = String::from_utf8_lossy(&request[..read]);
+		let mut request_line = request
+			.lines()
+			.next()
+			.unwrap_or_default()
+			.split_whitespace();
+		let method = request_line.next();
+		let (status, body) = match (method, request_line.next()) {
+			(Some("GET"), Some(path)) | (Some("HEAD"), Some(path)) => {
+				// The query string is ignored
+				self.respond(path.split('?').next().unwrap_or_default())
+			}
+			_ => (405, json!({ "error": "Method not allowed" })),
+		};
+		let body = body.to_string();
+		let mut response = format!(
+			"HTTP/1.1 {} {}\r\n\
+			Content-Type: application/json\r\n\
+			Content-Length: {}\r\n\
+			Connection: close\r\n\
+			\r\n",
+			status,
+			reason(status),
+			body.len(),
+		);
+
+		// The length of the body of a GET, without the body
+		if method != Some("HEAD") {
+			response.push_str(&body);
+		}
+
+		stream.write_all(response.as_bytes()).await?;
+		stream.flush().await
+	}
+}
+
+fn reason(status: u16) -> &'static str {
+	match status {
+		200 => "OK",
+		404 => "Not Found",
+		405 => "Method Not Allowed",
+		_ => "Service Unavailable",
+	}
+}
+
+/// Start the health endpoints, failing if their address can not be bound.
+///
+/// `keystore` is only given to validators, `/validator` fails on the other nodes.
+pub fn spawn<C>(
+	task_manager: &TaskManager,
+	config: HealthConfig,
+	client: Arc<C>,
+	keystore: Option<SyncCryptoStorePtr>,
+	network_status_sinks: &NetworkStatusSinks<Block>,
+) -> Result<(), String>
+where
+	C: 'static + Send + Sync + HeaderBackend<Block> + ProvideRuntimeApi<Block>,
+	C::Api: ChainMetricsApi<Block>,
+{
+	let listener = TcpListener::bind(config.listen_addr).map_err(|e| {
+		format!(
+			"Failed to bind the health endpoints to {}: {}",
+			config.listen_addr, e
+		)
+	})?;
+	let network = Arc::new(RwLock::new(NetworkSnapshot::default()));
+
+	info!(
+		target: LOG_TARGET,
+		"Health endpoints listening on http://{}", config.listen_addr
+	);
+
+	task_manager.spawn_handle().spawn("health-network-status", {
+		let network = network.clone();
+
+		network_status_sinks
+			.status_stream(Duration::from_secs(1))
+			.for_each(move |status| {
+				*network.write().expect("Never poisoned; qed") = NetworkSnapshot {
+					best_seen_block: status.best_seen_block,
+					peers: status.num_connected_peers,
+				};
+
+				futures::future::ready(())
+			})
+	});
+
+	let health = Arc::new(Health {
+		config,
+		client,
+		keystore,
+		network,
+	});
+	let listener = async_std::net::TcpListener::from(listener);
+
+	task_manager
+		.spawn_handle()
+		.spawn("health-endpoints", async move {
+			listener
+				.incoming()
+				.for_each_concurrent(MAX_CONCURRENT_REQUESTS, |stream| {
+					let health = health.clone();
+
+					async move {
+						let result = match stream {
+							Ok(stream) => health.serve(stream).await,
+							Err(e) => Err(e),
+						};
+
+						if let Err(e) = result {
+							debug!(target: LOG_TARGET, "Failed to answer a request: {}", e);
+						}
+					}
+				})
+				.await;
+		});
+
+	Ok(())
+}
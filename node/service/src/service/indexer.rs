@@ -0,0 +1,435 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! The embedded event indexer, see `darwinia_indexer`.
+//!
+//! The events are decoded with the native runtime types, the blocks of a runtime with another
+//! event layout are skipped with a warning. Only the blocks whose state is still there are
+//! indexed, so an archive node is needed to index the whole history. A block failing on the
+//! database or on the runtime API is retried on the next finalized block.
+
+// --- std ---
+use std::{fs, path::PathBuf, sync::Arc};
+// --- crates ---
+use codec::{Codec, Decode, EncodeLike};
+use futures::StreamExt;
+use log::{info, warn};
+// --- substrate ---
+use frame_system::{EventRecord, Phase};
+use sc_client_api::{Backend, BlockchainEvents, StorageProvider};
+use sc_service::{BasePath, Configuration, TaskManager};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{crypto::Ss58Codec, hashing::twox_128, storage::StorageKey};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Header as HeaderT, Member},
+};
+// --- darwinia ---
+use darwinia_indexer::{
+	BridgeAction, DvmTransaction, Event, EventLocation, IndexedEvent, Indexer, Token,
+};
+use darwinia_primitives::{BlockNumber, Hash, OpaqueBlock as Block};
+use dvm_rpc_runtime_api::EthereumRuntimeRPCApi;
+
+const LOG_TARGET: &str = "indexer";
+
+/// A runtime event, keeping the ones explorers look for.
+///
+/// The bounds are the ones of `frame_system::EventRecord`.
+pub trait IndexEvent: Member + Codec + EncodeLike {
+	/// The indexed form of this event, `None` if it is not indexed.
+	fn index(self) -> Option<Event>;
+}
+
+macro_rules! impl_index_event {
+	($runtime:ident { $($bridge_arms:tt)* }) => {
+		impl IndexEvent for $runtime::Event {
+			fn index(self) -> Option<Event> {
+				match self {
+					$runtime::Event::darwinia_balances_Instance0(
+						darwinia_balances::Event::Transfer(from, to, amount),
+					) => Some(Event::Transfer {
+						token: Token::Ring,
+						from: from.to_ss58check(),
+						to: to.to_ss58check(),
+						amount: amount.to_string(),
+					}),
+					$runtime::Event::darwinia_balances_Instance1(
+						darwinia_balances::Event::Transfer(from, to, amount),
+					) => Some(Event::Transfer {
+						token: Token::Kton,
+						from: from.to_ss58check(),
+						to: to.to_ss58check(),
+						amount: amount.to_string(),
+					}),
+					$runtime::Event::darwinia_staking(
+						darwinia_staking::RawEvent::Reward(stash, amount),
+					) => Some(Event::StakingReward {
+						stash: stash.to_ss58check(),
+						amount: amount.to_string(),
+					}),
+					$runtime::Event::darwinia_staking(
+						darwinia_staking::RawEvent::Slash(account, ring, kton),
+					) => Some(Event::StakingSlash {
+						account: account.to_ss58check(),
+						ring: ring.to_string(),
+						kton: kton.to_string(),
+					}),
+					$runtime::Event::darwinia_treasury(
+						darwinia_treasury::RawEvent::Awarded(
+							proposal_index,
+							ring,
+							kton,
+							beneficiary,
+						),
+					) => Some(Event::TreasurySpend {
+						proposal_index,
+						beneficiary: beneficiary.to_ss58check(),
+						ring: ring.to_string(),
+						kton: kton.to_string(),
+					}),
+					$($bridge_arms)*
+					_ => None,
+				}
+			}
+		}
+	};
+}
+
+// Crab has no backing of its own to index.
+impl_index_event!(crab_runtime {});
+impl_index_event!(darwinia_runtime {
+	darwinia_runtime::Event::darwinia_ethereum_backing(
+		darwinia_ethereum_backing::RawEvent::LockRing(account, .., amount),
+	) => Some(bridge(BridgeAction::Lock, Token::Ring, account, amount)),
+	darwinia_runtime::Event::darwinia_ethereum_backing(
+		darwinia_ethereum_backing::RawEvent::LockKton(account, .., amount),
+	) => Some(bridge(BridgeAction::Lock, Token::Kton, account, amount)),
+	darwinia_runtime::Event::darwinia_ethereum_backing(
+		darwinia_ethereum_backing::RawEvent::RedeemRing(account, amount, ..),
+	) => Some(bridge(BridgeAction::Redeem, Token::Ring, account, amount)),
+	darwinia_runtime::Event::darwinia_ethereum_backing(
+		darwinia_ethereum_backing::RawEvent::RedeemKton(account, amount, ..),
+	) => Some(bridge(BridgeAction::Redeem, Token::Kton, account, amount)),
+	// The *RING* of a redeemed deposit comes back locked in staking
+	darwinia_runtime::Event::darwinia_ethereum_backing(
+		darwinia_ethereum_backing::RawEvent::RedeemDeposit(account, _, amount, ..),
+	) => Some(bridge(BridgeAction::Redeem, Token::Ring, account, amount)),
+});
+
+fn bridge(
+	action: BridgeAction,
+	token: Token,
+	account: impl Ss58Codec,
+	amount: impl ToString,
+) -> Event {
+	Event::Bridge {
+		action,
+		token,
+		account: account.to_ss58check(),
+		amount: amount.to_string(),
+	}
+}
+
+/// The path of the indexer database, under the config directory of the chain.
+pub fn database_path(config: &Configuration) -> PathBuf {
+	config
+		.base_path
+		.as_ref()
+		.map(|base_path| base_path.config_dir(config.chain_spec.id()))
+		.unwrap_or_else(|| {
+			BasePath::from_project("", "", "darwinia").config_dir(config.chain_spec.id())
+		})
+		.join("indexer")
+		.join("indexer.sqlite")
+}
+
+/// Open, or create, the indexer database.
+pub fn open(config: &Configuration) -> Result<Arc<Indexer>, String> {
+	let path = database_path(config);
+
+	if let Some(dir) = path.parent() {
+		fs::create_dir_all(dir)
+			.map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+	}
+
+	let indexer = Indexer::open(&path).map_err(|e| {
+		format!(
+			"Failed to open the indexer database {}: {}",
+			path.display(),
+			e
+		)
+	})?;
+
+	info!(target: LOG_TARGET, "Indexing the finalized events into {}", path.display());
+
+	Ok(Arc::new(indexer))
+}
+
+/// Spawn the task indexing the events of `E` of each finalized block.
+pub fn spawn<E, C, BE>(task_manager: &TaskManager, indexer: Arc<Indexer>, client: Arc<C>)
+where
+	E: IndexEvent,
+	C: 'static
+		+ Send
+		+ Sync
+		+ BlockchainEvents<Block>
+		+ HeaderBackend<Block>
+		+ ProvideRuntimeApi<Block>
+		+ StorageProvider<Block, BE>,
+	C::Api: EthereumRuntimeRPCApi<Block>,
+	BE: 'static + Backend<Block>,
+{
+	task_manager
+		.spawn_handle()
+		.spawn_blocking("indexer", indexer_task::<E, C, BE>(indexer, client));
+}
+
+async fn indexer_task<E, C, BE>(indexer: Arc<Indexer>, client: Arc<C>)
+where
+	E: IndexEvent,
+	C: BlockchainEvents<Block>
+		+ HeaderBackend<Block>
+		+ ProvideRuntimeApi<Block>
+		+ StorageProvider<Block, BE>,
+	C::Api: EthereumRuntimeRPCApi<Block>,
+	BE: Backend<Block>,
+{
+	let mut finality_notifications = client.finality_notification_stream();
+
+	while let Some(notification) = finality_notifications.next().await {
+		let finalized = *notification.header.number();
+		let from = match indexer.last_indexed_block() {
+			Ok(last) => last.map_or(0, |last| last + 1),
+			Err(e) => {
+				warn!(target: LOG_TARGET, "Failed to read the last indexed block, {}", e);
+
+				continue;
+			}
+		};
+		// The blocks whose state is gone, or whose events do not decode, reported once for the
+		// whole range
+		let mut unavailable = None;
+		let mut undecodable = None;
+
+		for number in from..=finalized {
+			match index_block::<E, C, BE>(&indexer, &*client, number) {
+				Ok(BlockIndexing::Indexed) => (),
+				Ok(BlockIndexing::Pruned) => {
+					unavailable.get_or_insert((number, number)).1 = number;
+				}
+				Ok(BlockIndexing::Undecodable) => {
+					undecodable.get_or_insert((number, number)).1 = number;
+				}
+				// Indexing a later block would move the last indexed block past this one for good
+				Err(e) => {
+					warn!(
+						target: LOG_TARGET,
+						"Failed to index block #{}, retrying on the next finalized block, {}",
+						number,
+						e
+					);
+
+					break;
+				}
+			}
+		}
+
+		if let Some((first, last)) = unavailable {
+			warn!(
+				target: LOG_TARGET,
+				"Skipped blocks #{} to #{}, their state is pruned, \
+				run an archive node (`--pruning archive`) to index them",
+				first,
+				last
+			);
+		}
+		if let Some((first, last)) = undecodable {
+			warn!(
+				target: LOG_TARGET,
+				"Skipped blocks #{} to #{}, their events do not decode with the native runtime",
+				first,
+				last
+			);
+		}
+	}
+}
+
+// The outcome of indexing a block.
+#[derive(Debug, PartialEq, Eq)]
+enum BlockIndexing {
+	Indexed,
+	// Its state is not available
+	Pruned,
+	// Its events were emitted by a runtime with another event layout
+	Undecodable,
+}
+
+// Index a block, the errors are the ones of the database and of the runtime API, worth a retry.
+fn index_block<E, C, BE>(
+	indexer: &Indexer,
+	client: &C,
+	number: BlockNumber,
+) -> Result<BlockIndexing, String>
+where
+	E: IndexEvent,
+	C: HeaderBackend<Block> + ProvideRuntimeApi<Block> + StorageProvider<Block, BE>,
+	C::Api: EthereumRuntimeRPCApi<Block>,
+	BE: Backend<Block>,
+{
+	let hash = client
+		.hash(number)
+		.map_err(|e| format!("{:?}", e))?
+		.ok_or_else(|| format!("Block #{} not found in the database", number))?;
+	let at = BlockId::Hash(hash);
+	let block_hash = format!("{:?}", hash);
+	let events = match client.storage(&at, &events_key()) {
+		Ok(Some(data)) => match decode_events::<E>(number, &block_hash, &data.0) {
+			Some(events) => events,
+			None => return Ok(BlockIndexing::Undecodable),
+		},
+		Ok(None) => vec![],
+		Err(_) => return Ok(BlockIndexing::Pruned),
+	};
+	let api = client.runtime_api();
+	// The blocks before the DVM have no Ethereum runtime API
+	let dvm_statuses = if api
+		.has_api::<dyn EthereumRuntimeRPCApi<Block>>(&at)
+		.map_err(|e| format!("Failed to read the runtime APIs: {:?}", e))?
+	{
+		api.current_transaction_statuses(&at)
+			.map_err(|e| format!("Failed to read the DVM transactions: {:?}", e))?
+			.unwrap_or_default()
+	} else {
+		vec![]
+	};
+	let dvm_transactions = dvm_statuses
+		.into_iter()
+		.map(|status| DvmTransaction {
+			block_number: number,
+			block_hash: block_hash.clone(),
+			transaction_index: status.transaction_index,
+			transaction_hash: format!("{:?}", status.transaction_hash),
+			from: format!("{:?}", status.from),
+			to: status.to.map(|to| format!("{:?}", to)),
+			contract_address: status
+				.contract_address
+				.map(|contract_address| format!("{:?}", contract_address)),
+		})
+		.collect::<Vec<_>>();
+
+	indexer
+		.insert_block(number, &block_hash, &events, &dvm_transactions)
+		.map_err(|e| format!("Failed to write the block: {}", e))?;
+
+	Ok(BlockIndexing::Indexed)
+}
+
+// Decode the `System::Events` of a block into the indexed ones, `None` if they do not decode.
+fn decode_events<E>(number: BlockNumber, block_hash: &str, data: &[u8]) -> Option<Vec<IndexedEvent>>
+where
+	E: IndexEvent,
+{
+	let records = Vec::<EventRecord<E, Hash>>::decode(&mut &*data).ok()?;
+
+	Some(
+		records
+			.into_iter()
+			.enumerate()
+			.filter_map(|(event_index, record)| {
+				let extrinsic_index = match record.phase {
+					Phase::ApplyExtrinsic(extrinsic_index) => Some(extrinsic_index),
+					_ => None,
+				};
+
+				record.event.index().map(|event| IndexedEvent {
+					location: EventLocation {
+						block_number: number,
+						block_hash: block_hash.to_owned(),
+						extrinsic_index,
+						event_index: event_index as _,
+					},
+					event,
+				})
+			})
+			.collect(),
+	)
+}
+
+fn events_key() -> StorageKey {
+	StorageKey([twox_128(b"System"), twox_128(b"Events")].concat())
+}
+
+#[cfg(test)]
+mod tests {
+	// --- crates ---
+	use codec::Encode;
+	// --- darwinia ---
+	use super::*;
+	use darwinia_primitives::AccountId;
+
+	#[test]
+	fn events_should_decode_into_the_indexed_ones() {
+		let (from, to) = (AccountId::from([1; 32]), AccountId::from([2; 32]));
+		let records = vec![
+			EventRecord {
+				phase: Phase::Initialization,
+				event: crab_runtime::Event::frame_system(frame_system::Event::NewAccount(
+					to.clone(),
+				)),
+				topics: vec![],
+			},
+			EventRecord {
+				phase: Phase::ApplyExtrinsic(1),
+				event: crab_runtime::Event::darwinia_balances_Instance0(
+					darwinia_balances::Event::Transfer(from.clone(), to.clone(), 10),
+				),
+				topics: vec![],
+			},
+		];
+		let events = decode_events::<crab_runtime::Event>(1, "0x01", &records.encode()).unwrap();
+
+		assert_eq!(
+			events,
+			vec![IndexedEvent {
+				location: EventLocation {
+					block_number: 1,
+					block_hash: "0x01".into(),
+					extrinsic_index: Some(1),
+					event_index: 1,
+				},
+				event: Event::Transfer {
+					token: Token::Ring,
+					from: from.to_ss58check(),
+					to: to.to_ss58check(),
+					amount: "10".into(),
+				},
+			}]
+		);
+	}
+
+	#[test]
+	fn undecodable_events_should_be_skipped() {
+		// One record, in the `Finalization` phase, of a pallet index the runtime does not have
+		let data = [4, 1, 0xff];
+
+		assert_eq!(decode_events::<crab_runtime::Event>(1, "0x01", &data), None);
+		assert_eq!(decode_events::<crab_runtime::Event>(1, "0x01", &[]), None);
+	}
+}
@@ -0,0 +1,388 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! The Prometheus metrics of the node, on top of the substrate ones.
+
+// --- std ---
+use std::{sync::Arc, time::Duration};
+// --- crates ---
+use futures::StreamExt;
+use futures_timer::Delay;
+// --- substrate ---
+use sc_client_api::BlockchainEvents;
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{crypto::key_types::IM_ONLINE, sr25519};
+use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
+use sp_runtime::{generic::BlockId, traits::Header as HeaderT};
+use substrate_prometheus_endpoint::{
+	exponential_buckets, register, Counter, Gauge, GaugeVec, HistogramOpts, HistogramVec, Opts,
+	PrometheusError, Registry, F64, U64,
+};
+// --- darwinia ---
+use darwinia_primitives::{
+	metrics::{ChainMetricsApi, EthereumBridgeMetrics, StakingMetrics, ValidatorMetrics},
+	BlockNumber, Hash, OpaqueBlock as Block,
+};
+use dc_db::Backend;
+use dp_rpc::{FilterPool, PendingTransactions};
+
+const LOG_TARGET: &str = "chain-metrics";
+
+/// The mapping sync lag is only searched this far back from the best block.
+const MAX_MAPPING_SYNC_LAG: BlockNumber = 4096;
+/// How often the DVM metrics are sampled.
+const DVM_METRICS_INTERVAL: Duration = Duration::from_secs(6);
+
+/// The metrics of the DVM tasks and RPCs.
+#[derive(Clone)]
+pub struct DvmMetrics {
+	mapping_sync_lag: Gauge<U64>,
+	mapping_sync_last_mapped: Gauge<U64>,
+	pending_transactions: Gauge<U64>,
+	filter_pool: Gauge<U64>,
+	evicted_filters: Counter<U64>,
+	/// The latency of the `EthApi` calls, by method.
+	pub eth_api_latency: HistogramVec,
+}
+impl DvmMetrics {
+	/// Register the DVM metrics.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			mapping_sync_lag: register(
+				Gauge::new(
+					"dvm_mapping_sync_lag",
+					"Number of blocks between the best block and the last mapped one, \
+					capped at 4096",
+				)?,
+				registry,
+			)?,
+			mapping_sync_last_mapped: register(
+				Gauge::new(
+					"dvm_mapping_sync_last_mapped",
+					"Number of the last mapped block of the best chain",
+				)?,
+				registry,
+			)?,
+			pending_transactions: register(
+				Gauge::new(
+					"dvm_pending_transactions",
+					"Number of Ethereum transactions in the pending transactions map",
+				)?,
+				registry,
+			)?,
+			filter_pool: register(
+				Gauge::new(
+					"dvm_filter_pool",
+					"Number of eth filters in the filter pool",
+				)?,
+				registry,
+			)?,
+			evicted_filters: register(
+				Counter::new(
+					"dvm_filter_pool_evicted_total",
+					"Number of eth filters evicted from the filter pool",
+				)?,
+				registry,
+			)?,
+			eth_api_latency: register(
+				HistogramVec::new(
+					HistogramOpts {
+						common_opts: Opts::new(
+							"dvm_eth_api_call_duration_seconds",
+							"Duration of the EthApi calls",
+						),
+						buckets: exponential_buckets(0.001, 4.0, 9)?,
+					},
+					&["method"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+
+	/// Report how far the mapping sync is behind the best block.
+	///
+	/// The sync maps the new blocks from the leaves backwards, so the lag is the number of the
+	/// unmapped blocks on top of the best chain.
+	///
+	/// `last_mapped` is the last mapped block found by the previous report. A mapped block stays
+	/// mapped, so the search stops there and only walks the blocks imported since.
+	pub fn report_mapping_sync<C>(
+		&self,
+		client: &C,
+		frontier_backend: &Backend<Block>,
+		last_mapped: &mut Option<(Hash, BlockNumber)>,
+	) where
+		C: HeaderBackend<Block>,
+	{
+		let info = client.info();
+		let mut hash = info.best_hash;
+		let mut number = info.best_number;
+
+		loop {
+			let mapped = *last_mapped == Some((hash, number))
+				|| match frontier_backend.mapping().is_synced(&hash) {
+					Ok(mapped) => mapped,
+					Err(_) => return,
+				};
+
+			if mapped {
+				self.mapping_sync_last_mapped.set(number.into());
+				*last_mapped = Some((hash, number));
+
+				break;
+			}
+
+			if number == 0 || info.best_number - number >= MAX_MAPPING_SYNC_LAG {
+				break;
+			}
+
+			hash = match client.header(BlockId::Hash(hash)) {
+				Ok(Some(header)) => *header.parent_hash(),
+				_ => return,
+			};
+			number -= 1;
+		}
+
+		self.mapping_sync_lag
+			.set((info.best_number - number).into());
+	}
+
+	/// Report the sizes of the pending transactions map and of the filter pool.
+	pub fn report_pools(
+		&self,
+		pending_transactions: &PendingTransactions,
+		filter_pool: &Option<FilterPool>,
+	) {
+		if let Some(Ok(pending_transactions)) = pending_transactions.as_ref().map(|p| p.lock()) {
+			self.pending_transactions
+				.set(pending_transactions.len() as _);
+		}
+		if let Some(Ok(filter_pool)) = filter_pool.as_ref().map(|p| p.lock()) {
+			self.filter_pool.set(filter_pool.len() as _);
+		}
+	}
+
+	/// Count the eth filters evicted from the filter pool.
+	pub fn report_evicted_filters(&self, evicted: usize) {
+		self.evicted_filters.inc_by(evicted as _);
+	}
+}
+
+/// Report the DVM metrics every `DVM_METRICS_INTERVAL`, apart from the mapping sync worker.
+pub async fn dvm_metrics_task<C>(
+	client: Arc<C>,
+	frontier_backend: Arc<Backend<Block>>,
+	pending_transactions: PendingTransactions,
+	filter_pool: Option<FilterPool>,
+	metrics: DvmMetrics,
+) where
+	C: HeaderBackend<Block>,
+{
+	let mut last_mapped = None;
+
+	loop {
+		Delay::new(DVM_METRICS_INTERVAL).await;
+
+		metrics.report_mapping_sync(&*client, &frontier_backend, &mut last_mapped);
+		metrics.report_pools(&pending_transactions, &filter_pool);
+	}
+}
+
+/// The metrics of the chain state, read through `ChainMetricsApi`.
+#[derive(Clone)]
+pub struct ChainMetrics {
+	active_era: Gauge<U64>,
+	validator_count: Gauge<U64>,
+	validator_era_points: Gauge<U64>,
+	validator_online: Gauge<U64>,
+	pending_relay_header_parcels: Gauge<U64>,
+	mmr_roots_to_sign: Gauge<U64>,
+	backing_locked: GaugeVec<F64>,
+}
+impl ChainMetrics {
+	/// Register the chain metrics.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			active_era: register(
+				Gauge::new("staking_active_era", "Index of the active era")?,
+				registry,
+			)?,
+			validator_count: register(
+				Gauge::new(
+					"staking_validator_count",
+					"Number of validators of the current session",
+				)?,
+				registry,
+			)?,
+			validator_era_points: register(
+				Gauge::new(
+					"staking_validator_era_points",
+					"Reward points of our validator in the active era",
+				)?,
+				registry,
+			)?,
+			validator_online: register(
+				Gauge::new(
+					"staking_validator_online",
+					"Whether our validator was seen online in the current session",
+				)?,
+				registry,
+			)?,
+			pending_relay_header_parcels: register(
+				Gauge::new(
+					"ethereum_relay_pending_header_parcels",
+					"Number of Ethereum relay header parcels waiting for their votes",
+				)?,
+				registry,
+			)?,
+			mmr_roots_to_sign: register(
+				Gauge::new(
+					"ethereum_relay_authorities_mmr_roots_to_sign",
+					"Number of MMR roots waiting for the signatures of the relay authorities",
+				)?,
+				registry,
+			)?,
+			backing_locked: register(
+				GaugeVec::new(
+					Opts::new(
+						"ethereum_backing_locked",
+						"Balance locked in the Ethereum backing account, in the smallest unit",
+					),
+					&["token"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+
+	/// Report the chain metrics at a block.
+	///
+	/// The validator metrics are only read with an im-online key, and only reported if it is the
+	/// one of a validator of the current session.
+	pub fn report<C>(
+		&self,
+		client: &C,
+		at: &BlockId<Block>,
+		im_online_keys: &[sr25519::Public],
+	) -> Result<(), String>
+	where
+		C: ProvideRuntimeApi<Block>,
+		C::Api: ChainMetricsApi<Block>,
+	{
+		let api = client.runtime_api();
+
+		// The runtime may not have been upgraded yet
+		if !api
+			.has_api::<dyn ChainMetricsApi<Block>>(at)
+			.map_err(|e| e.to_string())?
+		{
+			return Ok(());
+		}
+
+		let StakingMetrics {
+			active_era,
+			validator_count,
+		} = api.staking(at).map_err(|e| e.to_string())?;
+
+		if let Some(active_era) = active_era {
+			self.active_era.set(active_era.into());
+		}
+		self.validator_count.set(validator_count.into());
+
+		// Our first key in the active set, the validator gauges are reset if none of them is
+		let mut validator = None;
+
+		for im_online_key in im_online_keys {
+			validator = api
+				.validator(at, im_online_key.to_owned())
+				.map_err(|e| e.to_string())?;
+
+			if validator.is_some() {
+				break;
+			}
+		}
+
+		let ValidatorMetrics {
+			era_points,
+			is_online,
+		} = validator.unwrap_or_default();
+
+		self.validator_era_points.set(era_points.into());
+		self.validator_online.set(is_online.into());
+
+		if let Some(EthereumBridgeMetrics {
+			pending_relay_header_parcels,
+			mmr_roots_to_sign,
+			locked_ring,
+			locked_kton,
+		}) = api.ethereum_bridge(at).map_err(|e| e.to_string())?
+		{
+			self.pending_relay_header_parcels
+				.set(pending_relay_header_parcels.into());
+			self.mmr_roots_to_sign.set(mmr_roots_to_sign.into());
+			self.backing_locked
+				.with_label_values(&["ring"])
+				.set(locked_ring as _);
+			self.backing_locked
+				.with_label_values(&["kton"])
+				.set(locked_kton as _);
+		}
+
+		Ok(())
+	}
+}
+
+/// Report the chain metrics on each new best block.
+///
+/// The im-online keys of our validator are looked up in the keystore on each block, since they
+/// change with the session keys, and the validator metrics are read for the first of them in the
+/// active set.
+pub async fn chain_metrics_task<C>(
+	client: Arc<C>,
+	keystore: Option<SyncCryptoStorePtr>,
+	metrics: ChainMetrics,
+) where
+	C: ProvideRuntimeApi<Block> + BlockchainEvents<Block>,
+	C::Api: ChainMetricsApi<Block>,
+{
+	let mut import_notifications = client.import_notification_stream();
+
+	while let Some(notification) = import_notifications.next().await {
+		if !notification.is_new_best {
+			continue;
+		}
+
+		let im_online_keys = keystore
+			.as_ref()
+			.map(|keystore| SyncCryptoStore::sr25519_public_keys(&**keystore, IM_ONLINE))
+			.unwrap_or_default();
+
+		let at = BlockId::Hash(notification.hash);
+
+		if let Err(e) = metrics.report(&*client, &at, &im_online_keys) {
+			log::warn!(
+				target: LOG_TARGET,
+				"Failed to read the chain metrics at {}, {}",
+				notification.hash,
+				e
+			);
+		}
+	}
+}
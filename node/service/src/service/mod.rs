@@ -34,6 +34,7 @@ macro_rules! impl_runtime_apis {
 			+ darwinia_balances_rpc_runtime_api::BalancesApi<Block, AccountId, Balance>
 			+ darwinia_header_mmr_rpc_runtime_api::HeaderMMRApi<Block, Hash>
 			+ darwinia_staking_rpc_runtime_api::StakingApi<Block, AccountId, Power>
+			+ darwinia_primitives::metrics::ChainMetricsApi<Block>
 			$(+ $extra_apis),*
 		where
 			<Self as sp_api::ApiExt<Block>>::StateBackend: sp_api::StateBackend<BlakeTwo256>,
@@ -55,6 +56,7 @@ macro_rules! impl_runtime_apis {
 				+ darwinia_balances_rpc_runtime_api::BalancesApi<Block, AccountId, Balance>
 				+ darwinia_header_mmr_rpc_runtime_api::HeaderMMRApi<Block, Hash>
 				+ darwinia_staking_rpc_runtime_api::StakingApi<Block, AccountId, Power>
+				+ darwinia_primitives::metrics::ChainMetricsApi<Block>
 				$(+ $extra_apis),*,
 			<Self as sp_api::ApiExt<Block>>::StateBackend: sp_api::StateBackend<BlakeTwo256>,
 		{
@@ -62,18 +64,33 @@ macro_rules! impl_runtime_apis {
 	};
 }
 
+pub mod checkpoint;
 pub mod crab;
 pub mod darwinia;
+pub mod dry_run;
+pub mod dvm;
+pub mod health;
+pub mod indexer;
+pub mod metrics;
+pub mod sealing;
+pub mod voting_rules;
 
 // --- std ---
 use std::sync::Arc;
+// --- crates ---
+use codec::Decode;
 // --- substrate ---
-use sc_keystore::LocalKeystore;
+use sc_executor::{NativeExecutor, WasmExecutionMethod};
 use sc_service::{config::PrometheusConfig, ChainSpec, Configuration, Error as ServiceError};
+use sp_core::{crypto::Ss58AddressFormat, storage::well_known_keys, traits::ReadRuntimeVersion};
 use sp_runtime::traits::BlakeTwo256;
+use sp_state_machine::BasicExternalities;
+use sp_version::RuntimeVersion;
 use substrate_prometheus_endpoint::Registry;
 // --- darwinia ---
+use crate::chain_spec::Extensions;
 use darwinia_primitives::{AccountId, Balance, Hash, Nonce, OpaqueBlock as Block, Power};
+use darwinia_remote_keystore::RemoteKeystore;
 
 type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
@@ -91,22 +108,108 @@ type LightClient<RuntimeApi, Executor> =
 pub trait RuntimeExtrinsic: codec::Codec + Send + Sync + 'static {}
 impl<E> RuntimeExtrinsic for E where E: codec::Codec + Send + Sync + 'static {}
 
-/// Can be called for a `Configuration` to check if it is a configuration for the `Crab` network.
+/// The native runtime a chain spec was built for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuntimeVariant {
+	/// The `Crab` runtime, executed by `CrabExecutor`.
+	Crab,
+	/// The `Darwinia` runtime, executed by `DarwiniaExecutor`.
+	Darwinia,
+}
+impl RuntimeVariant {
+	/// Map a `RuntimeVersion::spec_name` to the variant it belongs to.
+	pub fn from_spec_name(spec_name: &str) -> Option<Self> {
+		match spec_name {
+			"Crab" => Some(RuntimeVariant::Crab),
+			"Darwinia" => Some(RuntimeVariant::Darwinia),
+			_ => None,
+		}
+	}
+
+	/// The SS58 address format used by the accounts of this runtime.
+	pub fn ss58_format(&self) -> Ss58AddressFormat {
+		match self {
+			RuntimeVariant::Crab => Ss58AddressFormat::SubstrateAccount,
+			RuntimeVariant::Darwinia => Ss58AddressFormat::DarwiniaAccount,
+		}
+	}
+}
+
+/// Can be called for a `ChainSpec` to find out which runtime its genesis is built on.
 pub trait IdentifyVariant {
+	/// Returns the variant of the runtime stored under `:code` in the genesis storage.
+	fn variant(&self) -> Result<RuntimeVariant, String>;
+
 	/// Returns if this is a configuration for the `Crab` network.
-	fn is_crab(&self) -> bool;
+	fn is_crab(&self) -> bool {
+		self.variant() == Ok(RuntimeVariant::Crab)
+	}
 
 	/// Returns if this is a configuration for the `Darwinia` network.
-	fn is_darwinia(&self) -> bool;
+	fn is_darwinia(&self) -> bool {
+		self.variant() == Ok(RuntimeVariant::Darwinia)
+	}
 }
 impl IdentifyVariant for Box<dyn ChainSpec> {
-	fn is_crab(&self) -> bool {
-		self.id().starts_with("crab")
+	fn variant(&self) -> Result<RuntimeVariant, String> {
+		// Reading the genesis runtime builds the whole genesis storage, so our specs keep it
+		match Extensions::try_get(&**self) {
+			Some(extensions) => extensions
+				.runtime_variant
+				.get_or_try_init(|| genesis_runtime_variant(&**self)),
+			None => genesis_runtime_variant(&**self),
+		}
 	}
+}
 
-	fn is_darwinia(&self) -> bool {
-		self.id().starts_with("darwinia")
-	}
+fn genesis_runtime_variant(chain_spec: &dyn ChainSpec) -> Result<RuntimeVariant, String> {
+	let version = genesis_runtime_version(chain_spec)?;
+	let spec_name = version.spec_name.to_string();
+
+	RuntimeVariant::from_spec_name(&spec_name).ok_or_else(|| {
+		format!(
+			"Unknown runtime `{}` in the genesis of chain spec `{}`, expected `Crab` or `Darwinia`",
+			spec_name,
+			chain_spec.id()
+		)
+	})
+}
+
+/// Read the `RuntimeVersion` of the wasm runtime stored in the genesis storage of `chain_spec`.
+pub fn genesis_runtime_version(chain_spec: &dyn ChainSpec) -> Result<RuntimeVersion, String> {
+	let storage = chain_spec.as_storage_builder().build_storage()?;
+	let code = storage.top.get(well_known_keys::CODE).ok_or_else(|| {
+		format!(
+			"Chain spec `{}` has no `:code` in its genesis storage",
+			chain_spec.id()
+		)
+	})?;
+
+	runtime_version_of(code)
+}
+
+/// Call `Core_version` on the given wasm blob and decode the result.
+pub fn runtime_version_of(code: &[u8]) -> Result<RuntimeVersion, String> {
+	// Both runtimes share the same host functions, so any of our executors is able to run them.
+	let executor = NativeExecutor::<darwinia::DarwiniaExecutor>::new(
+		WasmExecutionMethod::Interpreted,
+		None,
+		1,
+	);
+	let mut ext = BasicExternalities::default();
+	let encoded = executor.read_runtime_version(code, &mut ext).map_err(|e| {
+		format!(
+			"Failed to read the runtime version of the genesis wasm: {}",
+			e
+		)
+	})?;
+
+	RuntimeVersion::decode(&mut &encoded[..]).map_err(|e| {
+		format!(
+			"Failed to decode the runtime version of the genesis wasm: {}",
+			e
+		)
+	})
 }
 
 // If we're using prometheus, use a registry with a prefix of `darwinia`.
@@ -118,9 +221,23 @@ fn set_prometheus_registry(config: &mut Configuration) -> Result<(), ServiceErro
 	Ok(())
 }
 
-fn remote_keystore(_url: &String) -> Result<Arc<LocalKeystore>, &'static str> {
-	// FIXME: here would the concrete keystore be built,
-	//        must return a concrete type (NOT `LocalKeystore`) that
-	//        implements `CryptoStore` and `SyncCryptoStore`
-	Err("Remote Keystore not supported.")
+// The Header MMR RPC reads the nodes the runtime writes through offchain indexing.
+fn warn_offchain_indexing(config: &Configuration) {
+	if !config.offchain_worker.indexing_enabled {
+		log::warn!(
+			"Offchain indexing is disabled, the Header MMR RPC generates every proof from the \
+			state of the best block, run with `--enable-offchain-indexing true` to read the \
+			indexed nodes instead"
+		);
+	}
+}
+
+// Connect to the signer holding the keys, see `darwinia-signer`.
+fn remote_keystore(url: &str) -> Result<Arc<RemoteKeystore>, ServiceError> {
+	RemoteKeystore::open(url).map(Arc::new).map_err(|e| {
+		ServiceError::Other(format!(
+			"Error hooking up remote keystore for {}: {}",
+			url, e
+		))
+	})
 }
@@ -0,0 +1,157 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! The block sealing of a development node, `--sealing` on the command line.
+
+// --- std ---
+use std::{fmt, str::FromStr, sync::Arc, time::Duration};
+// --- crates ---
+use futures::{
+	channel::mpsc::Receiver,
+	stream::{self, BoxStream, Stream, StreamExt},
+};
+use futures_timer::Delay;
+// --- substrate ---
+use sc_consensus_manual_seal::rpc::EngineCommand;
+use sp_transaction_pool::TransactionPool;
+// --- darwinia ---
+use darwinia_primitives::{Hash, OpaqueBlock as Block};
+
+const INTERVAL_PREFIX: &str = "interval:";
+
+/// How a development node seals its blocks, instead of BABE and GRANDPA.
+///
+/// Whatever the sealing, the `engine_createBlock` and `engine_finalizeBlock` RPCs seal and
+/// finalize blocks on demand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sealing {
+	/// Seal and finalize a block as soon as a transaction enters the pool, `instant`.
+	Instant,
+	/// Seal blocks only through the RPCs, `manual`.
+	Manual,
+	/// Seal and finalize a block every this number of milliseconds, `interval:<ms>` or `<ms>`.
+	Interval(u64),
+}
+impl fmt::Display for Sealing {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Sealing::Instant => write!(f, "instant"),
+			Sealing::Manual => write!(f, "manual"),
+			Sealing::Interval(ms) => write!(f, "{}{}", INTERVAL_PREFIX, ms),
+		}
+	}
+}
+impl FromStr for Sealing {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"instant" => Ok(Sealing::Instant),
+			"manual" => Ok(Sealing::Manual),
+			_ => {
+				let ms = if s.starts_with(INTERVAL_PREFIX) {
+					&s[INTERVAL_PREFIX.len()..]
+				} else {
+					s
+				};
+
+				match ms.parse() {
+					Ok(0) => Err("The sealing interval must be at least 1 ms".into()),
+					Ok(ms) => Ok(Sealing::Interval(ms)),
+					Err(_) => Err(format!(
+						"Unknown sealing `{}`, expected `instant`, `manual` or `interval:<ms>`",
+						s
+					)),
+				}
+			}
+		}
+	}
+}
+
+/// The commands of the manual seal, those of the RPCs merged with the ones of the sealing.
+pub fn commands_stream<P>(
+	sealing: Sealing,
+	pool: Arc<P>,
+	rpc_commands: Receiver<EngineCommand<Hash>>,
+) -> impl Stream<Item = EngineCommand<Hash>>
+where
+	P: TransactionPool<Block = Block>,
+{
+	let seal_commands: BoxStream<EngineCommand<Hash>> = match sealing {
+		Sealing::Instant => pool
+			.import_notification_stream()
+			.map(|_| seal_new_block(false))
+			.boxed(),
+		Sealing::Manual => stream::pending().boxed(),
+		Sealing::Interval(ms) => stream::unfold((), move |_| async move {
+			Delay::new(Duration::from_millis(ms)).await;
+
+			Some((seal_new_block(true), ()))
+		})
+		.boxed(),
+	};
+
+	stream::select(rpc_commands, seal_commands)
+}
+
+fn seal_new_block(create_empty: bool) -> EngineCommand<Hash> {
+	EngineCommand::SealNewBlock {
+		create_empty,
+		finalize: true,
+		parent_hash: None,
+		sender: None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn sealing_should_parse_instant_and_manual() {
+		assert_eq!("instant".parse(), Ok(Sealing::Instant));
+		assert_eq!("manual".parse(), Ok(Sealing::Manual));
+	}
+
+	#[test]
+	fn sealing_should_parse_intervals() {
+		assert_eq!("interval:3000".parse(), Ok(Sealing::Interval(3000)));
+		assert_eq!("500".parse(), Ok(Sealing::Interval(500)));
+	}
+
+	#[test]
+	fn sealing_should_reject_invalid_intervals() {
+		for s in &[
+			"interval:0",
+			"0",
+			"interval:",
+			"interval:fast",
+			"fast",
+			"Instant",
+		] {
+			assert!(s.parse::<Sealing>().is_err(), "`{}` should be rejected", s);
+		}
+	}
+
+	#[test]
+	fn sealing_should_display_what_it_parses() {
+		for sealing in &[Sealing::Instant, Sealing::Manual, Sealing::Interval(6000)] {
+			assert_eq!(sealing.to_string().parse(), Ok(*sealing));
+		}
+	}
+}
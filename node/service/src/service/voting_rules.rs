@@ -0,0 +1,409 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! The GRANDPA voting rules of the node, selected by the chain spec or `--grandpa-voting-rule`.
+
+// --- std ---
+use std::{marker::PhantomData, sync::Arc};
+// --- substrate ---
+use sc_client_api::{Backend, StorageProvider};
+use sc_finality_grandpa::{BeforeBestBlockBy, VotingRule, VotingRuleResult, VotingRulesBuilder};
+use sp_blockchain::HeaderBackend;
+use sp_core::{hashing::twox_128, storage::StorageKey};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT, NumberFor},
+};
+// --- darwinia ---
+use crate::chain_spec::GrandpaVotingRule;
+
+const LOG_TARGET: &str = "grandpa-voting-rules";
+
+/// The substrate default voting rules, restricted further by `rules`.
+///
+/// `EthereumRelayConfirmed` goes last whatever its position, as any rule after it could move the
+/// vote past the last confirmation.
+pub fn voting_rule<Block, B, R>(
+	rules: &[GrandpaVotingRule],
+	confirmations: R,
+) -> impl VotingRule<Block, B> + Clone
+where
+	Block: BlockT,
+	B: HeaderBackend<Block> + 'static,
+	R: RelayConfirmations<Block> + 'static,
+{
+	let builder = rules
+		.iter()
+		.fold(VotingRulesBuilder::default(), |builder, rule| match *rule {
+			GrandpaVotingRule::BehindBestBy(n) => {
+				builder.add(BeforeBestBlockBy::<NumberFor<Block>>(n.into()))
+			}
+			GrandpaVotingRule::EthereumRelayConfirmed => builder,
+		});
+
+	if rules.contains(&GrandpaVotingRule::EthereumRelayConfirmed) {
+		builder.add(EthereumRelayConfirmed(confirmations)).build()
+	} else {
+		builder.build()
+	}
+}
+
+/// Tells which blocks confirmed an Ethereum relay header parcel.
+pub trait RelayConfirmations<Block: BlockT>: Clone + Send + Sync {
+	/// Whether the block of `header` confirmed a header parcel.
+	fn confirms(&self, header: &Block::Header) -> Result<bool, String>;
+}
+
+/// Reads the confirmations from the `ConfirmedBlockNumbers` of the Ethereum relay, which changes
+/// in the blocks confirming a header parcel.
+pub struct ClientRelayConfirmations<C, BE> {
+	client: Arc<C>,
+	_marker: PhantomData<fn() -> BE>,
+}
+impl<C, BE> ClientRelayConfirmations<C, BE> {
+	pub fn new(client: Arc<C>) -> Self {
+		Self {
+			client,
+			_marker: PhantomData,
+		}
+	}
+}
+impl<C, BE> Clone for ClientRelayConfirmations<C, BE> {
+	fn clone(&self) -> Self {
+		Self::new(self.client.clone())
+	}
+}
+impl<Block, C, BE> RelayConfirmations<Block> for ClientRelayConfirmations<C, BE>
+where
+	Block: BlockT,
+	C: 'static + Send + Sync + StorageProvider<Block, BE>,
+	BE: 'static + Backend<Block>,
+{
+	fn confirms(&self, header: &Block::Header) -> Result<bool, String> {
+		let key = StorageKey(
+			[
+				twox_128(b"DarwiniaEthereumRelay"),
+				twox_128(b"ConfirmedBlockNumbers"),
+			]
+			.concat(),
+		);
+		let confirmed_block_numbers = |hash| {
+			self.client
+				.storage(&BlockId::Hash(hash), &key)
+				.map_err(|e| format!("Failed to read the relay confirmations at {}: {}", hash, e))
+		};
+
+		Ok(confirmed_block_numbers(header.hash())?
+			!= confirmed_block_numbers(*header.parent_hash())?)
+	}
+}
+
+/// Never vote past the last block which confirmed an Ethereum relay header parcel, so that the
+/// blocks after a confirmation are only finalized once the next one is made.
+///
+/// Votes on the round base if no unfinalized block confirmed a parcel, or if the confirmations
+/// cannot be read.
+#[derive(Clone)]
+pub struct EthereumRelayConfirmed<R>(pub R);
+impl<Block, B, R> VotingRule<Block, B> for EthereumRelayConfirmed<R>
+where
+	Block: BlockT,
+	B: HeaderBackend<Block> + 'static,
+	R: RelayConfirmations<Block> + 'static,
+{
+	fn restrict_vote(
+		&self,
+		backend: Arc<B>,
+		base: &Block::Header,
+		_best_target: &Block::Header,
+		current_target: &Block::Header,
+	) -> VotingRuleResult<Block> {
+		let target = last_confirmed(&*backend, &self.0, base, current_target);
+
+		Box::pin(async move { target })
+	}
+}
+
+fn last_confirmed<Block, B, R>(
+	backend: &B,
+	confirmations: &R,
+	base: &Block::Header,
+	current_target: &Block::Header,
+) -> Option<(Block::Hash, NumberFor<Block>)>
+where
+	Block: BlockT,
+	B: HeaderBackend<Block>,
+	R: RelayConfirmations<Block>,
+{
+	if current_target.number() <= base.number() {
+		return None;
+	}
+
+	let mut header = current_target.clone();
+
+	// Walk back from the target to the base, the blocks in between are unfinalized so their
+	// state is still around
+	while header.number() > base.number() {
+		match confirmations.confirms(&header) {
+			Ok(true) if header.hash() == current_target.hash() => return None,
+			Ok(true) => return Some((header.hash(), *header.number())),
+			Ok(false) => {}
+			Err(e) => {
+				log::warn!(target: LOG_TARGET, "{}, voting on the round base", e);
+
+				break;
+			}
+		}
+
+		header = match backend.header(BlockId::Hash(*header.parent_hash())) {
+			Ok(Some(parent)) => parent,
+			_ => {
+				log::warn!(
+					target: LOG_TARGET,
+					"Missing the parent of block {}, voting on the round base",
+					header.hash()
+				);
+
+				break;
+			}
+		};
+	}
+
+	Some((base.hash(), *base.number()))
+}
+
+#[cfg(test)]
+mod tests {
+	// --- std ---
+	use std::collections::{HashMap, HashSet};
+	// --- substrate ---
+	use sp_blockchain::{BlockStatus, Info, Result as BlockchainResult};
+	// --- darwinia ---
+	use super::*;
+	use darwinia_primitives::{BlockNumber, Hash, Header, OpaqueBlock as Block};
+
+	struct MockHeaderBackend(HashMap<Hash, Header>, Vec<Hash>);
+	impl MockHeaderBackend {
+		/// A chain of `len` blocks, genesis included.
+		fn new(len: BlockNumber) -> Self {
+			let mut headers = HashMap::new();
+			let mut hashes = vec![];
+			let mut parent_hash = Default::default();
+
+			for number in 0..len {
+				let header = Header::new(
+					number,
+					Default::default(),
+					Default::default(),
+					parent_hash,
+					Default::default(),
+				);
+
+				parent_hash = header.hash();
+				headers.insert(parent_hash, header);
+				hashes.push(parent_hash);
+			}
+
+			Self(headers, hashes)
+		}
+
+		fn header_of(&self, number: BlockNumber) -> Header {
+			self.0[&self.1[number as usize]].clone()
+		}
+	}
+	impl HeaderBackend<Block> for MockHeaderBackend {
+		fn header(&self, id: BlockId<Block>) -> BlockchainResult<Option<Header>> {
+			Ok(match id {
+				BlockId::Hash(hash) => self.0.get(&hash).cloned(),
+				BlockId::Number(number) => {
+					self.1.get(number as usize).map(|hash| self.0[hash].clone())
+				}
+			})
+		}
+
+		fn info(&self) -> Info<Block> {
+			let best_number = self.1.len() as BlockNumber - 1;
+
+			Info {
+				best_hash: self.1[best_number as usize],
+				best_number,
+				genesis_hash: self.1[0],
+				finalized_hash: self.1[0],
+				finalized_number: 0,
+				number_leaves: 1,
+			}
+		}
+
+		fn status(&self, id: BlockId<Block>) -> BlockchainResult<BlockStatus> {
+			Ok(match self.header(id)? {
+				Some(_) => BlockStatus::InChain,
+				None => BlockStatus::Unknown,
+			})
+		}
+
+		fn number(&self, hash: Hash) -> BlockchainResult<Option<BlockNumber>> {
+			Ok(self.0.get(&hash).map(|header| header.number))
+		}
+
+		fn hash(&self, number: BlockNumber) -> BlockchainResult<Option<Hash>> {
+			Ok(self.1.get(number as usize).cloned())
+		}
+	}
+
+	#[derive(Clone)]
+	struct MockRelayConfirmations(Arc<HashSet<BlockNumber>>);
+	impl MockRelayConfirmations {
+		fn new(confirmed: &[BlockNumber]) -> Self {
+			Self(Arc::new(confirmed.iter().cloned().collect()))
+		}
+	}
+	impl RelayConfirmations<Block> for MockRelayConfirmations {
+		fn confirms(&self, header: &Header) -> Result<bool, String> {
+			Ok(self.0.contains(&header.number))
+		}
+	}
+
+	fn restrict_vote<R>(
+		rule: &R,
+		backend: &Arc<MockHeaderBackend>,
+		base: BlockNumber,
+		best: BlockNumber,
+		target: BlockNumber,
+	) -> Option<BlockNumber>
+	where
+		R: VotingRule<Block, MockHeaderBackend>,
+	{
+		futures::executor::block_on(rule.restrict_vote(
+			backend.clone(),
+			&backend.header_of(base),
+			&backend.header_of(best),
+			&backend.header_of(target),
+		))
+		.map(|(hash, number)| {
+			assert_eq!(hash, backend.header_of(number).hash());
+
+			number
+		})
+	}
+
+	#[test]
+	fn ethereum_relay_confirmed_should_restrict_to_the_last_confirmation() {
+		let backend = Arc::new(MockHeaderBackend::new(11));
+		let rule = EthereumRelayConfirmed(MockRelayConfirmations::new(&[3, 5]));
+
+		assert_eq!(restrict_vote(&rule, &backend, 1, 10, 8), Some(5));
+		assert_eq!(restrict_vote(&rule, &backend, 1, 10, 4), Some(3));
+	}
+
+	#[test]
+	fn ethereum_relay_confirmed_should_keep_a_confirmed_target() {
+		let backend = Arc::new(MockHeaderBackend::new(11));
+		let rule = EthereumRelayConfirmed(MockRelayConfirmations::new(&[3, 8]));
+
+		assert_eq!(restrict_vote(&rule, &backend, 1, 10, 8), None);
+		assert_eq!(restrict_vote(&rule, &backend, 8, 10, 8), None);
+	}
+
+	#[test]
+	fn ethereum_relay_confirmed_should_vote_on_the_base_without_confirmation() {
+		let backend = Arc::new(MockHeaderBackend::new(11));
+		let rule = EthereumRelayConfirmed(MockRelayConfirmations::new(&[1, 9]));
+
+		// The confirmations at or before the base are already finalized
+		assert_eq!(restrict_vote(&rule, &backend, 1, 10, 8), Some(1));
+		assert_eq!(restrict_vote(&rule, &backend, 2, 10, 8), Some(2));
+	}
+
+	#[test]
+	fn configured_rules_should_restrict_the_defaults_further() {
+		let backend = Arc::new(MockHeaderBackend::new(21));
+		let confirmations = MockRelayConfirmations::new(&[4, 12]);
+		let restrict_with = |rules: &[GrandpaVotingRule]| {
+			restrict_vote(
+				&voting_rule(rules, confirmations.clone()),
+				&backend,
+				0,
+				20,
+				20,
+			)
+			.unwrap()
+		};
+		let default = restrict_with(&[]);
+
+		assert!(default > 12);
+		assert_eq!(restrict_with(&[GrandpaVotingRule::BehindBestBy(10)]), 10);
+		assert_eq!(
+			restrict_with(&[GrandpaVotingRule::EthereumRelayConfirmed]),
+			12
+		);
+		assert_eq!(
+			restrict_with(&[
+				GrandpaVotingRule::EthereumRelayConfirmed,
+				GrandpaVotingRule::BehindBestBy(10),
+			]),
+			4
+		);
+		assert_eq!(
+			restrict_with(&[
+				GrandpaVotingRule::BehindBestBy(10),
+				GrandpaVotingRule::EthereumRelayConfirmed,
+			]),
+			4
+		);
+	}
+
+	#[test]
+	fn voting_rules_should_parse_from_cli() {
+		assert_eq!(
+			"behind-best-by=8".parse(),
+			Ok(GrandpaVotingRule::BehindBestBy(8))
+		);
+		assert_eq!(
+			"ethereum-relay-confirmed".parse(),
+			Ok(GrandpaVotingRule::EthereumRelayConfirmed)
+		);
+		assert!("behind-best-by".parse::<GrandpaVotingRule>().is_err());
+		assert!("behind-best-by=x".parse::<GrandpaVotingRule>().is_err());
+		assert!("ethereum-relay-confirmed=1"
+			.parse::<GrandpaVotingRule>()
+			.is_err());
+
+		for rule in &[
+			GrandpaVotingRule::BehindBestBy(8),
+			GrandpaVotingRule::EthereumRelayConfirmed,
+		] {
+			assert_eq!(rule.to_string().parse(), Ok(*rule));
+		}
+	}
+
+	#[test]
+	fn voting_rules_should_read_the_cli_syntax_from_chain_spec() {
+		let rules = vec![
+			GrandpaVotingRule::BehindBestBy(8),
+			GrandpaVotingRule::EthereumRelayConfirmed,
+		];
+		let json = r#"["behind-best-by=8","ethereum-relay-confirmed"]"#;
+
+		assert_eq!(serde_json::to_string(&rules).unwrap(), json);
+		assert_eq!(
+			serde_json::from_str::<Vec<GrandpaVotingRule>>(json).unwrap(),
+			rules
+		);
+		assert!(serde_json::from_str::<GrandpaVotingRule>(r#""behindBestBy""#).is_err());
+	}
+}
@@ -0,0 +1,290 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! Ethereum and Tron addresses, and their mapping to the accounts of the DVM.
+//!
+//! - `ConcatAddressMapping` maps an H160 to the account `"dvm:" ++ [0; 7] ++ H160 ++ checksum`.
+//! - `EnsureAddressTruncated` lets an account act as the H160 made of its first 20 bytes.
+
+// --- core ---
+#[cfg(feature = "std")]
+use core::{fmt, str::FromStr};
+// --- substrate ---
+#[cfg(feature = "std")]
+use sp_core::hexdisplay::HexDisplay;
+use sp_core::H160;
+// --- darwinia ---
+use crate::AccountId;
+
+/// The prefix of the accounts mapped from an H160 by `ConcatAddressMapping`.
+pub const DVM_ACCOUNT_PREFIX: &[u8; 4] = b"dvm:";
+/// The version byte of the Tron addresses.
+pub const TRON_ADDRESS_PREFIX: u8 = 0x41;
+
+/// The account of an H160 under `ConcatAddressMapping`.
+pub fn dvm_account_id(address: H160) -> AccountId {
+	let mut raw = [0; 32];
+
+	raw[0..4].copy_from_slice(DVM_ACCOUNT_PREFIX);
+	raw[11..31].copy_from_slice(&address[..]);
+	raw[31] = dvm_checksum(&raw);
+
+	raw.into()
+}
+
+/// The H160 of an account mapped by `ConcatAddressMapping`, `None` for the other accounts.
+pub fn dvm_address_of(account: &AccountId) -> Option<H160> {
+	let raw: &[u8; 32] = account.as_ref();
+
+	if &raw[0..4] == DVM_ACCOUNT_PREFIX
+		&& raw[4..11].iter().all(|&byte| byte == 0)
+		&& raw[31] == dvm_checksum(raw)
+	{
+		Some(H160::from_slice(&raw[11..31]))
+	} else {
+		None
+	}
+}
+
+/// The H160 an account acts as under `EnsureAddressTruncated`.
+pub fn truncated_address_of(account: &AccountId) -> H160 {
+	let raw: &[u8; 32] = account.as_ref();
+
+	H160::from_slice(&raw[0..20])
+}
+
+fn dvm_checksum(raw: &[u8; 32]) -> u8 {
+	raw[1..31]
+		.iter()
+		.fold(raw[0], |checksum, &byte| checksum ^ byte)
+}
+
+/// An error parsing an Ethereum or a Tron address.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AddressError {
+	/// Not a valid hex or base58 encoding of an address.
+	InvalidFormat,
+	/// The checksum of the address does not match.
+	InvalidChecksum,
+}
+#[cfg(feature = "std")]
+impl fmt::Display for AddressError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			AddressError::InvalidFormat => write!(f, "Invalid address format"),
+			AddressError::InvalidChecksum => write!(f, "Invalid address checksum"),
+		}
+	}
+}
+#[cfg(feature = "std")]
+impl std::error::Error for AddressError {}
+
+/// An Ethereum address, displayed with its EIP-55 checksum.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EthereumAddress(pub H160);
+#[cfg(feature = "std")]
+impl EthereumAddress {
+	/// The EIP-55 mixed case encoding of the address.
+	pub fn to_checksum(&self) -> String {
+		let hex = HexDisplay::from(&self.0.as_bytes()).to_string();
+		let hash = sp_core::hashing::keccak_256(hex.as_bytes());
+
+		let checksummed = hex
+			.char_indices()
+			.map(|(i, c)| {
+				let nibble = if i % 2 == 0 {
+					hash[i / 2] >> 4
+				} else {
+					hash[i / 2] & 0x0f
+				};
+
+				if nibble >= 8 {
+					c.to_ascii_uppercase()
+				} else {
+					c
+				}
+			})
+			.collect::<String>();
+
+		format!("0x{}", checksummed)
+	}
+}
+#[cfg(feature = "std")]
+impl FromStr for EthereumAddress {
+	type Err = AddressError;
+
+	/// Parse a hex address, checking its EIP-55 checksum if it is mixed case.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let hex = s.strip_prefix("0x").unwrap_or(s);
+		let address = Self(H160::from_slice(&parse_hex(hex, 20)?));
+		let mixed_case = hex.chars().any(|c| c.is_ascii_lowercase())
+			&& hex.chars().any(|c| c.is_ascii_uppercase());
+
+		if mixed_case && address.to_checksum()[2..] != *hex {
+			return Err(AddressError::InvalidChecksum);
+		}
+
+		Ok(address)
+	}
+}
+#[cfg(feature = "std")]
+impl fmt::Display for EthereumAddress {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.to_checksum())
+	}
+}
+impl From<H160> for EthereumAddress {
+	fn from(address: H160) -> Self {
+		Self(address)
+	}
+}
+
+/// A Tron address, displayed in base58check.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TronAddress(pub H160);
+#[cfg(feature = "std")]
+impl TronAddress {
+	/// The base58check encoding of the address, starting with `T`.
+	pub fn to_base58(&self) -> String {
+		// --- crates ---
+		use base58::ToBase58;
+
+		let mut raw = Vec::with_capacity(25);
+
+		raw.push(TRON_ADDRESS_PREFIX);
+		raw.extend_from_slice(self.0.as_bytes());
+		raw.extend_from_slice(&tron_checksum(&raw));
+
+		raw.to_base58()
+	}
+
+	/// The `41` prefixed hex encoding of the address.
+	pub fn to_hex(&self) -> String {
+		format!(
+			"{:02x}{}",
+			TRON_ADDRESS_PREFIX,
+			HexDisplay::from(&self.0.as_bytes())
+		)
+	}
+}
+#[cfg(feature = "std")]
+impl FromStr for TronAddress {
+	type Err = AddressError;
+
+	/// Parse a base58check address, or a `41` prefixed hex address.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		// --- crates ---
+		use base58::FromBase58;
+
+		let hex = s.strip_prefix("0x").unwrap_or(s);
+
+		if hex.len() == 42 {
+			let raw = parse_hex(hex, 21)?;
+
+			if raw[0] != TRON_ADDRESS_PREFIX {
+				return Err(AddressError::InvalidFormat);
+			}
+
+			return Ok(Self(H160::from_slice(&raw[1..])));
+		}
+
+		let raw = s.from_base58().map_err(|_| AddressError::InvalidFormat)?;
+
+		if raw.len() != 25 || raw[0] != TRON_ADDRESS_PREFIX {
+			return Err(AddressError::InvalidFormat);
+		}
+		if tron_checksum(&raw[..21]) != raw[21..] {
+			return Err(AddressError::InvalidChecksum);
+		}
+
+		Ok(Self(H160::from_slice(&raw[1..21])))
+	}
+}
+#[cfg(feature = "std")]
+impl fmt::Display for TronAddress {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.to_base58())
+	}
+}
+impl From<H160> for TronAddress {
+	fn from(address: H160) -> Self {
+		Self(address)
+	}
+}
+
+#[cfg(feature = "std")]
+fn tron_checksum(payload: &[u8]) -> [u8; 4] {
+	let hash = sp_core::hashing::sha2_256(&sp_core::hashing::sha2_256(payload));
+	let mut checksum = [0; 4];
+
+	checksum.copy_from_slice(&hash[..4]);
+
+	checksum
+}
+
+#[cfg(feature = "std")]
+fn parse_hex(hex: &str, len: usize) -> Result<Vec<u8>, AddressError> {
+	let bytes = sp_core::bytes::from_hex(hex).map_err(|_| AddressError::InvalidFormat)?;
+
+	if bytes.len() == len {
+		Ok(bytes)
+	} else {
+		Err(AddressError::InvalidFormat)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	// --- darwinia ---
+	use super::*;
+
+	#[test]
+	fn dvm_mapping_should_round_trip() {
+		let address = H160::repeat_byte(0xab);
+		let account = dvm_account_id(address);
+
+		assert_eq!(dvm_address_of(&account), Some(address));
+		assert_eq!(dvm_address_of(&AccountId::from([1; 32])), None);
+	}
+
+	#[test]
+	fn ethereum_checksum_should_follow_eip55() {
+		let checksummed = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+		let address = checksummed
+			.to_lowercase()
+			.parse::<EthereumAddress>()
+			.unwrap();
+
+		assert_eq!(address.to_string(), checksummed);
+		assert_eq!(checksummed.parse::<EthereumAddress>(), Ok(address));
+		assert_eq!(
+			"0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD".parse::<EthereumAddress>(),
+			Err(AddressError::InvalidChecksum)
+		);
+	}
+
+	#[test]
+	fn tron_address_should_round_trip() {
+		let address = TronAddress(H160::repeat_byte(0x11));
+
+		assert!(address.to_base58().starts_with('T'));
+		assert_eq!(address.to_base58().parse::<TronAddress>(), Ok(address));
+		assert_eq!(address.to_hex().parse::<TronAddress>(), Ok(address));
+	}
+}
@@ -0,0 +1,33 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! The keys of the Header MMR nodes in the offchain storage, written by the runtime through
+//! offchain indexing and read by the node-side MMR store.
+
+// --- crates ---
+use codec::Encode;
+// --- substrate ---
+use sp_std::prelude::*;
+
+/// The prefix of the offchain keys of the Header MMR nodes.
+pub const OFFCHAIN_PREFIX: &[u8] = b"header-mmr-node";
+
+/// The offchain key of the Header MMR node at `position`.
+pub fn offchain_key(position: u64) -> Vec<u8> {
+	(OFFCHAIN_PREFIX, position).encode()
+}
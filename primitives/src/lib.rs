@@ -21,6 +21,10 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs)]
 
+pub mod address;
+pub mod header_mmr;
+pub mod metrics;
+
 // --- substrate ---
 use sp_core::H256;
 use sp_runtime::{
@@ -74,45 +78,3 @@ pub type Header = generic::Header<BlockNumber, BlakeTwo256>;
 
 /// Block type.
 pub type OpaqueBlock = generic::Block<Header, OpaqueExtrinsic>;
-
-#[ignore]
-#[test]
-fn print_module_account() {
-	// --- substrate ---
-	use sp_core::crypto::{set_default_ss58_version, Ss58AddressFormat, Ss58AddressFormat::*};
-	use sp_runtime::{traits::AccountIdConversion, ModuleId};
-
-	fn account_of(alias: [u8; 8], ss58_version: Ss58AddressFormat) {
-		set_default_ss58_version(ss58_version);
-
-		let alias_str = unsafe { core::str::from_utf8_unchecked(&alias) };
-		let id = <ModuleId as AccountIdConversion<AccountId>>::into_account(&ModuleId(alias));
-
-		eprintln!("{}:\n\t{}\n\t{:?}", alias_str, id, id);
-	}
-
-	// da/trsry:
-	// 5EYCAe5gKAhKhPeR7nUZzpcX2f9eYoAhqtEHqnG433EfnCpQ
-	// 6d6f646c64612f74727372790000000000000000000000000000000000000000 (5EYCAe5g...)
-	account_of(*b"da/trsry", SubstrateAccount);
-	// da/ethbk:
-	// 2qeMxq616BhqvTW8a1bp2g7VKPAmpda1vXuAAz5TxV5ehivG
-	// 6d6f646c64612f657468626b0000000000000000000000000000000000000000 (2qeMxq61...)
-	account_of(*b"da/ethbk", DarwiniaAccount);
-	// da/crais:
-	// 5EYCAe5gKAhHQ8Hp3UUSqEGzsUtdrevrhUadXKWuwzDYmX9T
-	// 6d6f646c64612f63726169730000000000000000000000000000000000000000 (5EYCAe5g...)
-	account_of(*b"da/crais", SubstrateAccount);
-	// da/crabk:
-	// 2qeMxq616BhqeiaffX3gbqb4PPhBo3usSkjx7ZRRTkWexMAo
-	// 6d6f646c64612f637261626b0000000000000000000000000000000000000000 (2qeMxq61...)
-	account_of(*b"da/crabk", DarwiniaAccount);
-	// da/staki:
-	// 2qeMxq616BhspChjTR7DN4GHvDMvRApmawT35ayQijghNchk
-	// 6d6f646c64612f7374616b690000000000000000000000000000000000000000 (2qeMxq61...)
-	account_of(*b"da/staki", DarwiniaAccount);
-	// da/trobk:
-	// 2qeMxq616BhswyueZhqkyWntaMt8QXshns9rBbmWBs1k9G4V
-	// 6d6f646c64612f74726f626b0000000000000000000000000000000000000000 (2qeMxq61...)
-	account_of(*b"da/trobk", DarwiniaAccount);
-}
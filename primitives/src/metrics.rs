@@ -0,0 +1,74 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! The chain metrics, read by the node through `ChainMetricsApi` and exported to Prometheus.
+
+// --- crates ---
+use codec::{Decode, Encode};
+// --- substrate ---
+use sp_core::sr25519;
+// --- darwinia ---
+use crate::Balance;
+
+/// The staking metrics.
+#[derive(Clone, Default, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct StakingMetrics {
+	/// The index of the active era, `None` before the first one.
+	pub active_era: Option<u32>,
+	/// The number of validators of the current session.
+	pub validator_count: u32,
+}
+
+/// The metrics of a validator of the current session.
+#[derive(Clone, Default, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ValidatorMetrics {
+	/// The reward points of the validator in the active era.
+	pub era_points: u32,
+	/// Whether the validator was seen online in the current session.
+	pub is_online: bool,
+}
+
+/// The metrics of the Ethereum bridge.
+#[derive(Clone, Default, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct EthereumBridgeMetrics {
+	/// The number of relay header parcels waiting for their votes.
+	pub pending_relay_header_parcels: u32,
+	/// The number of MMR roots waiting for the signatures of the relay authorities.
+	pub mmr_roots_to_sign: u32,
+	/// The RING locked in the backing account.
+	pub locked_ring: Balance,
+	/// The KTON locked in the backing account.
+	pub locked_kton: Balance,
+}
+
+sp_api::decl_runtime_apis! {
+	/// The chain metrics.
+	#[api_version(1)]
+	pub trait ChainMetricsApi {
+		/// The staking metrics.
+		fn staking() -> StakingMetrics;
+		/// The metrics of the validator of an im-online key, `None` if it is not a validator of
+		/// the current session.
+		fn validator(im_online_key: sr25519::Public) -> Option<ValidatorMetrics>;
+		/// The metrics of the Ethereum bridge, `None` if the runtime has no such bridge.
+		fn ethereum_bridge() -> Option<EthereumBridgeMetrics>;
+	}
+}
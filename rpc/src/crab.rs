@@ -21,14 +21,13 @@
 #![warn(missing_docs)]
 
 // --- std ---
-use std::{collections::BTreeMap, sync::Arc};
+use std::sync::Arc;
 // --- substrate ---
+use sc_consensus_manual_seal::rpc::EngineCommand;
 use sp_api::ProvideRuntimeApi;
 // --- darwinia ---
-use crate::*;
+use crate::{dvm::DvmDeps, *};
 use darwinia_primitives::{AccountId, Balance, Nonce, Power};
-use dp_rpc::{FilterPool, PendingTransactions};
-use dvm_ethereum::EthereumStorageSchema;
 
 /// Full client dependencies
 pub struct FullDeps<C, P, SC, B> {
@@ -46,16 +45,28 @@ pub struct FullDeps<C, P, SC, B> {
 	pub babe: BabeDeps,
 	/// GRANDPA specific dependencies.
 	pub grandpa: GrandpaDeps<B>,
-	/// The Node authority flag
-	pub is_authority: bool,
-	/// Network service
-	pub network: Arc<sc_network::NetworkService<Block, Hash>>,
-	/// Ethereum pending transactions.
-	pub pending_transactions: PendingTransactions,
-	/// EthFilterApi pool.
-	pub filter_pool: Option<FilterPool>,
-	/// Backend.
-	pub backend: Arc<dc_db::Backend<Block>>,
+	/// DVM specific dependencies.
+	pub dvm: DvmDeps,
+	/// The backend instance to use.
+	pub backend: Arc<B>,
+	/// The embedded event indexer, if enabled.
+	pub indexer: Option<Arc<darwinia_indexer::Indexer>>,
+}
+
+/// Development node dependencies, the manual seal replacing BABE and GRANDPA.
+pub struct DevDeps<C, P, B> {
+	/// The client instance to use.
+	pub client: Arc<C>,
+	/// Transaction pool instance.
+	pub pool: Arc<P>,
+	/// Whether to deny unsafe calls
+	pub deny_unsafe: sc_rpc::DenyUnsafe,
+	/// DVM specific dependencies.
+	pub dvm: DvmDeps,
+	/// The backend instance to use.
+	pub backend: Arc<B>,
+	/// The channel of the `engine_*` RPCs to the manual seal.
+	pub command_sink: futures::channel::mpsc::Sender<EngineCommand<Hash>>,
 }
 
 /// Light client extra dependencies.
@@ -71,10 +82,7 @@ pub struct LightDeps<C, F, P> {
 }
 
 /// Instantiate all RPC extensions.
-pub fn create_full<C, P, SC, B>(
-	deps: FullDeps<C, P, SC, B>,
-	subscription_task_executor: sc_rpc::SubscriptionTaskExecutor,
-) -> RpcExtension
+pub fn create_full<C, P, SC, B>(deps: FullDeps<C, P, SC, B>) -> RpcExtension
 where
 	C: 'static
 		+ Send
@@ -90,7 +98,6 @@ where
 	C::Api: sc_consensus_babe::BabeApi<Block>,
 	C::Api: sp_block_builder::BlockBuilder<Block>,
 	C::Api: darwinia_balances_rpc::BalancesRuntimeApi<Block, AccountId, Balance>,
-	C::Api: darwinia_header_mmr_rpc::HeaderMMRRuntimeApi<Block, Hash>,
 	C::Api: darwinia_staking_rpc::StakingRuntimeApi<Block, AccountId, Power>,
 	C::Api: dvm_rpc_runtime_api::EthereumRuntimeRPCApi<Block>,
 	P: 'static + Sync + Send + sp_transaction_pool::TransactionPool<Block = Block>,
@@ -105,16 +112,14 @@ where
 	use sc_sync_state_rpc::{SyncStateRpcApi, SyncStateRpcHandler};
 	use substrate_frame_rpc_system::{FullSystem, SystemApi};
 	// --- darwinia ---
+	use crate::{
+		dvm::extend_with_dvm,
+		header_mmr::{HeaderMMR, HeaderMMRApi},
+		indexer::{Indexer, IndexerApi},
+	};
 	use crab_runtime::TransactionConverter;
 	use darwinia_balances_rpc::{Balances, BalancesApi};
-	use darwinia_header_mmr_rpc::{HeaderMMR, HeaderMMRApi};
 	use darwinia_staking_rpc::{Staking, StakingApi};
-	use dc_rpc::{
-		EthApi, EthApiServer, EthFilterApi, EthFilterApiServer, EthPubSubApi, EthPubSubApiServer,
-		HexEncodedIdProvider, NetApi, NetApiServer, OverrideHandle, RuntimeApiStorageOverride,
-		SchemaV1Override, StorageOverride, Web3Api, Web3ApiServer,
-	};
-	use jsonrpc_pubsub::manager::SubscriptionManager;
 
 	let FullDeps {
 		client,
@@ -124,11 +129,9 @@ where
 		deny_unsafe,
 		babe,
 		grandpa,
-		is_authority,
-		network,
-		pending_transactions,
-		filter_pool,
+		dvm,
 		backend,
+		indexer,
 	} = deps;
 	let mut io = jsonrpc_core::IoHandler::default();
 
@@ -175,53 +178,79 @@ where
 		deny_unsafe,
 	)));
 	io.extend_with(BalancesApi::to_delegate(Balances::new(client.clone())));
-	io.extend_with(HeaderMMRApi::to_delegate(HeaderMMR::new(client.clone())));
-	io.extend_with(StakingApi::to_delegate(Staking::new(client.clone())));
-
-	// DVM
-	let mut overrides_map = BTreeMap::new();
-	overrides_map.insert(
-		EthereumStorageSchema::V1,
-		Box::new(SchemaV1Override::new(client.clone()))
-			as Box<dyn StorageOverride<_> + Send + Sync>,
-	);
-	let overrides = Arc::new(OverrideHandle {
-		schemas: overrides_map,
-		fallback: Box::new(RuntimeApiStorageOverride::new(client.clone())),
-	});
-	io.extend_with(EthApiServer::to_delegate(EthApi::new(
+	io.extend_with(HeaderMMRApi::to_delegate(HeaderMMR::new(
 		client.clone(),
-		pool.clone(),
-		TransactionConverter,
-		network.clone(),
-		overrides.clone(),
-		pending_transactions.clone(),
 		backend,
-		is_authority,
 	)));
-	if let Some(filter_pool) = filter_pool {
-		io.extend_with(EthFilterApiServer::to_delegate(EthFilterApi::new(
-			client.clone(),
-			filter_pool.clone(),
-			500 as usize, // max stored filters
-			overrides.clone(),
-		)));
+	io.extend_with(StakingApi::to_delegate(Staking::new(client.clone())));
+	if let Some(indexer) = indexer {
+		io.extend_with(IndexerApi::to_delegate(Indexer::new(indexer)));
 	}
-	io.extend_with(EthPubSubApiServer::to_delegate(EthPubSubApi::new(
+	extend_with_dvm(&mut io, client, pool, TransactionConverter, dvm);
+
+	io
+}
+
+/// Instantiate all RPC extensions for development node.
+pub fn create_dev<C, P, B>(deps: DevDeps<C, P, B>) -> RpcExtension
+where
+	C: 'static
+		+ Send
+		+ Sync
+		+ ProvideRuntimeApi<Block>
+		+ sc_client_api::BlockchainEvents<Block>
+		+ sc_client_api::StorageProvider<Block, B>
+		+ sp_blockchain::HeaderBackend<Block>
+		+ sp_blockchain::HeaderMetadata<Block, Error = sp_blockchain::Error>,
+	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
+	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
+	C::Api: sp_block_builder::BlockBuilder<Block>,
+	C::Api: darwinia_balances_rpc::BalancesRuntimeApi<Block, AccountId, Balance>,
+	C::Api: darwinia_staking_rpc::StakingRuntimeApi<Block, AccountId, Power>,
+	C::Api: dvm_rpc_runtime_api::EthereumRuntimeRPCApi<Block>,
+	P: 'static + Sync + Send + sp_transaction_pool::TransactionPool<Block = Block>,
+	B: 'static + Send + Sync + sc_client_api::Backend<Block>,
+	B::State: sc_client_api::StateBackend<sp_runtime::traits::HashFor<Block>>,
+{
+	// --- substrate ---
+	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
+	use sc_consensus_manual_seal::rpc::{ManualSeal, ManualSealApi};
+	use substrate_frame_rpc_system::{FullSystem, SystemApi};
+	// --- darwinia ---
+	use crate::{
+		dvm::extend_with_dvm,
+		header_mmr::{HeaderMMR, HeaderMMRApi},
+	};
+	use crab_runtime::TransactionConverter;
+	use darwinia_balances_rpc::{Balances, BalancesApi};
+	use darwinia_staking_rpc::{Staking, StakingApi};
+
+	let DevDeps {
+		client,
 		pool,
+		deny_unsafe,
+		dvm,
+		backend,
+		command_sink,
+	} = deps;
+	let mut io = jsonrpc_core::IoHandler::default();
+
+	io.extend_with(SystemApi::to_delegate(FullSystem::new(
 		client.clone(),
-		network.clone(),
-		SubscriptionManager::<HexEncodedIdProvider>::with_id_provider(
-			HexEncodedIdProvider::default(),
-			Arc::new(subscription_task_executor),
-		),
-		overrides,
+		pool.clone(),
+		deny_unsafe,
 	)));
-	io.extend_with(NetApiServer::to_delegate(NetApi::new(
+	io.extend_with(TransactionPaymentApi::to_delegate(TransactionPayment::new(
 		client.clone(),
-		network,
 	)));
-	io.extend_with(Web3ApiServer::to_delegate(Web3Api::new(client)));
+	io.extend_with(ManualSealApi::to_delegate(ManualSeal::new(command_sink)));
+	io.extend_with(BalancesApi::to_delegate(Balances::new(client.clone())));
+	io.extend_with(HeaderMMRApi::to_delegate(HeaderMMR::new(
+		client.clone(),
+		backend,
+	)));
+	io.extend_with(StakingApi::to_delegate(Staking::new(client.clone())));
+	extend_with_dvm(&mut io, client, pool, TransactionConverter, dvm);
 
 	io
 }
@@ -232,11 +261,14 @@ where
 	C: 'static + Send + Sync + ProvideRuntimeApi<Block> + sp_blockchain::HeaderBackend<Block>,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
-	P: 'static + sp_transaction_pool::TransactionPool,
+	P: 'static + Sync + Send + sp_transaction_pool::TransactionPool<Block = Block>,
 	F: 'static + sc_client_api::Fetcher<Block>,
 {
 	// --- substrate ---
 	use substrate_frame_rpc_system::{LightSystem, SystemApi};
+	// --- darwinia ---
+	use crate::light::{extend_with_light, extend_with_light_dvm};
+	use crab_runtime::TransactionConverter;
 
 	let LightDeps {
 		client,
@@ -247,8 +279,27 @@ where
 	let mut io = jsonrpc_core::IoHandler::default();
 
 	io.extend_with(SystemApi::<Hash, AccountId, Nonce>::to_delegate(
-		LightSystem::new(client, remote_blockchain, fetcher, pool),
+		LightSystem::new(
+			client.clone(),
+			remote_blockchain.clone(),
+			fetcher.clone(),
+			pool.clone(),
+		),
 	));
+	extend_with_light::<crab_runtime::Runtime, _, _>(
+		&mut io,
+		client.clone(),
+		remote_blockchain.clone(),
+		fetcher.clone(),
+	);
+	extend_with_light_dvm(
+		&mut io,
+		client,
+		remote_blockchain,
+		fetcher,
+		pool,
+		TransactionConverter,
+	);
 
 	io
 }
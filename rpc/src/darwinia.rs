@@ -27,7 +27,7 @@ use std::sync::Arc;
 // --- substrate ---
 use sp_api::ProvideRuntimeApi;
 // --- darwinia ---
-use crate::*;
+use crate::{dvm::DvmDeps, *};
 use darwinia_primitives::{AccountId, Balance, Nonce, Power};
 
 /// Full client dependencies
@@ -46,6 +46,12 @@ pub struct FullDeps<C, P, SC, B> {
 	pub babe: BabeDeps,
 	/// GRANDPA specific dependencies.
 	pub grandpa: GrandpaDeps<B>,
+	/// DVM specific dependencies.
+	pub dvm: DvmDeps,
+	/// The backend instance to use.
+	pub backend: Arc<B>,
+	/// The embedded event indexer, if enabled.
+	pub indexer: Option<Arc<darwinia_indexer::Indexer>>,
 }
 
 /// Light client extra dependencies.
@@ -68,6 +74,8 @@ where
 		+ Sync
 		+ ProvideRuntimeApi<Block>
 		+ sc_client_api::AuxStore
+		+ sc_client_api::BlockchainEvents<Block>
+		+ sc_client_api::StorageProvider<Block, B>
 		+ sp_blockchain::HeaderBackend<Block>
 		+ sp_blockchain::HeaderMetadata<Block, Error = sp_blockchain::Error>,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
@@ -75,9 +83,9 @@ where
 	C::Api: sc_consensus_babe::BabeApi<Block>,
 	C::Api: sp_block_builder::BlockBuilder<Block>,
 	C::Api: darwinia_balances_rpc::BalancesRuntimeApi<Block, AccountId, Balance>,
-	C::Api: darwinia_header_mmr_rpc::HeaderMMRRuntimeApi<Block, Hash>,
 	C::Api: darwinia_staking_rpc::StakingRuntimeApi<Block, AccountId, Power>,
-	P: 'static + sp_transaction_pool::TransactionPool,
+	C::Api: dvm_rpc_runtime_api::EthereumRuntimeRPCApi<Block>,
+	P: 'static + Sync + Send + sp_transaction_pool::TransactionPool<Block = Block>,
 	SC: 'static + sp_consensus::SelectChain<Block>,
 	B: 'static + Send + Sync + sc_client_api::Backend<Block>,
 	B::State: sc_client_api::StateBackend<sp_runtime::traits::HashFor<Block>>,
@@ -89,8 +97,13 @@ where
 	use sc_sync_state_rpc::{SyncStateRpcApi, SyncStateRpcHandler};
 	use substrate_frame_rpc_system::{FullSystem, SystemApi};
 	// --- darwinia ---
+	use crate::{
+		dvm::extend_with_dvm,
+		header_mmr::{HeaderMMR, HeaderMMRApi},
+		indexer::{Indexer, IndexerApi},
+	};
 	use darwinia_balances_rpc::{Balances, BalancesApi};
-	use darwinia_header_mmr_rpc::{HeaderMMR, HeaderMMRApi};
+	use darwinia_runtime::TransactionConverter;
 	use darwinia_staking_rpc::{Staking, StakingApi};
 
 	let FullDeps {
@@ -101,12 +114,15 @@ where
 		deny_unsafe,
 		babe,
 		grandpa,
+		dvm,
+		backend,
+		indexer,
 	} = deps;
 	let mut io = jsonrpc_core::IoHandler::default();
 
 	io.extend_with(SystemApi::to_delegate(FullSystem::new(
 		client.clone(),
-		pool,
+		pool.clone(),
 		deny_unsafe,
 	)));
 	io.extend_with(TransactionPaymentApi::to_delegate(TransactionPayment::new(
@@ -147,8 +163,15 @@ where
 		deny_unsafe,
 	)));
 	io.extend_with(BalancesApi::to_delegate(Balances::new(client.clone())));
-	io.extend_with(HeaderMMRApi::to_delegate(HeaderMMR::new(client.clone())));
-	io.extend_with(StakingApi::to_delegate(Staking::new(client)));
+	io.extend_with(HeaderMMRApi::to_delegate(HeaderMMR::new(
+		client.clone(),
+		backend,
+	)));
+	io.extend_with(StakingApi::to_delegate(Staking::new(client.clone())));
+	if let Some(indexer) = indexer {
+		io.extend_with(IndexerApi::to_delegate(Indexer::new(indexer)));
+	}
+	extend_with_dvm(&mut io, client, pool, TransactionConverter, dvm);
 
 	io
 }
@@ -159,11 +182,14 @@ where
 	C: 'static + Send + Sync + ProvideRuntimeApi<Block> + sp_blockchain::HeaderBackend<Block>,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
-	P: 'static + sp_transaction_pool::TransactionPool,
+	P: 'static + Sync + Send + sp_transaction_pool::TransactionPool<Block = Block>,
 	F: 'static + sc_client_api::Fetcher<Block>,
 {
 	// --- substrate ---
 	use substrate_frame_rpc_system::{LightSystem, SystemApi};
+	// --- darwinia ---
+	use crate::light::{extend_with_light, extend_with_light_dvm};
+	use darwinia_runtime::TransactionConverter;
 
 	let LightDeps {
 		client,
@@ -174,8 +200,27 @@ where
 	let mut io = jsonrpc_core::IoHandler::default();
 
 	io.extend_with(SystemApi::<Hash, AccountId, Nonce>::to_delegate(
-		LightSystem::new(client, remote_blockchain, fetcher, pool),
+		LightSystem::new(
+			client.clone(),
+			remote_blockchain.clone(),
+			fetcher.clone(),
+			pool.clone(),
+		),
 	));
+	extend_with_light::<darwinia_runtime::Runtime, _, _>(
+		&mut io,
+		client.clone(),
+		remote_blockchain.clone(),
+		fetcher.clone(),
+	);
+	extend_with_light_dvm(
+		&mut io,
+		client,
+		remote_blockchain,
+		fetcher,
+		pool,
+		TransactionConverter,
+	);
 
 	io
 }
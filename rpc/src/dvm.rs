@@ -0,0 +1,175 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! The Ethereum compatible RPCs of the DVM, shared by the runtimes which include it.
+
+// --- std ---
+use std::{collections::BTreeMap, sync::Arc};
+// --- crates ---
+use jsonrpc_core::{
+	futures::Future, BoxFuture, Metadata, Params, RemoteProcedure, RpcMethod, Value,
+};
+// --- substrate ---
+use sp_api::ProvideRuntimeApi;
+use sp_runtime::OpaqueExtrinsic;
+use substrate_prometheus_endpoint::{Histogram, HistogramVec};
+// --- darwinia ---
+use crate::*;
+use dp_rpc::{FilterPool, PendingTransactions};
+use dvm_ethereum::EthereumStorageSchema;
+use dvm_rpc_runtime_api::ConvertTransaction;
+
+/// Dependencies for the DVM
+pub struct DvmDeps {
+	/// The Node authority flag
+	pub is_authority: bool,
+	/// Network service
+	pub network: Arc<sc_network::NetworkService<Block, Hash>>,
+	/// Ethereum pending transactions.
+	pub pending_transactions: PendingTransactions,
+	/// EthFilterApi pool.
+	pub filter_pool: Option<FilterPool>,
+	/// The maximum number of filters in the filter pool.
+	pub max_stored_filters: usize,
+	/// The DVM mapping database.
+	pub backend: Arc<dc_db::Backend<Block>>,
+	/// Executor to drive the subscription manager in the EthPubSub RPC handler.
+	pub subscription_task_executor: SubscriptionTaskExecutor,
+	/// The latency of the EthApi calls, by method, if Prometheus is enabled.
+	pub eth_api_latency: Option<HistogramVec>,
+}
+
+/// Add the Eth, EthFilter, EthPubSub, Net and Web3 RPCs to `io`.
+///
+/// `converter` wraps the raw Ethereum transactions into the extrinsics of the runtime.
+pub fn extend_with_dvm<C, P, B, CT>(
+	io: &mut RpcExtension,
+	client: Arc<C>,
+	pool: Arc<P>,
+	converter: CT,
+	deps: DvmDeps,
+) where
+	C: 'static
+		+ Send
+		+ Sync
+		+ ProvideRuntimeApi<Block>
+		+ sc_client_api::BlockchainEvents<Block>
+		+ sc_client_api::StorageProvider<Block, B>
+		+ sp_blockchain::HeaderBackend<Block>
+		+ sp_blockchain::HeaderMetadata<Block, Error = sp_blockchain::Error>,
+	C::Api: sp_block_builder::BlockBuilder<Block>,
+	C::Api: dvm_rpc_runtime_api::EthereumRuntimeRPCApi<Block>,
+	P: 'static + Sync + Send + sp_transaction_pool::TransactionPool<Block = Block>,
+	B: 'static + Send + Sync + sc_client_api::Backend<Block>,
+	B::State: sc_client_api::StateBackend<sp_runtime::traits::HashFor<Block>>,
+	CT: 'static + Send + Sync + ConvertTransaction<OpaqueExtrinsic>,
+{
+	// --- darwinia ---
+	use dc_rpc::{
+		EthApi, EthApiServer, EthFilterApi, EthFilterApiServer, EthPubSubApi, EthPubSubApiServer,
+		HexEncodedIdProvider, NetApi, NetApiServer, OverrideHandle, RuntimeApiStorageOverride,
+		SchemaV1Override, StorageOverride, Web3Api, Web3ApiServer,
+	};
+	use jsonrpc_pubsub::manager::SubscriptionManager;
+
+	let DvmDeps {
+		is_authority,
+		network,
+		pending_transactions,
+		filter_pool,
+		max_stored_filters,
+		backend,
+		subscription_task_executor,
+		eth_api_latency,
+	} = deps;
+	let mut overrides_map = BTreeMap::new();
+	overrides_map.insert(
+		EthereumStorageSchema::V1,
+		Box::new(SchemaV1Override::new(client.clone()))
+			as Box<dyn StorageOverride<_> + Send + Sync>,
+	);
+	let overrides = Arc::new(OverrideHandle {
+		schemas: overrides_map,
+		fallback: Box::new(RuntimeApiStorageOverride::new(client.clone())),
+	});
+	let eth = EthApiServer::to_delegate(EthApi::new(
+		client.clone(),
+		pool.clone(),
+		converter,
+		network.clone(),
+		overrides.clone(),
+		pending_transactions,
+		backend,
+		is_authority,
+	));
+	if let Some(eth_api_latency) = eth_api_latency {
+		io.extend_with(eth.into_iter().map(|(name, procedure)| match procedure {
+			RemoteProcedure::Method(method) => {
+				let latency = eth_api_latency.with_label_values(&[&name]);
+
+				(
+					name,
+					RemoteProcedure::Method(Arc::new(TimedMethod { method, latency })),
+				)
+			}
+			procedure => (name, procedure),
+		}));
+	} else {
+		io.extend_with(eth);
+	}
+	if let Some(filter_pool) = filter_pool {
+		io.extend_with(EthFilterApiServer::to_delegate(EthFilterApi::new(
+			client.clone(),
+			filter_pool,
+			max_stored_filters,
+			overrides.clone(),
+		)));
+	}
+	io.extend_with(EthPubSubApiServer::to_delegate(EthPubSubApi::new(
+		pool,
+		client.clone(),
+		network.clone(),
+		SubscriptionManager::<HexEncodedIdProvider>::with_id_provider(
+			HexEncodedIdProvider::default(),
+			Arc::new(subscription_task_executor),
+		),
+		overrides,
+	)));
+	io.extend_with(NetApiServer::to_delegate(NetApi::new(
+		client.clone(),
+		network,
+	)));
+	io.extend_with(Web3ApiServer::to_delegate(Web3Api::new(client)));
+}
+
+/// An RPC method observing the duration of its calls.
+struct TimedMethod<M: Metadata> {
+	method: Arc<dyn RpcMethod<M>>,
+	latency: Histogram,
+}
+impl<M: Metadata> RpcMethod<M> for TimedMethod<M> {
+	fn call(&self, params: Params, meta: M) -> BoxFuture<Value> {
+		let timer = self.latency.start_timer();
+
+		Box::new(self.method.call(params, meta).then(move |result| {
+			timer.observe_duration();
+
+			result
+		}))
+	}
+}
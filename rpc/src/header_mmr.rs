@@ -0,0 +1,290 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! The Header MMR RPCs of a full node, served from a node-side MMR store without executing any
+//! runtime code.
+//!
+//! The runtime writes the MMR nodes through offchain indexing. The nodes missing there, those of
+//! the blocks imported before the indexing, are read from the state of the best block. The
+//! proofs are checked against the MMR root in the digest of the block after the last leaf, and
+//! generated again from the state only if the offchain nodes do not match it, after a fork.
+
+// --- std ---
+use std::sync::Arc;
+// --- crates ---
+use code::{Decode, Encode};
+use jsonrpc_core::{Error, ErrorCode, Result as RpcResult};
+use jsonrpc_derive::rpc;
+use mmr::{leaf_index_to_mmr_size, leaf_index_to_pos, MMRStore, Merge, MMR};
+// --- substrate ---
+use sc_client_api::{Backend, StorageProvider};
+use sp_blockchain::HeaderBackend;
+use sp_core::{
+	hashing::{blake2_256, twox_128},
+	offchain::OffchainStorage,
+	storage::StorageKey,
+};
+use sp_runtime::{
+	generic::{BlockId, DigestItem},
+	traits::Header as HeaderT,
+};
+// --- darwinia ---
+use crate::*;
+use darwinia_header_mmr_rpc_runtime_api::{Proof, RuntimeDispatchInfo};
+use darwinia_primitives::header_mmr;
+
+/// The error code of a failed proof generation, the one of the former runtime handler.
+const PROOF_ERROR: i64 = 1;
+
+/// The prefix of the MMR root of the parent block in the digest, `PARENT_MMR_ROOT_LOG_ID`.
+const PARENT_MMR_ROOT_LOG_ID: [u8; 4] = *b"MMRR";
+
+/// The Header MMR RPCs.
+#[rpc]
+pub trait HeaderMMRApi<Response> {
+	/// The proof of the leaf of a block, in the MMR of the headers up to the last leaf.
+	#[rpc(name = "headerMMR_genProof")]
+	fn gen_proof(
+		&self,
+		block_number_of_member_leaf: u64,
+		block_number_of_last_leaf: u64,
+	) -> RpcResult<Response>;
+
+	/// The proof of the leaves of several blocks at once, in the MMR of the headers up to the
+	/// last leaf.
+	#[rpc(name = "headerMMR_genBatchProof")]
+	fn gen_batch_proof(
+		&self,
+		block_numbers_of_member_leaves: Vec<u64>,
+		block_number_of_last_leaf: u64,
+	) -> RpcResult<Response>;
+}
+
+/// The merge of the Header MMR, the hash of the two encoded children as in the runtime.
+pub struct HeaderMMRMerge;
+impl Merge for HeaderMMRMerge {
+	type Item = Hash;
+
+	fn merge(lhs: &Self::Item, rhs: &Self::Item) -> Self::Item {
+		blake2_256(&(lhs, rhs).encode()).into()
+	}
+}
+
+/// Where the MMR nodes are read from.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Source {
+	/// The offchain storage, then the state.
+	Offchain,
+	/// The state only.
+	State,
+}
+
+/// A read only `MMRStore` of the Header MMR nodes.
+struct NodeStore<'a, C, B>
+where
+	B: Backend<Block>,
+{
+	client: &'a C,
+	offchain_storage: Option<B::OffchainStorage>,
+	best_hash: Hash,
+}
+impl<'a, C, B> NodeStore<'a, C, B>
+where
+	C: StorageProvider<Block, B>,
+	B: Backend<Block>,
+{
+	/// The node at `position` in the state, `MMRNodeList` of `darwinia_header_mmr`.
+	fn state_node(&self, position: u64) -> mmr::Result<Option<Hash>> {
+		self.client
+			.storage(
+				&BlockId::Hash(self.best_hash),
+				&StorageKey(node_key(position)),
+			)
+			.map_err(|e| mmr::Error::StoreError(format!("{:?}", e)))?
+			.map(|data| decode_node(&data.0))
+			.transpose()
+	}
+}
+impl<'a, C, B> MMRStore<Hash> for NodeStore<'a, C, B>
+where
+	C: StorageProvider<Block, B>,
+	B: Backend<Block>,
+{
+	fn get_elem(&self, position: u64) -> mmr::Result<Option<Hash>> {
+		let offchain_node = self.offchain_storage.as_ref().and_then(|offchain_storage| {
+			offchain_storage.get(
+				sp_offchain::STORAGE_PREFIX,
+				&header_mmr::offchain_key(position),
+			)
+		});
+
+		match offchain_node {
+			Some(node) => decode_node(&node).map(Some),
+			None => self.state_node(position),
+		}
+	}
+
+	fn append(&mut self, _: u64, _: Vec<Hash>) -> mmr::Result<()> {
+		Err(mmr::Error::StoreError(
+			"The Header MMR store is read only".into(),
+		))
+	}
+}
+
+/// The storage key of the node at `position`, `MMRNodeList` of `darwinia_header_mmr`.
+pub(crate) fn node_key(position: u64) -> Vec<u8> {
+	[
+		&twox_128(b"DarwiniaHeaderMMR")[..],
+		&twox_128(b"MMRNodeList")[..],
+		&position.encode(),
+	]
+	.concat()
+}
+
+pub(crate) fn decode_node(data: &[u8]) -> mmr::Result<Hash> {
+	Hash::decode(&mut &*data).map_err(|e| mmr::Error::StoreError(e.to_string()))
+}
+
+/// The Header MMR RPCs over the node-side MMR store.
+pub struct HeaderMMR<C, B> {
+	client: Arc<C>,
+	backend: Arc<B>,
+}
+impl<C, B> HeaderMMR<C, B>
+where
+	C: HeaderBackend<Block> + StorageProvider<Block, B>,
+	B: Backend<Block>,
+{
+	/// Create a new `HeaderMMR`.
+	pub fn new(client: Arc<C>, backend: Arc<B>) -> Self {
+		Self { client, backend }
+	}
+
+	/// The proof of the leaves of `members`, in the MMR up to the leaf of `last`.
+	///
+	/// As the runtime handler, answer an empty proof of size 0 for the leaves which are not in the
+	/// MMR up to `last`.
+	fn proof(&self, mut members: Vec<u64>, last: u64) -> Result<RuntimeDispatchInfo<Hash>, String> {
+		let info = self.client.info();
+
+		members.sort_unstable();
+		members.dedup();
+
+		// The MMR of the best block ends with the leaf of its parent
+		if members.is_empty()
+			|| members.iter().any(|member| *member > last)
+			|| last >= info.best_number as u64
+		{
+			return Ok(RuntimeDispatchInfo {
+				mmr_size: 0,
+				proof: Proof(vec![]),
+			});
+		}
+
+		let mmr_size = leaf_index_to_mmr_size(last);
+		let positions = members
+			.iter()
+			.map(|member| leaf_index_to_pos(*member))
+			.collect::<Vec<_>>();
+		let leaves = members
+			.iter()
+			.map(|member| Ok((leaf_index_to_pos(*member), self.block_hash(*member)?)))
+			.collect::<Result<Vec<_>, String>>()?;
+		let root = self.parent_mmr_root(last + 1)?;
+
+		for source in [Source::Offchain, Source::State].iter() {
+			let store = NodeStore::<C, B> {
+				client: &*self.client,
+				offchain_storage: match source {
+					Source::Offchain => self.backend.offchain_storage(),
+					Source::State => None,
+				},
+				best_hash: info.best_hash,
+			};
+			let proof = MMR::<_, HeaderMMRMerge, _>::new(mmr_size, store)
+				.gen_proof(positions.clone())
+				.map_err(|e| format!("{:?}", e))?;
+			let valid = match root {
+				Some(root) => proof.verify(root, leaves.clone()).unwrap_or(false),
+				// Nothing to check against
+				None => true,
+			};
+
+			if valid {
+				return Ok(RuntimeDispatchInfo {
+					mmr_size,
+					proof: Proof(proof.proof_items().to_vec()),
+				});
+			}
+		}
+
+		Err(format!(
+			"The proof does not match the MMR root of block #{}",
+			last + 1
+		))
+	}
+
+	fn block_hash(&self, number: u64) -> Result<Hash, String> {
+		self.client
+			.hash(number as _)
+			.map_err(|e| format!("{:?}", e))?
+			.ok_or_else(|| format!("Unknown block number {}", number))
+	}
+
+	/// The MMR root of the parent of a block, from its digest.
+	fn parent_mmr_root(&self, number: u64) -> Result<Option<Hash>, String> {
+		let header = self
+			.client
+			.header(BlockId::Number(number as _))
+			.map_err(|e| format!("{:?}", e))?
+			.ok_or_else(|| format!("Unknown block number {}", number))?;
+
+		Ok(header.digest().logs().iter().find_map(|log| match log {
+			DigestItem::Other(data) if data.starts_with(&PARENT_MMR_ROOT_LOG_ID) => {
+				Hash::decode(&mut &data[PARENT_MMR_ROOT_LOG_ID.len()..]).ok()
+			}
+			_ => None,
+		}))
+	}
+}
+impl<C, B> HeaderMMRApi<RuntimeDispatchInfo<Hash>> for HeaderMMR<C, B>
+where
+	C: 'static + Send + Sync + HeaderBackend<Block> + StorageProvider<Block, B>,
+	B: 'static + Send + Sync + Backend<Block>,
+{
+	fn gen_proof(
+		&self,
+		block_number_of_member_leaf: u64,
+		block_number_of_last_leaf: u64,
+	) -> RpcResult<RuntimeDispatchInfo<Hash>> {
+		self.gen_batch_proof(vec![block_number_of_member_leaf], block_number_of_last_leaf)
+	}
+
+	fn gen_batch_proof(
+		&self,
+		block_numbers_of_member_leaves: Vec<u64>,
+		block_number_of_last_leaf: u64,
+	) -> RpcResult<RuntimeDispatchInfo<Hash>> {
+		self.proof(block_numbers_of_member_leaves, block_number_of_last_leaf)
+			.map_err(|e| Error {
+				code: ErrorCode::ServerError(PROOF_ERROR),
+				message: "Unable to generate the header mmr proof.".into(),
+				data: Some(e.into()),
+			})
+	}
+}
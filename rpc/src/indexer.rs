@@ -0,0 +1,179 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! The `indexer_*` RPCs, over the embedded event indexer of the node, see `--indexer`.
+//!
+//! The rows come newest first, a page at a time, see `Query`. The accounts are answered in the SS58
+//! format of the chain, whatever the one of the request.
+
+// --- std ---
+use std::sync::Arc;
+// --- crates ---
+use jsonrpc_core::{Error, ErrorCode, Result as RpcResult};
+use jsonrpc_derive::rpc;
+// --- substrate ---
+use sp_core::{crypto::Ss58Codec, H160};
+// --- darwinia ---
+use darwinia_indexer::{DvmTransaction, IndexedEvent, Indexer as IndexerBackend, Query};
+use darwinia_primitives::{AccountId, BlockNumber};
+
+/// The error code of a failed read of the indexer database.
+const INDEXER_ERROR: i64 = 1;
+
+/// The indexer RPCs.
+#[rpc]
+pub trait IndexerApi {
+	/// The number of the last indexed block, `null` if none.
+	#[rpc(name = "indexer_lastIndexedBlock")]
+	fn last_indexed_block(&self) -> RpcResult<Option<BlockNumber>>;
+
+	/// The *RING* and *KTON* transfers from or to an account.
+	#[rpc(name = "indexer_transfers")]
+	fn transfers(&self, account: AccountId, query: Option<Query>) -> RpcResult<Vec<IndexedEvent>>;
+
+	/// The staking rewards of a stash.
+	#[rpc(name = "indexer_stakingRewards")]
+	fn staking_rewards(
+		&self,
+		stash: AccountId,
+		query: Option<Query>,
+	) -> RpcResult<Vec<IndexedEvent>>;
+
+	/// The staking slashes of an account, or of every account.
+	#[rpc(name = "indexer_stakingSlashes")]
+	fn staking_slashes(
+		&self,
+		account: Option<AccountId>,
+		query: Option<Query>,
+	) -> RpcResult<Vec<IndexedEvent>>;
+
+	/// The treasury spends to a beneficiary, or to every beneficiary.
+	#[rpc(name = "indexer_treasurySpends")]
+	fn treasury_spends(
+		&self,
+		beneficiary: Option<AccountId>,
+		query: Option<Query>,
+	) -> RpcResult<Vec<IndexedEvent>>;
+
+	/// The bridge locks and redeems of an account, or of every account.
+	#[rpc(name = "indexer_bridgeEvents")]
+	fn bridge_events(
+		&self,
+		account: Option<AccountId>,
+		query: Option<Query>,
+	) -> RpcResult<Vec<IndexedEvent>>;
+
+	/// The DVM transactions sent by, sent to or creating an address.
+	#[rpc(name = "indexer_dvmTransactions")]
+	fn dvm_transactions(
+		&self,
+		address: H160,
+		query: Option<Query>,
+	) -> RpcResult<Vec<DvmTransaction>>;
+}
+
+/// The indexer RPCs over the indexer database of the node.
+pub struct Indexer {
+	backend: Arc<IndexerBackend>,
+}
+impl Indexer {
+	/// Create a new `Indexer`.
+	pub fn new(backend: Arc<IndexerBackend>) -> Self {
+		Self { backend }
+	}
+}
+impl IndexerApi for Indexer {
+	fn last_indexed_block(&self) -> RpcResult<Option<BlockNumber>> {
+		self.backend.last_indexed_block().map_err(into_rpc_error)
+	}
+
+	fn transfers(&self, account: AccountId, query: Option<Query>) -> RpcResult<Vec<IndexedEvent>> {
+		self.backend
+			.transfers(&account.to_ss58check(), query.unwrap_or_default())
+			.map_err(into_rpc_error)
+	}
+
+	fn staking_rewards(
+		&self,
+		stash: AccountId,
+		query: Option<Query>,
+	) -> RpcResult<Vec<IndexedEvent>> {
+		self.backend
+			.staking_rewards(&stash.to_ss58check(), query.unwrap_or_default())
+			.map_err(into_rpc_error)
+	}
+
+	fn staking_slashes(
+		&self,
+		account: Option<AccountId>,
+		query: Option<Query>,
+	) -> RpcResult<Vec<IndexedEvent>> {
+		self.backend
+			.staking_slashes(
+				account.map(|account| account.to_ss58check()).as_deref(),
+				query.unwrap_or_default(),
+			)
+			.map_err(into_rpc_error)
+	}
+
+	fn treasury_spends(
+		&self,
+		beneficiary: Option<AccountId>,
+		query: Option<Query>,
+	) -> RpcResult<Vec<IndexedEvent>> {
+		self.backend
+			.treasury_spends(
+				beneficiary
+					.map(|beneficiary| beneficiary.to_ss58check())
+					.as_deref(),
+				query.unwrap_or_default(),
+			)
+			.map_err(into_rpc_error)
+	}
+
+	fn bridge_events(
+		&self,
+		account: Option<AccountId>,
+		query: Option<Query>,
+	) -> RpcResult<Vec<IndexedEvent>> {
+		self.backend
+			.bridge_events(
+				account.map(|account| account.to_ss58check()).as_deref(),
+				query.unwrap_or_default(),
+			)
+			.map_err(into_rpc_error)
+	}
+
+	fn dvm_transactions(
+		&self,
+		address: H160,
+		query: Option<Query>,
+	) -> RpcResult<Vec<DvmTransaction>> {
+		self.backend
+			.dvm_transactions(&format!("{:?}", address), query.unwrap_or_default())
+			.map_err(into_rpc_error)
+	}
+}
+
+fn into_rpc_error(e: darwinia_indexer::Error) -> Error {
+	Error {
+		code: ErrorCode::ServerError(INDEXER_ERROR),
+		message: "Unable to read the indexer database.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
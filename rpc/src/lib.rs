@@ -18,6 +18,10 @@
 
 pub mod crab;
 pub mod darwinia;
+pub mod dvm;
+pub mod header_mmr;
+pub mod indexer;
+pub mod light;
 
 pub use sc_rpc::{DenyUnsafe, SubscriptionTaskExecutor};
 
@@ -0,0 +1,768 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! The Balances, Staking, HeaderMMR and a subset of the Ethereum RPCs of the light clients.
+//!
+//! The Balances, Staking and HeaderMMR RPCs read the storage of a remote full node, checked against
+//! its storage proof, and evaluate it here as the runtime APIs behind the full node handlers do.
+//!
+//! The Ethereum RPCs call the runtime APIs on a remote full node, which answers with a proof of the
+//! storage they read. The fetcher checks the proof by executing the call against it.
+
+// --- std ---
+use std::{
+	cell::RefCell,
+	collections::{BTreeSet, HashMap},
+	future::Future,
+	marker::PhantomData,
+	sync::Arc,
+};
+// --- crates ---
+use code::{Decode, Encode};
+use futures::{FutureExt, TryFutureExt};
+use jsonrpc_core::{futures::Future as _, BoxFuture, Error, ErrorCode, Result as RpcResult};
+use jsonrpc_derive::rpc;
+use mmr::{leaf_index_to_mmr_size, leaf_index_to_pos, MMRStore, MMR};
+// --- substrate ---
+use frame_support::{
+	storage::{StorageMap, StorageValue},
+	traits::Get,
+};
+use frame_system::AccountInfo;
+use sc_client_api::light::{
+	future_header, Fetcher, RemoteBlockchain, RemoteCallRequest, RemoteReadRequest,
+};
+use sp_blockchain::{Error as ClientError, HeaderBackend};
+use sp_core::{hashing::keccak_256, hexdisplay::HexDisplay, H160, H256, U256};
+use sp_runtime::{
+	generic::BlockId, traits::Header as HeaderT, OpaqueExtrinsic, Perquintill, SaturatedConversion,
+};
+use sp_transaction_pool::{TransactionPool, TransactionSource};
+// --- darwinia ---
+use crate::{
+	header_mmr::{self, HeaderMMRMerge},
+	*,
+};
+use darwinia_balances::{Instance0 as RingInstance, Instance1 as KtonInstance};
+use darwinia_balances_rpc_runtime_api::RuntimeDispatchInfo as BalancesRuntimeDispatchInfo;
+use darwinia_evm::Account as EVMAccount;
+use darwinia_header_mmr_rpc_runtime_api::{
+	Proof, RuntimeDispatchInfo as HeaderMMRRuntimeDispatchInfo,
+};
+use darwinia_primitives::{AccountId, Balance, BlockNumber as NativeBlockNumber, Header, Power};
+use darwinia_staking::StakingLedger;
+use darwinia_staking_rpc_runtime_api::RuntimeDispatchInfo as StakingRuntimeDispatchInfo;
+use darwinia_support::balance::{
+	lock::{BalanceLock, LockFor, LockReasons},
+	BalanceInfo,
+};
+use dp_rpc::{BlockNumber, Bytes};
+use dvm_ethereum::Transaction;
+use dvm_rpc_runtime_api::ConvertTransaction;
+
+/// Return the error of `$result` as a failed `BoxFuture`.
+macro_rules! try_future {
+	($result:expr) => {
+		match $result {
+			Ok(value) => value,
+			Err(e) => return Box::new(jsonrpc_core::futures::future::err(e)),
+		}
+	};
+}
+
+/// The error code of the full node handlers for a failed runtime call.
+const RUNTIME_ERROR: i64 = 1;
+
+/// The values of the storage keys read on a remote full node, `None` for the absent ones.
+type StorageValues = HashMap<Vec<u8>, Option<Vec<u8>>>;
+
+fn internal_err<T: ToString>(message: T) -> Error {
+	Error {
+		code: ErrorCode::InternalError,
+		message: message.to_string(),
+		data: None,
+	}
+}
+
+/// `balances_usableBalance` of a light client.
+#[rpc]
+pub trait LightBalancesApi<BlockHash, AccountId, Response> {
+	/// The usable balance of `who`, of RING for the instance `0` and of KTON for `1`.
+	#[rpc(name = "balances_usableBalance")]
+	fn usable_balance(
+		&self,
+		instance: u8,
+		who: AccountId,
+		at: Option<BlockHash>,
+	) -> BoxFuture<Response>;
+}
+
+/// `headerMMR_genProof` of a light client.
+#[rpc]
+pub trait LightHeaderMMRApi<BlockHash, Response> {
+	/// The proof of the leaf of a block, in the MMR of the headers up to the last leaf.
+	#[rpc(name = "headerMMR_genProof")]
+	fn gen_proof(
+		&self,
+		block_number_of_member_leaf: u64,
+		block_number_of_last_leaf: u64,
+		at: Option<BlockHash>,
+	) -> BoxFuture<Response>;
+}
+
+/// `staking_powerOf` of a light client.
+#[rpc]
+pub trait LightStakingApi<BlockHash, AccountId, Response> {
+	/// The power of `who` in staking.
+	#[rpc(name = "staking_powerOf")]
+	fn power_of(&self, who: AccountId, at: Option<BlockHash>) -> BoxFuture<Response>;
+}
+
+/// The Ethereum RPCs of a light client, enough for a wallet to show the accounts and send
+/// transactions.
+#[rpc]
+pub trait LightEthApi {
+	/// The chain id of the DVM.
+	#[rpc(name = "eth_chainId")]
+	fn chain_id(&self) -> BoxFuture<U256>;
+
+	/// The number of the best block.
+	#[rpc(name = "eth_blockNumber")]
+	fn block_number(&self) -> RpcResult<U256>;
+
+	/// The balance of an account, with the DVM decimals.
+	#[rpc(name = "eth_getBalance")]
+	fn balance(&self, address: H160, number: Option<BlockNumber>) -> BoxFuture<U256>;
+
+	/// The nonce of an account.
+	#[rpc(name = "eth_getTransactionCount")]
+	fn transaction_count(&self, address: H160, number: Option<BlockNumber>) -> BoxFuture<U256>;
+
+	/// The code of a contract.
+	#[rpc(name = "eth_getCode")]
+	fn code_at(&self, address: H160, number: Option<BlockNumber>) -> BoxFuture<Bytes>;
+
+	/// A storage slot of a contract.
+	#[rpc(name = "eth_getStorageAt")]
+	fn storage_at(
+		&self,
+		address: H160,
+		index: U256,
+		number: Option<BlockNumber>,
+	) -> BoxFuture<H256>;
+
+	/// Submit a signed transaction, its hash.
+	#[rpc(name = "eth_sendRawTransaction")]
+	fn send_raw_transaction(&self, bytes: Bytes) -> BoxFuture<H256>;
+}
+
+/// Calls the runtime APIs on a remote full node.
+pub struct RemoteRuntimeApi<C, F> {
+	client: Arc<C>,
+	remote_blockchain: Arc<dyn RemoteBlockchain<Block>>,
+	fetcher: Arc<F>,
+}
+impl<C, F> RemoteRuntimeApi<C, F>
+where
+	C: 'static + Send + Sync + HeaderBackend<Block>,
+	F: 'static + Fetcher<Block>,
+{
+	/// Create a new `RemoteRuntimeApi`.
+	pub fn new(
+		client: Arc<C>,
+		remote_blockchain: Arc<dyn RemoteBlockchain<Block>>,
+		fetcher: Arc<F>,
+	) -> Self {
+		Self {
+			client,
+			remote_blockchain,
+			fetcher,
+		}
+	}
+
+	/// Call `method` on the best block.
+	pub fn call<R>(
+		&self,
+		method: &'static str,
+		call_data: Vec<u8>,
+		what: &'static str,
+	) -> BoxFuture<R>
+	where
+		R: 'static + Send + Decode,
+	{
+		self.call_at(self.client.info().best_hash, method, call_data, what)
+	}
+
+	/// Call `method` on the block `at`, `what` names the queried data in the errors.
+	pub fn call_at<R>(
+		&self,
+		at: Hash,
+		method: &'static str,
+		call_data: Vec<u8>,
+		what: &'static str,
+	) -> BoxFuture<R>
+	where
+		R: 'static + Send + Decode,
+	{
+		let remote_blockchain = self.remote_blockchain.clone();
+		let fetcher = self.fetcher.clone();
+		let result = async move {
+			let header = future_header(&*remote_blockchain, &*fetcher, BlockId::Hash(at))
+				.await?
+				.ok_or_else(|| ClientError::UnknownBlock(at.to_string()))?;
+			let result = fetcher
+				.remote_call(RemoteCallRequest {
+					block: at,
+					header,
+					method: method.into(),
+					call_data,
+					retry_count: None,
+				})
+				.await?;
+
+			R::decode(&mut &result[..]).map_err(|e| ClientError::CallResultDecode(method, e))
+		};
+
+		Box::new(
+			result
+				.map_err(move |e| Error {
+					code: ErrorCode::ServerError(RUNTIME_ERROR),
+					message: format!("Unable to query {}.", what),
+					data: Some(format!("{:?}", e).into()),
+				})
+				.boxed()
+				.compat(),
+		)
+	}
+
+	/// The hash of the block of an Ethereum block number, the best one if `None`.
+	fn block_hash(&self, number: Option<BlockNumber>) -> RpcResult<Hash> {
+		let number = match number {
+			Some(BlockNumber::Num(number)) => number.saturated_into(),
+			Some(BlockNumber::Earliest) => 0,
+			_ => return Ok(self.client.info().best_hash),
+		};
+
+		self.client
+			.hash(number)
+			.map_err(|e| internal_err(format!("{:?}", e)))?
+			.ok_or_else(|| internal_err(format!("Unknown block number {}", number)))
+	}
+}
+impl<C, F> Clone for RemoteRuntimeApi<C, F> {
+	fn clone(&self) -> Self {
+		Self {
+			client: self.client.clone(),
+			remote_blockchain: self.remote_blockchain.clone(),
+			fetcher: self.fetcher.clone(),
+		}
+	}
+}
+/// The pallets of a runtime read by the Balances, Staking and HeaderMMR RPCs of a light client.
+pub trait LightRuntime:
+	'static
+	+ Send
+	+ Sync
+	+ frame_system::Config<AccountId = AccountId, BlockNumber = NativeBlockNumber>
+	+ darwinia_balances::Config<RingInstance, Balance = Balance>
+	+ darwinia_balances::Config<KtonInstance, Balance = Balance>
+	+ darwinia_staking::Config
+{
+}
+impl<R> LightRuntime for R where
+	R: 'static
+		+ Send
+		+ Sync
+		+ frame_system::Config<AccountId = AccountId, BlockNumber = NativeBlockNumber>
+		+ darwinia_balances::Config<RingInstance, Balance = Balance>
+		+ darwinia_balances::Config<KtonInstance, Balance = Balance>
+		+ darwinia_staking::Config
+{
+}
+
+/// Reads the storage of the runtime `R` on a remote full node, checked against its storage proof.
+pub struct RemoteState<C, F, R> {
+	client: Arc<C>,
+	remote_blockchain: Arc<dyn RemoteBlockchain<Block>>,
+	fetcher: Arc<F>,
+	_runtime: PhantomData<fn() -> R>,
+}
+impl<C, F, R> RemoteState<C, F, R>
+where
+	C: 'static + Send + Sync + HeaderBackend<Block>,
+	F: 'static + Fetcher<Block>,
+{
+	/// Create a new `RemoteState`.
+	pub fn new(
+		client: Arc<C>,
+		remote_blockchain: Arc<dyn RemoteBlockchain<Block>>,
+		fetcher: Arc<F>,
+	) -> Self {
+		Self {
+			client,
+			remote_blockchain,
+			fetcher,
+			_runtime: PhantomData,
+		}
+	}
+
+	/// Evaluate `evaluate` on the header of the block `at`, the best one if `None`.
+	///
+	/// `what` names the queried data in the errors.
+	fn evaluate<T, E, Fut>(&self, at: Option<Hash>, what: &'static str, evaluate: E) -> BoxFuture<T>
+	where
+		T: 'static + Send,
+		E: 'static + Send + FnOnce(Arc<F>, Header) -> Fut,
+		Fut: 'static + Send + Future<Output = Result<T, ClientError>>,
+	{
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let remote_blockchain = self.remote_blockchain.clone();
+		let fetcher = self.fetcher.clone();
+		let result = async move {
+			let header = future_header(&*remote_blockchain, &*fetcher, BlockId::Hash(at))
+				.await?
+				.ok_or_else(|| ClientError::UnknownBlock(at.to_string()))?;
+
+			evaluate(fetcher, header).await
+		};
+
+		Box::new(
+			result
+				.map_err(move |e| Error {
+					code: ErrorCode::ServerError(RUNTIME_ERROR),
+					message: format!("Unable to query {}.", what),
+					data: Some(format!("{:?}", e).into()),
+				})
+				.boxed()
+				.compat(),
+		)
+	}
+}
+impl<C, F, R> Clone for RemoteState<C, F, R> {
+	fn clone(&self) -> Self {
+		Self {
+			client: self.client.clone(),
+			remote_blockchain: self.remote_blockchain.clone(),
+			fetcher: self.fetcher.clone(),
+			_runtime: PhantomData,
+		}
+	}
+}
+impl<C, F, R> LightBalancesApi<Hash, AccountId, BalancesRuntimeDispatchInfo<Balance>>
+	for RemoteState<C, F, R>
+where
+	C: 'static + Send + Sync + HeaderBackend<Block>,
+	F: 'static + Fetcher<Block>,
+	R: LightRuntime,
+	R::AccountData: BalanceInfo<Balance, RingInstance> + BalanceInfo<Balance, KtonInstance>,
+{
+	fn usable_balance(
+		&self,
+		instance: u8,
+		who: AccountId,
+		at: Option<Hash>,
+	) -> BoxFuture<BalancesRuntimeDispatchInfo<Balance>> {
+		let account_key = frame_system::Account::<R>::hashed_key_for(&who);
+		let (locks_key, free): (_, fn(&R::AccountData) -> Balance) = match instance {
+			0 => (
+				darwinia_balances::Locks::<R, RingInstance>::hashed_key_for(&who),
+				<R::AccountData as BalanceInfo<Balance, RingInstance>>::free,
+			),
+			1 => (
+				darwinia_balances::Locks::<R, KtonInstance>::hashed_key_for(&who),
+				<R::AccountData as BalanceInfo<Balance, KtonInstance>>::free,
+			),
+			// As the runtime handler
+			_ => {
+				return Box::new(jsonrpc_core::futures::future::ok(
+					BalancesRuntimeDispatchInfo { usable_balance: 0 },
+				))
+			}
+		};
+
+		self.evaluate(at, "usable balance", move |fetcher, header| async move {
+			let values = read(
+				&*fetcher,
+				&header,
+				vec![account_key.clone(), locks_key.clone()],
+			)
+			.await?;
+			let account = decode::<AccountInfo<R::Index, R::AccountData>>(&values, &account_key)?
+				.unwrap_or_default();
+			let locks =
+				decode::<Vec<BalanceLock<Balance, NativeBlockNumber>>>(&values, &locks_key)?
+					.unwrap_or_default();
+
+			Ok(BalancesRuntimeDispatchInfo {
+				usable_balance: free(&account.data)
+					.saturating_sub(misc_frozen(&locks, header.number)),
+			})
+		})
+	}
+}
+impl<C, F, R> LightHeaderMMRApi<Hash, HeaderMMRRuntimeDispatchInfo<Hash>> for RemoteState<C, F, R>
+where
+	C: 'static + Send + Sync + HeaderBackend<Block>,
+	F: 'static + Fetcher<Block>,
+	R: LightRuntime,
+{
+	fn gen_proof(
+		&self,
+		block_number_of_member_leaf: u64,
+		block_number_of_last_leaf: u64,
+		at: Option<Hash>,
+	) -> BoxFuture<HeaderMMRRuntimeDispatchInfo<Hash>> {
+		let (member, last) = (block_number_of_member_leaf, block_number_of_last_leaf);
+
+		self.evaluate(at, "header mmr proof", move |fetcher, header| async move {
+			// As the runtime handler, the MMR of a block ends with the leaf of its parent
+			if member > last || last >= header.number as u64 {
+				return Ok(HeaderMMRRuntimeDispatchInfo {
+					mmr_size: 0,
+					proof: Proof(vec![]),
+				});
+			}
+
+			let mmr_size = leaf_index_to_mmr_size(last);
+			let position = leaf_index_to_pos(member);
+			let keys = node_positions(mmr_size, position)
+				.map_err(mmr_error)?
+				.into_iter()
+				.map(header_mmr::node_key)
+				.collect();
+			let values = read(&*fetcher, &header, keys).await?;
+			let proof = MMR::<_, HeaderMMRMerge, _>::new(mmr_size, RemoteNodeStore(&values))
+				.gen_proof(vec![position])
+				.map_err(mmr_error)?;
+
+			Ok(HeaderMMRRuntimeDispatchInfo {
+				mmr_size,
+				proof: Proof(proof.proof_items().to_vec()),
+			})
+		})
+	}
+}
+impl<C, F, R> LightStakingApi<Hash, AccountId, StakingRuntimeDispatchInfo<Power>>
+	for RemoteState<C, F, R>
+where
+	C: 'static + Send + Sync + HeaderBackend<Block>,
+	F: 'static + Fetcher<Block>,
+	R: LightRuntime,
+{
+	fn power_of(
+		&self,
+		who: AccountId,
+		at: Option<Hash>,
+	) -> BoxFuture<StakingRuntimeDispatchInfo<Power>> {
+		let bonded_key = darwinia_staking::Bonded::<R>::hashed_key_for(&who);
+		let ring_pool_key = darwinia_staking::RingPool::<R>::hashed_key().to_vec();
+		let kton_pool_key = darwinia_staking::KtonPool::<R>::hashed_key().to_vec();
+		let total_power = <R as darwinia_staking::Config>::TotalPower::get();
+
+		self.evaluate(at, "power", move |fetcher, header| async move {
+			let values = read(&*fetcher, &header, vec![bonded_key.clone()]).await?;
+			let controller = match decode::<AccountId>(&values, &bonded_key)? {
+				Some(controller) => controller,
+				// As the runtime handler, not a stash
+				None => return Ok(StakingRuntimeDispatchInfo { power: 0 }),
+			};
+			let ledger_key = darwinia_staking::Ledger::<R>::hashed_key_for(&controller);
+			let values = read(
+				&*fetcher,
+				&header,
+				vec![
+					ledger_key.clone(),
+					ring_pool_key.clone(),
+					kton_pool_key.clone(),
+				],
+			)
+			.await?;
+			let ring_pool = decode::<Balance>(&values, &ring_pool_key)?.unwrap_or_default();
+			let kton_pool = decode::<Balance>(&values, &kton_pool_key)?.unwrap_or_default();
+			let power_of = |active: Balance, pool: Balance| {
+				(Perquintill::from_rational_approximation(active, pool.max(1))
+					* (total_power as Balance / 2)) as Power
+			};
+			let power = decode::<StakingLedger<AccountId, Balance, Balance, NativeBlockNumber>>(
+				&values,
+				&ledger_key,
+			)?
+			.map(|ledger| {
+				power_of(ledger.active_ring, ring_pool) + power_of(ledger.active_kton, kton_pool)
+			})
+			.unwrap_or(0);
+
+			Ok(StakingRuntimeDispatchInfo { power })
+		})
+	}
+}
+
+/// Read `keys` at the block of `header`, checked against the storage proof of the remote full node.
+async fn read<F>(
+	fetcher: &F,
+	header: &Header,
+	keys: Vec<Vec<u8>>,
+) -> Result<StorageValues, ClientError>
+where
+	F: Fetcher<Block>,
+{
+	fetcher
+		.remote_read(RemoteReadRequest {
+			block: header.hash(),
+			header: header.clone(),
+			keys,
+			retry_count: None,
+		})
+		.await
+}
+
+/// Decode the value of `key`, `None` if it is absent.
+fn decode<T>(values: &StorageValues, key: &[u8]) -> Result<Option<T>, ClientError>
+where
+	T: Decode,
+{
+	values
+		.get(key)
+		.cloned()
+		.flatten()
+		.map(|value| T::decode(&mut &*value))
+		.transpose()
+		.map_err(|e| {
+			ClientError::Msg(format!(
+				"Failed to decode the value of 0x{}: {}",
+				HexDisplay::from(&key),
+				e
+			))
+		})
+}
+
+/// The balance frozen at `now` by the locks for `Misc`, as `darwinia_balances`.
+fn misc_frozen(
+	locks: &[BalanceLock<Balance, NativeBlockNumber>],
+	now: NativeBlockNumber,
+) -> Balance {
+	locks
+		.iter()
+		.filter(|lock| matches!(lock.lock_reasons, LockReasons::All | LockReasons::Misc))
+		.map(|lock| match &lock.lock_for {
+			LockFor::Common { amount } => *amount,
+			LockFor::Staking(staking_lock) => staking_lock.locked_amount(now),
+		})
+		.max()
+		.unwrap_or(0)
+}
+
+/// The positions of the nodes read to prove the leaf at `position`, in the MMR of `mmr_size`.
+fn node_positions(mmr_size: u64, position: u64) -> mmr::Result<BTreeSet<u64>> {
+	let positions = RefCell::new(BTreeSet::new());
+
+	MMR::<_, HeaderMMRMerge, _>::new(mmr_size, PositionRecorder(&positions))
+		.gen_proof(vec![position])?;
+
+	Ok(positions.into_inner())
+}
+
+fn mmr_error(e: mmr::Error) -> ClientError {
+	ClientError::Msg(format!("{:?}", e))
+}
+
+/// An `MMRStore` recording the positions read, which only depend on the shape of the MMR.
+struct PositionRecorder<'a>(&'a RefCell<BTreeSet<u64>>);
+impl<'a> MMRStore<Hash> for PositionRecorder<'a> {
+	fn get_elem(&self, position: u64) -> mmr::Result<Option<Hash>> {
+		self.0.borrow_mut().insert(position);
+
+		Ok(Some(Default::default()))
+	}
+
+	fn append(&mut self, _: u64, _: Vec<Hash>) -> mmr::Result<()> {
+		Err(mmr::Error::StoreError(
+			"The Header MMR store is read only".into(),
+		))
+	}
+}
+
+/// A read only `MMRStore` of the Header MMR nodes read on a remote full node.
+struct RemoteNodeStore<'a>(&'a StorageValues);
+impl<'a> MMRStore<Hash> for RemoteNodeStore<'a> {
+	fn get_elem(&self, position: u64) -> mmr::Result<Option<Hash>> {
+		self.0
+			.get(&header_mmr::node_key(position))
+			.cloned()
+			.flatten()
+			.map(|data| header_mmr::decode_node(&data))
+			.transpose()
+	}
+
+	fn append(&mut self, _: u64, _: Vec<Hash>) -> mmr::Result<()> {
+		Err(mmr::Error::StoreError(
+			"The Header MMR store is read only".into(),
+		))
+	}
+}
+
+/// `LightEthApi` over a remote full node, submitting the transactions to the light pool.
+pub struct LightEth<C, F, P, CT> {
+	remote_runtime_api: RemoteRuntimeApi<C, F>,
+	pool: Arc<P>,
+	converter: CT,
+}
+impl<C, F, P, CT> LightEthApi for LightEth<C, F, P, CT>
+where
+	C: 'static + Send + Sync + HeaderBackend<Block>,
+	F: 'static + Fetcher<Block>,
+	P: 'static + Send + Sync + TransactionPool<Block = Block>,
+	CT: 'static + Send + Sync + ConvertTransaction<OpaqueExtrinsic>,
+{
+	fn chain_id(&self) -> BoxFuture<U256> {
+		Box::new(
+			self.remote_runtime_api
+				.call::<u64>("EthereumRuntimeRPCApi_chain_id", vec![], "chain id")
+				.map(U256::from),
+		)
+	}
+
+	fn block_number(&self) -> RpcResult<U256> {
+		Ok(self.remote_runtime_api.client.info().best_number.into())
+	}
+
+	fn balance(&self, address: H160, number: Option<BlockNumber>) -> BoxFuture<U256> {
+		self.account_basic(address, number, |account| account.balance)
+	}
+
+	fn transaction_count(&self, address: H160, number: Option<BlockNumber>) -> BoxFuture<U256> {
+		self.account_basic(address, number, |account| account.nonce)
+	}
+
+	fn code_at(&self, address: H160, number: Option<BlockNumber>) -> BoxFuture<Bytes> {
+		let at = try_future!(self.remote_runtime_api.block_hash(number));
+
+		Box::new(
+			self.remote_runtime_api
+				.call_at::<Vec<u8>>(
+					at,
+					"EthereumRuntimeRPCApi_account_code_at",
+					address.encode(),
+					"code",
+				)
+				.map(Bytes),
+		)
+	}
+
+	fn storage_at(
+		&self,
+		address: H160,
+		index: U256,
+		number: Option<BlockNumber>,
+	) -> BoxFuture<H256> {
+		let at = try_future!(self.remote_runtime_api.block_hash(number));
+
+		self.remote_runtime_api.call_at(
+			at,
+			"EthereumRuntimeRPCApi_storage_at",
+			(address, index).encode(),
+			"storage",
+		)
+	}
+
+	fn send_raw_transaction(&self, bytes: Bytes) -> BoxFuture<H256> {
+		let transaction = try_future!(rlp::decode::<Transaction>(&bytes.0)
+			.map_err(|_| internal_err("decode transaction failed")));
+		let transaction_hash = H256::from(keccak_256(&rlp::encode(&transaction)));
+		let best_hash = self.remote_runtime_api.client.info().best_hash;
+		let submission = self.pool.submit_one(
+			&BlockId::Hash(best_hash),
+			TransactionSource::Local,
+			self.converter.convert_transaction(transaction),
+		);
+
+		Box::new(
+			submission
+				.map_ok(move |_| transaction_hash)
+				.map_err(|e| internal_err(format!("submit transaction to pool failed: {:?}", e)))
+				.compat(),
+		)
+	}
+}
+impl<C, F, P, CT> LightEth<C, F, P, CT>
+where
+	C: 'static + Send + Sync + HeaderBackend<Block>,
+	F: 'static + Fetcher<Block>,
+{
+	fn account_basic(
+		&self,
+		address: H160,
+		number: Option<BlockNumber>,
+		field: fn(EVMAccount) -> U256,
+	) -> BoxFuture<U256> {
+		let at = try_future!(self.remote_runtime_api.block_hash(number));
+
+		Box::new(
+			self.remote_runtime_api
+				.call_at::<EVMAccount>(
+					at,
+					"EthereumRuntimeRPCApi_account_basic",
+					address.encode(),
+					"account",
+				)
+				.map(field),
+		)
+	}
+}
+
+/// Register the Balances, Staking and HeaderMMR RPCs of a light client of the runtime `R`.
+pub fn extend_with_light<R, C, F>(
+	io: &mut RpcExtension,
+	client: Arc<C>,
+	remote_blockchain: Arc<dyn RemoteBlockchain<Block>>,
+	fetcher: Arc<F>,
+) where
+	R: LightRuntime,
+	R::AccountData: BalanceInfo<Balance, RingInstance> + BalanceInfo<Balance, KtonInstance>,
+	C: 'static + Send + Sync + HeaderBackend<Block>,
+	F: 'static + Fetcher<Block>,
+{
+	let remote_state = RemoteState::<C, F, R>::new(client, remote_blockchain, fetcher);
+
+	io.extend_with(LightBalancesApi::to_delegate(remote_state.clone()));
+	io.extend_with(LightHeaderMMRApi::to_delegate(remote_state.clone()));
+	io.extend_with(LightStakingApi::to_delegate(remote_state));
+}
+
+/// Register `LightEthApi` on a light client of a runtime with the DVM.
+///
+/// `converter` wraps the raw Ethereum transactions into the extrinsics of the runtime.
+pub fn extend_with_light_dvm<C, F, P, CT>(
+	io: &mut RpcExtension,
+	client: Arc<C>,
+	remote_blockchain: Arc<dyn RemoteBlockchain<Block>>,
+	fetcher: Arc<F>,
+	pool: Arc<P>,
+	converter: CT,
+) where
+	C: 'static + Send + Sync + HeaderBackend<Block>,
+	F: 'static + Fetcher<Block>,
+	P: 'static + Send + Sync + TransactionPool<Block = Block>,
+	CT: 'static + Send + Sync + ConvertTransaction<OpaqueExtrinsic>,
+{
+	io.extend_with(LightEthApi::to_delegate(LightEth {
+		remote_runtime_api: RemoteRuntimeApi::new(client, remote_blockchain, fetcher),
+		pool,
+		converter,
+	}));
+}
@@ -0,0 +1,59 @@
+// This file is part of Darwinia.
+//
+// Copyright (C) 2018-2021 Darwinia Network
+// SPDX-License-Identifier: GPL-3.0
+//
+// Darwinia is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// Darwinia is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with Darwinia. If not, see <https://www.gnu.org/licenses/>.
+
+//! Write the Header MMR nodes through offchain indexing, for the node-side MMR store.
+//!
+//! `darwinia_header_mmr` pushes the parent hash on finalize, so the leaf of block `n - 2` is the
+//! last one complete at the initialize of block `n`, and its nodes are indexed there.
+
+// --- crates ---
+use codec::Encode;
+// --- substrate ---
+use frame_support::{decl_module, traits::Get, weights::Weight};
+use sp_runtime::traits::UniqueSaturatedInto;
+// --- darwinia ---
+use darwinia_primitives::header_mmr;
+
+/// The Header MMR indexing needs nothing more than the Header MMR.
+pub trait Config: darwinia_header_mmr::Config {}
+
+decl_module! {
+	pub struct Module<T: Config> for enum Call where origin: T::Origin {
+		fn on_initialize(block_number: T::BlockNumber) -> Weight {
+			let block_number: u64 = block_number.unique_saturated_into();
+
+			if block_number < 2 {
+				return 0;
+			}
+
+			let leaf_index = block_number - 2;
+			// The leaf, then its parents up to the peak
+			let positions =
+				mmr::leaf_index_to_pos(leaf_index)..mmr::leaf_index_to_mmr_size(leaf_index);
+			let reads = positions.end - positions.start;
+
+			for position in positions {
+				if let Some(node) = <darwinia_header_mmr::Module<T>>::mmr_node_list(position) {
+					sp_io::offchain_index::set(&header_mmr::offchain_key(position), &node.encode());
+				}
+			}
+
+			T::DbWeight::get().reads(reads)
+		}
+	}
+}
@@ -24,6 +24,8 @@
 pub mod impls;
 pub use impls::*;
 
+pub mod header_mmr_indexing;
+
 pub use frame_support::weights::constants::{
 	BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight,
 };
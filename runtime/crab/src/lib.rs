@@ -66,6 +66,7 @@ use frame_support::{
 	weights::Weight,
 };
 use pallet_grandpa::{fg_primitives, AuthorityList as GrandpaAuthorityList};
+use pallet_im_online::sr25519::AuthorityId as ImOnlineId;
 use pallet_transaction_payment::FeeDetails;
 use pallet_transaction_payment_rpc_runtime_api::RuntimeDispatchInfo as TransactionPaymentRuntimeDispatchInfo;
 use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
@@ -88,7 +89,10 @@ use sp_version::RuntimeVersion;
 use darwinia_balances_rpc_runtime_api::RuntimeDispatchInfo as BalancesRuntimeDispatchInfo;
 use darwinia_evm::{Account as EVMAccount, FeeCalculator, Runner};
 use darwinia_header_mmr_rpc_runtime_api::RuntimeDispatchInfo as HeaderMMRRuntimeDispatchInfo;
-use darwinia_primitives::*;
+use darwinia_primitives::{
+	metrics::{EthereumBridgeMetrics, StakingMetrics, ValidatorMetrics},
+	*,
+};
 use darwinia_runtime_common::*;
 use darwinia_staking_rpc_runtime_api::RuntimeDispatchInfo as StakingRuntimeDispatchInfo;
 use dvm_rpc_runtime_api::TransactionStatus;
@@ -140,7 +144,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: sp_runtime::create_runtime_str!("Crab"),
 	impl_name: sp_runtime::create_runtime_str!("Darwinia Crab"),
 	authoring_version: 0,
-	spec_version: 43,
+	spec_version: 45,
 	impl_version: 0,
 	#[cfg(not(feature = "disable-runtime-api"))]
 	apis: RUNTIME_API_VERSIONS,
@@ -189,6 +193,7 @@ frame_support::construct_runtime! {
 		ImOnline: pallet_im_online::{Pallet, Call, Storage, Config<T>, Event<T>, ValidateUnsigned} = 12,
 		AuthorityDiscovery: pallet_authority_discovery::{Pallet, Call, Config} = 13,
 		HeaderMMR: darwinia_header_mmr::{Pallet, Call, Storage} = 31,
+		HeaderMMRIndexing: darwinia_runtime_common::header_mmr_indexing::{Pallet} = 41,
 
 		// Governance stuff; uncallable initially.
 		Council: pallet_collective::<Instance0>::{Pallet, Call, Storage, Origin<T>, Config<T>, Event<T>} = 14,
@@ -498,6 +503,40 @@ sp_api::impl_runtime_apis! {
 		}
 	}
 
+	impl darwinia_primitives::metrics::ChainMetricsApi<Block> for Runtime {
+		fn staking() -> StakingMetrics {
+			StakingMetrics {
+				active_era: Staking::active_era().map(|era| era.index),
+				validator_count: Session::validators().len() as _,
+			}
+		}
+
+		fn validator(im_online_key: sp_core::sr25519::Public) -> Option<ValidatorMetrics> {
+			let im_online_key = ImOnlineId::from(im_online_key);
+			// The im-online keys are indexed as the validators of the session
+			let index = ImOnline::keys().iter().position(|key| key == &im_online_key)?;
+			let stash = Session::validators().get(index)?.clone();
+			let era_points = Staking::active_era()
+				.and_then(|era| {
+					Staking::eras_reward_points(era.index)
+						.individual
+						.get(&stash)
+						.copied()
+				})
+				.unwrap_or_default();
+
+			Some(ValidatorMetrics {
+				era_points,
+				is_online: ImOnline::is_online(index as _),
+			})
+		}
+
+		fn ethereum_bridge() -> Option<EthereumBridgeMetrics> {
+			// The Ethereum bridge is not deployed on Crab
+			None
+		}
+	}
+
 	impl dvm_rpc_runtime_api::EthereumRuntimeRPCApi<Block> for Runtime {
 		fn chain_id() -> u64 {
 			<Runtime as darwinia_evm::Config>::ChainId::get()
@@ -619,6 +658,58 @@ sp_api::impl_runtime_apis! {
 			Ok((weight, RuntimeBlockWeights::get().max_block))
 		}
 	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	impl frame_benchmarking::Benchmark<Block> for Runtime {
+		fn dispatch_benchmark(
+			config: frame_benchmarking::BenchmarkConfig
+		) -> Result<Vec<frame_benchmarking::BenchmarkBatch>, sp_runtime::RuntimeString> {
+			// --- substrate ---
+			use frame_benchmarking::{add_benchmark, BenchmarkBatch, Benchmarking, TrackedStorageKey};
+			use frame_system_benchmarking::Module as SystemBench;
+
+			impl frame_system_benchmarking::Config for Runtime {}
+
+			let whitelist: Vec<TrackedStorageKey> = [
+				// Block Number
+				"0x26aa394eea5630e07c48ae0c9558cef702a5c1b19ab7a04f536c519aca4983ac",
+				// Execution Phase
+				"0x26aa394eea5630e07c48ae0c9558cef7ff553b5a9862a516939d82b3d3d8661a",
+				// Event Count
+				"0x26aa394eea5630e07c48ae0c9558cef70a98fdbe9ce6c55837576c60c7af3850",
+				// System Events
+				"0x26aa394eea5630e07c48ae0c9558cef780d41e5e16056765bc8461851072c9d7",
+			]
+			.iter()
+			.map(|key| array_bytes::hex2bytes_unchecked(key).into())
+			.collect();
+			let mut batches = Vec::<BenchmarkBatch>::new();
+			let params = (&config, &whitelist);
+
+			add_benchmark!(params, batches, frame_system, SystemBench::<Runtime>);
+			add_benchmark!(params, batches, darwinia_balances, Balances);
+			add_benchmark!(params, batches, darwinia_crab_issuing, CrabIssuing);
+			add_benchmark!(params, batches, darwinia_democracy, Democracy);
+			add_benchmark!(params, batches, darwinia_elections_phragmen, ElectionsPhragmen);
+			add_benchmark!(params, batches, darwinia_staking, Staking);
+			add_benchmark!(params, batches, darwinia_treasury, Treasury);
+			add_benchmark!(params, batches, pallet_collective, Council);
+			add_benchmark!(params, batches, pallet_identity, Identity);
+			add_benchmark!(params, batches, pallet_im_online, ImOnline);
+			add_benchmark!(params, batches, pallet_indices, Indices);
+			add_benchmark!(params, batches, pallet_multisig, Multisig);
+			add_benchmark!(params, batches, pallet_proxy, Proxy);
+			add_benchmark!(params, batches, pallet_scheduler, Scheduler);
+			add_benchmark!(params, batches, pallet_timestamp, Timestamp);
+			add_benchmark!(params, batches, pallet_utility, Utility);
+
+			if batches.is_empty() {
+				return Err("Benchmark not found for this pallet.".into());
+			}
+
+			Ok(batches)
+		}
+	}
 }
 
 pub struct TransactionConverter;
@@ -650,24 +741,24 @@ pub struct CustomOnRuntimeUpgrade;
 impl OnRuntimeUpgrade for CustomOnRuntimeUpgrade {
 	#[cfg(feature = "try-runtime")]
 	fn pre_upgrade() -> Result<(), &'static str> {
+		darwinia_staking::migrations::v6::pre_migrate::<Runtime>()
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade() -> Result<(), &'static str> {
 		// --- substrate ---
-		// use frame_support::migration::*;
 		use frame_support::storage::unhashed;
 		// --- darwinia ---
 		use dp_storage::PALLET_ETHEREUM_SCHEMA;
 		use dvm_ethereum::EthereumStorageSchema;
 
-		assert!(unhashed::get::<EthereumStorageSchema>(&PALLET_ETHEREUM_SCHEMA).is_none());
-
-		Self::on_runtime_upgrade();
-		darwinia_staking::migrations::v6::pre_migrate::<Runtime>()?;
-
-		assert_eq!(
-			unhashed::get::<EthereumStorageSchema>(&PALLET_ETHEREUM_SCHEMA),
-			Some(EthereumStorageSchema::V1),
-		);
-
-		Ok(())
+		if unhashed::get::<EthereumStorageSchema>(&PALLET_ETHEREUM_SCHEMA)
+			== Some(EthereumStorageSchema::V1)
+		{
+			Ok(())
+		} else {
+			Err("The Ethereum storage schema is not set to V1")
+		}
 	}
 
 	fn on_runtime_upgrade() -> Weight {
@@ -46,6 +46,9 @@ pub use authority_discovery::*;
 pub mod header_mmr;
 pub use header_mmr::*;
 
+pub mod header_mmr_indexing;
+pub use header_mmr_indexing::*;
+
 pub mod democracy;
 pub use democracy::*;
 
@@ -101,30 +101,31 @@ pub use darwinia_ethereum_relay::DagsMerkleRootsLoader;
 pub use darwinia_staking::{Forcing, StakerStatus};
 
 // --- crates ---
-use codec::Encode;
+use codec::{Decode, Encode};
 // --- substrate ---
 use frame_support::{
-	traits::{KeyOwnerProofSystem, OnRuntimeUpgrade, Randomness},
+	traits::{Currency, KeyOwnerProofSystem, OnRuntimeUpgrade, Randomness},
 	weights::Weight,
 };
 use pallet_grandpa::{
 	fg_primitives, AuthorityId as GrandpaId, AuthorityList as GrandpaAuthorityList,
 };
+use pallet_im_online::sr25519::AuthorityId as ImOnlineId;
 use pallet_session::historical as pallet_session_historical;
 use pallet_transaction_payment::FeeDetails;
 use pallet_transaction_payment_rpc_runtime_api::RuntimeDispatchInfo as TransactionPaymentRuntimeDispatchInfo;
 use sp_api::impl_runtime_apis;
 use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
 use sp_consensus_babe::{AllowedSlots, BabeEpochConfiguration};
-use sp_core::OpaqueMetadata;
+use sp_core::{OpaqueMetadata, H160, H256, U256};
 use sp_runtime::{
 	generic,
 	traits::{
-		AccountIdLookup, BlakeTwo256, Block as BlockT, ConvertInto, Extrinsic as ExtrinsicT,
-		NumberFor, SaturatedConversion, StaticLookup, Verify,
+		AccountIdConversion, AccountIdLookup, BlakeTwo256, Block as BlockT, ConvertInto,
+		Extrinsic as ExtrinsicT, NumberFor, SaturatedConversion, StaticLookup, Verify,
 	},
 	transaction_validity::{TransactionSource, TransactionValidity},
-	ApplyExtrinsicResult, ModuleId, MultiAddress,
+	ApplyExtrinsicResult, ModuleId, MultiAddress, OpaqueExtrinsic,
 };
 use sp_std::prelude::*;
 #[cfg(any(feature = "std", test))]
@@ -132,10 +133,15 @@ use sp_version::NativeVersion;
 use sp_version::RuntimeVersion;
 // --- darwinia ---
 use darwinia_balances_rpc_runtime_api::RuntimeDispatchInfo as BalancesRuntimeDispatchInfo;
+use darwinia_evm::{Account as EVMAccount, FeeCalculator, Runner};
 use darwinia_header_mmr_rpc_runtime_api::RuntimeDispatchInfo as HeaderMMRRuntimeDispatchInfo;
-use darwinia_primitives::*;
+use darwinia_primitives::{
+	metrics::{EthereumBridgeMetrics, StakingMetrics, ValidatorMetrics},
+	*,
+};
 use darwinia_runtime_common::*;
 use darwinia_staking_rpc_runtime_api::RuntimeDispatchInfo as StakingRuntimeDispatchInfo;
+use dvm_rpc_runtime_api::TransactionStatus;
 
 /// The address format for describing accounts.
 pub type Address = MultiAddress<AccountId, ()>;
@@ -187,7 +193,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: sp_runtime::create_runtime_str!("Darwinia"),
 	impl_name: sp_runtime::create_runtime_str!("Darwinia"),
 	authoring_version: 0,
-	spec_version: 24,
+	spec_version: 27,
 	impl_version: 0,
 	#[cfg(not(feature = "disable-runtime-api"))]
 	apis: RUNTIME_API_VERSIONS,
@@ -235,6 +241,7 @@ frame_support::construct_runtime! {
 		ImOnline: pallet_im_online::{Pallet, Call, Storage, Config<T>, Event<T>, ValidateUnsigned} = 14,
 		AuthorityDiscovery: pallet_authority_discovery::{Pallet, Call, Config} = 15,
 		HeaderMMR: darwinia_header_mmr::{Pallet, Call, Storage} = 35,
+		HeaderMMRIndexing: darwinia_runtime_common::header_mmr_indexing::{Pallet} = 41,
 
 		// Governance stuff; uncallable initially.
 		Democracy: darwinia_democracy::{Pallet, Call, Storage, Config, Event<T>} = 37,
@@ -281,6 +288,10 @@ frame_support::construct_runtime! {
 
 		// Tron bridge.
 		TronBacking: darwinia_tron_backing::{Pallet, Storage, Config<T>} = 34,
+
+		// DVM.
+		EVM: darwinia_evm::{Pallet, Call, Storage, Config, Event<T>} = 39,
+		Ethereum: dvm_ethereum::{Pallet, Call, Storage, Event, ValidateUnsigned} = 40,
 	}
 }
 
@@ -544,6 +555,160 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl darwinia_primitives::metrics::ChainMetricsApi<Block> for Runtime {
+		fn staking() -> StakingMetrics {
+			StakingMetrics {
+				active_era: Staking::active_era().map(|era| era.index),
+				validator_count: Session::validators().len() as _,
+			}
+		}
+
+		fn validator(im_online_key: sp_core::sr25519::Public) -> Option<ValidatorMetrics> {
+			let im_online_key = ImOnlineId::from(im_online_key);
+			// The im-online keys are indexed as the validators of the session
+			let index = ImOnline::keys().iter().position(|key| key == &im_online_key)?;
+			let stash = Session::validators().get(index)?.clone();
+			let era_points = Staking::active_era()
+				.and_then(|era| {
+					Staking::eras_reward_points(era.index)
+						.individual
+						.get(&stash)
+						.copied()
+				})
+				.unwrap_or_default();
+
+			Some(ValidatorMetrics {
+				era_points,
+				is_online: ImOnline::is_online(index as _),
+			})
+		}
+
+		fn ethereum_bridge() -> Option<EthereumBridgeMetrics> {
+			let backing_account: AccountId = EthereumBackingModuleId::get().into_account();
+
+			Some(EthereumBridgeMetrics {
+				pending_relay_header_parcels: EthereumRelay::pending_relay_header_parcels().len()
+					as _,
+				mmr_roots_to_sign: EthereumRelayAuthorities::mmr_root_to_sign_keys().len() as _,
+				locked_ring: <Ring as Currency<AccountId>>::free_balance(&backing_account),
+				locked_kton: <Kton as Currency<AccountId>>::free_balance(&backing_account),
+			})
+		}
+	}
+
+	impl dvm_rpc_runtime_api::EthereumRuntimeRPCApi<Block> for Runtime {
+		fn chain_id() -> u64 {
+			<Runtime as darwinia_evm::Config>::ChainId::get()
+		}
+
+		fn gas_price() -> U256 {
+			<Runtime as darwinia_evm::Config>::FeeCalculator::min_gas_price()
+		}
+
+		fn account_basic(address: H160) -> EVMAccount {
+			use darwinia_evm::AccountBasic;
+
+			<Runtime as darwinia_evm::Config>::RingAccountBasic::account_basic(&address)
+		}
+
+		fn account_code_at(address: H160) -> Vec<u8> {
+			darwinia_evm::Module::<Runtime>::account_codes(address)
+		}
+
+		fn author() -> H160 {
+			<dvm_ethereum::Module<Runtime>>::find_author()
+		}
+
+		fn storage_at(address: H160, index: U256) -> H256 {
+			let mut tmp = [0u8; 32];
+			index.to_big_endian(&mut tmp);
+			darwinia_evm::Module::<Runtime>::account_storages(address, H256::from_slice(&tmp[..]))
+		}
+
+		fn call(
+			from: H160,
+			to: H160,
+			data: Vec<u8>,
+			value: U256,
+			gas_limit: U256,
+			gas_price: Option<U256>,
+			nonce: Option<U256>,
+			estimate: bool,
+		) -> Result<darwinia_evm::CallInfo, sp_runtime::DispatchError> {
+			let config = if estimate {
+				let mut config = <Runtime as darwinia_evm::Config>::config().clone();
+				config.estimate = true;
+				Some(config)
+			} else {
+				None
+			};
+
+			<Runtime as darwinia_evm::Config>::Runner::call(
+				from,
+				to,
+				data,
+				value,
+				gas_limit.low_u64(),
+				gas_price,
+				nonce,
+				config.as_ref().unwrap_or(<Runtime as darwinia_evm::Config>::config()),
+			).map_err(|err| err.into())
+		}
+
+		fn create(
+			from: H160,
+			data: Vec<u8>,
+			value: U256,
+			gas_limit: U256,
+			gas_price: Option<U256>,
+			nonce: Option<U256>,
+			estimate: bool,
+		) -> Result<darwinia_evm::CreateInfo, sp_runtime::DispatchError> {
+			let config = if estimate {
+				let mut config = <Runtime as darwinia_evm::Config>::config().clone();
+				config.estimate = true;
+				Some(config)
+			} else {
+				None
+			};
+
+			<Runtime as darwinia_evm::Config>::Runner::create(
+				from,
+				data,
+				value,
+				gas_limit.low_u64(),
+				gas_price,
+				nonce,
+				config.as_ref().unwrap_or(<Runtime as darwinia_evm::Config>::config()),
+			).map_err(|err| err.into())
+		}
+
+
+		fn current_transaction_statuses() -> Option<Vec<TransactionStatus>> {
+			Ethereum::current_transaction_statuses()
+		}
+
+		fn current_block() -> Option<dvm_ethereum::Block> {
+			Ethereum::current_block()
+		}
+
+		fn current_receipts() -> Option<Vec<dvm_ethereum::Receipt>> {
+			Ethereum::current_receipts()
+		}
+
+		fn current_all() -> (
+			Option<dvm_ethereum::Block>,
+			Option<Vec<dvm_ethereum::Receipt>>,
+			Option<Vec<TransactionStatus>>
+		) {
+			(
+				Ethereum::current_block(),
+				Ethereum::current_receipts(),
+				Ethereum::current_transaction_statuses()
+			)
+		}
+	}
+
 	#[cfg(feature = "try-runtime")]
 	impl frame_try_runtime::TryRuntime<Block> for Runtime {
 		fn on_runtime_upgrade() -> Result<(Weight, Weight), sp_runtime::RuntimeString> {
@@ -552,6 +717,82 @@ impl_runtime_apis! {
 			Ok((weight, RuntimeBlockWeights::get().max_block))
 		}
 	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	impl frame_benchmarking::Benchmark<Block> for Runtime {
+		fn dispatch_benchmark(
+			config: frame_benchmarking::BenchmarkConfig
+		) -> Result<Vec<frame_benchmarking::BenchmarkBatch>, sp_runtime::RuntimeString> {
+			// --- substrate ---
+			use frame_benchmarking::{add_benchmark, BenchmarkBatch, Benchmarking, TrackedStorageKey};
+			use frame_system_benchmarking::Module as SystemBench;
+
+			impl frame_system_benchmarking::Config for Runtime {}
+
+			let whitelist: Vec<TrackedStorageKey> = [
+				// Block Number
+				"0x26aa394eea5630e07c48ae0c9558cef702a5c1b19ab7a04f536c519aca4983ac",
+				// Execution Phase
+				"0x26aa394eea5630e07c48ae0c9558cef7ff553b5a9862a516939d82b3d3d8661a",
+				// Event Count
+				"0x26aa394eea5630e07c48ae0c9558cef70a98fdbe9ce6c55837576c60c7af3850",
+				// System Events
+				"0x26aa394eea5630e07c48ae0c9558cef780d41e5e16056765bc8461851072c9d7",
+			]
+			.iter()
+			.map(|key| array_bytes::hex2bytes_unchecked(key).into())
+			.collect();
+			let mut batches = Vec::<BenchmarkBatch>::new();
+			let params = (&config, &whitelist);
+
+			add_benchmark!(params, batches, frame_system, SystemBench::<Runtime>);
+			add_benchmark!(params, batches, darwinia_balances, Balances);
+			add_benchmark!(params, batches, darwinia_crab_backing, CrabBacking);
+			add_benchmark!(params, batches, darwinia_democracy, Democracy);
+			add_benchmark!(params, batches, darwinia_elections_phragmen, ElectionsPhragmen);
+			add_benchmark!(params, batches, darwinia_ethereum_backing, EthereumBacking);
+			add_benchmark!(params, batches, darwinia_ethereum_relay, EthereumRelay);
+			add_benchmark!(params, batches, darwinia_relay_authorities, EthereumRelayAuthorities);
+			add_benchmark!(params, batches, darwinia_relayer_game, EthereumRelayerGame);
+			add_benchmark!(params, batches, darwinia_staking, Staking);
+			add_benchmark!(params, batches, darwinia_tron_backing, TronBacking);
+			add_benchmark!(params, batches, darwinia_treasury, Treasury);
+			add_benchmark!(params, batches, darwinia_vesting, Vesting);
+			add_benchmark!(params, batches, pallet_collective, Council);
+			add_benchmark!(params, batches, pallet_identity, Identity);
+			add_benchmark!(params, batches, pallet_im_online, ImOnline);
+			add_benchmark!(params, batches, pallet_multisig, Multisig);
+			add_benchmark!(params, batches, pallet_proxy, Proxy);
+			add_benchmark!(params, batches, pallet_scheduler, Scheduler);
+			add_benchmark!(params, batches, pallet_timestamp, Timestamp);
+			add_benchmark!(params, batches, pallet_utility, Utility);
+
+			if batches.is_empty() {
+				return Err("Benchmark not found for this pallet.".into());
+			}
+
+			Ok(batches)
+		}
+	}
+}
+
+pub struct TransactionConverter;
+impl dvm_rpc_runtime_api::ConvertTransaction<UncheckedExtrinsic> for TransactionConverter {
+	fn convert_transaction(&self, transaction: dvm_ethereum::Transaction) -> UncheckedExtrinsic {
+		UncheckedExtrinsic::new_unsigned(
+			<dvm_ethereum::Call<Runtime>>::transact(transaction).into(),
+		)
+	}
+}
+impl dvm_rpc_runtime_api::ConvertTransaction<OpaqueExtrinsic> for TransactionConverter {
+	fn convert_transaction(&self, transaction: dvm_ethereum::Transaction) -> OpaqueExtrinsic {
+		let extrinsic = UncheckedExtrinsic::new_unsigned(
+			<dvm_ethereum::Call<Runtime>>::transact(transaction).into(),
+		);
+		let encoded = extrinsic.encode();
+
+		OpaqueExtrinsic::decode(&mut &encoded[..]).expect("Encoded extrinsic is always valid")
+	}
 }
 
 impl pallet_babe::migrations::BabePalletPrefix for Runtime {
@@ -567,7 +808,32 @@ impl OnRuntimeUpgrade for CustomOnRuntimeUpgrade {
 		darwinia_staking::migrations::v6::pre_migrate::<Runtime>()
 	}
 
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade() -> Result<(), &'static str> {
+		// --- substrate ---
+		use frame_support::storage::unhashed;
+		// --- darwinia ---
+		use dp_storage::PALLET_ETHEREUM_SCHEMA;
+		use dvm_ethereum::EthereumStorageSchema;
+
+		if unhashed::get::<EthereumStorageSchema>(&PALLET_ETHEREUM_SCHEMA)
+			== Some(EthereumStorageSchema::V1)
+		{
+			Ok(())
+		} else {
+			Err("The Ethereum storage schema is not set to V1")
+		}
+	}
+
 	fn on_runtime_upgrade() -> Weight {
+		// --- substrate ---
+		use frame_support::storage::unhashed;
+		// --- darwinia ---
+		use dp_storage::PALLET_ETHEREUM_SCHEMA;
+		use dvm_ethereum::EthereumStorageSchema;
+
+		unhashed::put::<EthereumStorageSchema>(&PALLET_ETHEREUM_SCHEMA, &EthereumStorageSchema::V1);
+
 		pallet_babe::migrations::add_epoch_configuration::<Runtime>(BabeEpochConfiguration {
 			allowed_slots: AllowedSlots::PrimaryAndSecondaryPlainSlots,
 			..BABE_GENESIS_EPOCH_CONFIG
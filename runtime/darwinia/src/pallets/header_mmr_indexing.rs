@@ -0,0 +1,5 @@
+// --- darwinia ---
+use crate::*;
+use darwinia_runtime_common::header_mmr_indexing::Config;
+
+impl Config for Runtime {}
@@ -43,6 +43,9 @@ pub use authority_discovery::*;
 pub mod header_mmr;
 pub use header_mmr::*;
 
+pub mod header_mmr_indexing;
+pub use header_mmr_indexing::*;
+
 pub mod democracy;
 pub use democracy::*;
 
@@ -102,3 +105,9 @@ pub use relay_authorities::*;
 
 pub mod tron_backing;
 pub use tron_backing::*;
+
+pub mod evm;
+pub use evm::*;
+
+pub mod dvm;
+pub use dvm::*;